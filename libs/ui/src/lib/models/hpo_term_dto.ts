@@ -113,6 +113,8 @@ export interface MiningConcept {
   rowIndexList: number[];
   suggestedTerms: OntologyMatch[];
   miningStatus: MiningStatus;
+  /** Whether the concept describes an observed or an excluded feature (defaults to observed) */
+  clinicalStatus?: ClinicalStatus;
 }
 
 
@@ -121,6 +123,8 @@ export interface MappedTerm {
   hpoLabel: string;
   status: ClinicalStatus;
   onset: string; 
+  /** HPO ids of modifiers, e.g., HP:0012828 (Severe) */
+  modifiers?: string[];
 }
 
 export interface MinedCell {
@@ -129,4 +133,18 @@ export interface MinedCell {
   mappedTermList: MappedTerm[],
 }
 
+/** An HPO term mapped as observed by some concepts of a cell and as excluded by others */
+export interface CellStatusConflict {
+  cellText: string;
+  rowIndexList: number[];
+  hpoId: string;
+  hpoLabel: string;
+  observedIn: string[];
+  excludedIn: string[];
+  resolvedStatus: ClinicalStatus;
+}
 
+export interface CellMappingResult {
+  minedCells: MinedCell[];
+  conflicts: CellStatusConflict[];
+}
@@ -0,0 +1,328 @@
+//! Ready-to-run bundle for GPSEA and PheVal analyses
+//!
+//! The bundle has the following layout:
+//!
+//! ```text
+//! <cohort>_analysis_bundle/
+//!     phenopackets/                   phenopackets of all individuals
+//!     gpsea/cohort_config.json        gene, transcript and disease of each analysis (see [`gpsea_config`])
+//!     pheval/corpora/<cohort>/
+//!         phenopackets/               phenopackets referring to the VCF of the individual (`vcf/<id>.vcf`)
+//!         vcf/                        one VCF per individual with the SNVs that have genomic coordinates
+//! ```
+//!
+//! Structural variants have no coordinates in the templates and are not written to the VCF files; individuals
+//! without any such SNV have no VCF file (they can still be used for phenotype-only PheVal runs).
+
+use std::{fs, path::Path};
+
+use ga4ghphetools::dto::cohort_dto::{CohortData, RowData};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    cohort_export::phenopacket_collections::{cohort_id, find_phenopacket, read_phenopackets, required_cohort_id, sanitize_id},
+    util::json_fields::{required_str, str_field},
+};
+
+/// Chromosomes in VCF order
+const CHROMOSOMES: [&str; 25] = [
+    "1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11", "12", "13", "14", "15", "16", "17", "18", "19",
+    "20", "21", "22", "X", "Y", "MT",
+];
+
+
+/// Result of the export of an analysis bundle
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisBundleSummary {
+    pub bundle_dir: String,
+    pub n_phenopackets: usize,
+    pub n_vcf_files: usize,
+    pub gpsea_config: String,
+    pub pheval_corpus_dir: String,
+    pub warnings: Vec<String>,
+}
+
+/// A variant with genomic coordinates
+#[derive(Clone, Debug, PartialEq)]
+struct VcfRecord {
+    assembly: String,
+    chrom: String,
+    position: u64,
+    reference: String,
+    alternate: String,
+    id: String,
+    genotype: String,
+}
+
+/// Sort key of a chromosome (`chr1` and `1` are equivalent; unknown contigs come last)
+fn chromosome_rank(chrom: &str) -> usize {
+    let name = chrom.trim_start_matches("chr");
+    let name = if name == "M" { "MT" } else { name };
+    CHROMOSOMES.iter().position(|c| *c == name).unwrap_or(CHROMOSOMES.len())
+}
+
+/// GPSEA configuration: one analysis per disease and gene of the cohort
+pub fn gpsea_config(cohort: &CohortData, phenopacket_dir: &str) -> Value {
+    let mut analyses = Vec::new();
+    for disease in &cohort.disease_list {
+        for gene in &disease.gene_transcript_list {
+            analyses.push(json!({
+                "disease_id": disease.disease_id,
+                "disease_label": disease.disease_label,
+                "gene_symbol": gene.gene_symbol,
+                "hgnc_id": gene.hgnc_id,
+                "transcript": gene.transcript,
+            }));
+        }
+    }
+    json!({
+        "cohort_name": cohort_id(cohort),
+        "hpo_version": cohort.hpo_version,
+        "phenopacket_dir": phenopacket_dir,
+        "analyses": analyses,
+    })
+}
+
+/// The variants of a row that have genomic coordinates (HGVS and intergenic variants)
+fn vcf_records(cohort: &CohortData, row: &RowData, sex: &str) -> Vec<VcfRecord> {
+    let mut records = Vec::new();
+    for (key, count) in &row.allele_count_map {
+        // (assembly, chromosome, position, reference, alternate, genomic HGVS)
+        let variant = if let Some(v) = cohort.hgvs_variants.get(key) {
+            (v.assembly(), v.chr(), v.position(), v.ref_allele(), v.alt_allele(), v.g_hgvs())
+        } else if let Some(v) = cohort.intergenic_variants.get(key) {
+            (v.assembly(), v.chr(), v.position(), v.ref_allele(), v.alt_allele(), v.g_hgvs())
+        } else {
+            continue;
+        };
+        let (assembly, chrom, position, reference, alternate, g_hgvs) = variant;
+        if position == 0 || chrom.trim().is_empty() || reference.trim().is_empty() || alternate.trim().is_empty() {
+            continue;
+        }
+        let hemizygous = sex == "MALE" && matches!(chrom.trim().trim_start_matches("chr"), "X" | "Y");
+        let genotype = match count {
+            _ if hemizygous => "1",
+            2.. => "1/1",
+            _ => "0/1",
+        };
+        let id = match g_hgvs.trim() {
+            "" => key.clone(),
+            g_hgvs => g_hgvs.to_string(),
+        };
+        records.push(VcfRecord {
+            assembly: assembly.trim().to_string(),
+            chrom: chrom.trim().to_string(),
+            position: u64::from(position),
+            reference: reference.trim().to_string(),
+            alternate: alternate.trim().to_string(),
+            id,
+            genotype: genotype.to_string(),
+        });
+    }
+    records.sort_by_key(|r| (chromosome_rank(&r.chrom), r.position));
+    records
+}
+
+/// VCF 4.2 with one sample
+fn to_vcf(sample_id: &str, records: &[VcfRecord]) -> String {
+    let mut vcf = String::from("##fileformat=VCFv4.2\n##source=phenoboard\n");
+    if let Some(assembly) = records.iter().map(|r| &r.assembly).find(|a| !a.is_empty()) {
+        vcf.push_str(&format!("##reference={}\n", assembly));
+    }
+    let mut contigs: Vec<&str> = records.iter().map(|r| r.chrom.as_str()).collect();
+    contigs.dedup();
+    for contig in contigs {
+        vcf.push_str(&format!("##contig=<ID={}>\n", contig));
+    }
+    vcf.push_str("##FORMAT=<ID=GT,Number=1,Type=String,Description=\"Genotype\">\n");
+    vcf.push_str(&format!("#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\t{}\n", sample_id.replace(['\t', ' '], "_")));
+    for r in records {
+        vcf.push_str(&format!("{}\t{}\t{}\t{}\t{}\t.\tPASS\t.\tGT\t{}\n", r.chrom, r.position, r.id, r.reference, r.alternate, r.genotype));
+    }
+    vcf
+}
+
+/// The genome assembly in the form used by the phenopacket file attributes (e.g., `hg38` → `GRCh38`)
+fn genome_assembly(assembly: &str) -> String {
+    match assembly.to_lowercase().as_str() {
+        "hg38" | "grch38" => "GRCh38".to_string(),
+        "hg19" | "grch37" => "GRCh37".to_string(),
+        _ => assembly.to_string(),
+    }
+}
+
+fn write_json(path: &Path, json: &Value) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(json).map_err(|e| e.to_string())?;
+    fs::write(path, content).map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+fn create_dir(dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))
+}
+
+/// Write the analysis bundle of a cohort to `bundle_dir` using the phenopackets in `phenopacket_dir`.
+///
+/// An existing bundle is removed first, so that files of individuals that were removed from the cohort do not remain.
+pub fn write_analysis_bundle(cohort: &CohortData, phenopacket_dir: &Path, bundle_dir: &Path) -> Result<AnalysisBundleSummary, String> {
+    let phenopackets = read_phenopackets(phenopacket_dir)?;
+    if phenopackets.is_empty() {
+        return Err(format!("No phenopackets found in {}", phenopacket_dir.display()));
+    }
+    let cohort_name = required_cohort_id(cohort)?;
+    // the id names the files of the individual in the bundle
+    let ids = phenopackets
+        .iter()
+        .map(|p| required_str(p, "id", &format!("Phenopacket of {}", str_field(&p["subject"], "id"))))
+        .collect::<Result<Vec<String>, String>>()?;
+    if bundle_dir.exists() {
+        fs::remove_dir_all(bundle_dir).map_err(|e| format!("Could not remove previous bundle {}: {}", bundle_dir.display(), e))?;
+    }
+    let ppkt_dir = bundle_dir.join("phenopackets");
+    let gpsea_dir = bundle_dir.join("gpsea");
+    let corpus_dir = bundle_dir.join("pheval").join("corpora").join(&cohort_name);
+    let corpus_ppkt_dir = corpus_dir.join("phenopackets");
+    let vcf_dir = corpus_dir.join("vcf");
+    for dir in [&ppkt_dir, &gpsea_dir, &corpus_ppkt_dir, &vcf_dir] {
+        create_dir(dir)?;
+    }
+    let mut summary = AnalysisBundleSummary {
+        bundle_dir: bundle_dir.to_string_lossy().to_string(),
+        n_phenopackets: phenopackets.len(),
+        pheval_corpus_dir: corpus_dir.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+    for (id, phenopacket) in ids.iter().zip(&phenopackets) {
+        write_json(&ppkt_dir.join(format!("{}.json", sanitize_id(id))), phenopacket)?;
+    }
+    let gpsea_config_path = gpsea_dir.join("cohort_config.json");
+    write_json(&gpsea_config_path, &gpsea_config(cohort, "../phenopackets"))?;
+    summary.gpsea_config = gpsea_config_path.to_string_lossy().to_string();
+
+    for row in &cohort.rows {
+        let individual = &row.individual_data;
+        let Some(phenopacket) = find_phenopacket(individual, &phenopackets) else {
+            summary.warnings.push(format!("No phenopacket for {}", individual.individual_id));
+            continue;
+        };
+        let file_stem = sanitize_id(&str_field(phenopacket, "id"));
+        let mut phenopacket = phenopacket.clone();
+        let records = vcf_records(cohort, row, phenopacket["subject"]["sex"].as_str().unwrap_or_default());
+        if records.is_empty() {
+            summary.warnings.push(format!("{}: no variants with genomic coordinates, no VCF written", file_stem));
+        } else {
+            let vcf_path = vcf_dir.join(format!("{}.vcf", file_stem));
+            let sample_id = str_field(&phenopacket["subject"], "id");
+            fs::write(&vcf_path, to_vcf(&sample_id, &records))
+                .map_err(|e| format!("Could not write {}: {}", vcf_path.display(), e))?;
+            summary.n_vcf_files += 1;
+            let uri = format!("vcf/{}.vcf", file_stem);
+            phenopacket["files"] = json!([{
+                "uri": uri,
+                "individualToFileIdentifiers": {(sample_id.clone()): sample_id.replace(['\t', ' '], "_")},
+                "fileAttributes": {"fileFormat": "vcf", "genomeAssembly": genome_assembly(&records[0].assembly)},
+            }]);
+        }
+        write_json(&corpus_ppkt_dir.join(format!("{}.json", file_stem)), &phenopacket)?;
+    }
+    Ok(summary)
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hgvs_variant(key: &str, chr: &str, position: u32, g_hgvs: &str) -> Value {
+        json!({"assembly": "hg38", "chr": chr, "position": position, "refAllele": "C", "altAllele": "T",
+            "symbol": "FBN1", "hgncId": "HGNC:3603", "hgvs": "c.1C>T", "transcript": "NM_000138.5", "gHgvs": g_hgvs,
+            "pHgvs": null, "variantKey": key})
+    }
+
+    fn row(individual_id: &str, allele_counts: Value) -> Value {
+        json!({
+            "individualData": {"pmid": "PMID:1", "title": "", "individualId": individual_id, "comment": "",
+                "ageOfOnset": "na", "ageAtLastEncounter": "na", "deceased": "na", "sex": "U"},
+            "diseaseIdList": ["OMIM:154700"],
+            "alleleCountMap": allele_counts,
+            "hpoData": []
+        })
+    }
+
+    fn cohort() -> CohortData {
+        serde_json::from_value(json!({
+            "cohortType": "mendelian",
+            "cohortAcronym": "MFS",
+            "phetoolsSchemaVersion": "0.2",
+            "hpoVersion": "2025-05-06",
+            "diseaseList": [{"diseaseId": "OMIM:154700", "diseaseLabel": "Marfan syndrome", "modeOfInheritanceList": [],
+                "geneTranscriptList": [{"geneSymbol": "FBN1", "hgncId": "HGNC:3603", "transcript": "NM_000138.5"}]}],
+            "hpoHeaders": [],
+            "hgvsVariants": {
+                "v1": hgvs_variant("v1", "chr15", 48_410_000, "NC_000015.10:g.48410000A>G"),
+                "v2": hgvs_variant("v2", "chr2", 100, ""),
+                "vx": hgvs_variant("vx", "chrX", 200, "")
+            },
+            "structuralVariants": {"sv1": {"label": "deletion of exon 5", "geneSymbol": "FBN1", "transcript": "NM_000138.5",
+                "hgncId": "HGNC:3603", "svType": "DEL", "chromosome": "chr15", "variantKey": "sv1"}},
+            "intergenicVariants": {},
+            "curationHistory": [],
+            "rows": [
+                row("P1", json!({"v1": 2, "v2": 1, "vx": 1})),
+                row("P2", json!({"sv1": 1}))
+            ]
+        })).unwrap()
+    }
+
+    #[test]
+    fn test_vcf_records() {
+        let cohort = cohort();
+        let records = vcf_records(&cohort, &cohort.rows[0], "MALE");
+        let genotypes: Vec<(&str, &str)> = records.iter().map(|r| (r.chrom.as_str(), r.genotype.as_str())).collect();
+        assert_eq!(vec![("chr2", "0/1"), ("chr15", "1/1"), ("chrX", "1")], genotypes);
+        assert_eq!("NC_000015.10:g.48410000A>G", records[1].id);
+        assert!(vcf_records(&cohort, &cohort.rows[1], "MALE").is_empty());
+        let vcf = to_vcf("P1", &records);
+        assert!(vcf.contains("##reference=hg38\n"));
+        assert!(vcf.ends_with("chrX\t200\tvx\tC\tT\t.\tPASS\t.\tGT\t1\n"));
+    }
+
+    #[test]
+    fn test_write_analysis_bundle() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path();
+        let ppkt_dir = dir.join("ppkt");
+        fs::create_dir_all(&ppkt_dir).unwrap();
+        for id in ["P1", "P2"] {
+            let phenopacket = json!({"id": format!("PMID_1_{}", id), "subject": {"id": id, "sex": "FEMALE"}});
+            write_json(&ppkt_dir.join(format!("{}.json", id)), &phenopacket).unwrap();
+        }
+        let cohort = cohort();
+        // files of a previous export are removed
+        let stale_vcf = dir.join("bundle/pheval/corpora/MFS/vcf/PMID_1_P9.vcf");
+        fs::create_dir_all(stale_vcf.parent().unwrap()).unwrap();
+        fs::write(&stale_vcf, "").unwrap();
+        let summary = write_analysis_bundle(&cohort, &ppkt_dir, &dir.join("bundle")).unwrap();
+        assert!(!stale_vcf.exists());
+        assert_eq!(2, summary.n_phenopackets);
+        assert_eq!(1, summary.n_vcf_files);
+        assert_eq!(1, summary.warnings.len());
+        let config: Value = serde_json::from_str(&fs::read_to_string(&summary.gpsea_config).unwrap()).unwrap();
+        assert_eq!("NM_000138.5", config["analyses"][0]["transcript"]);
+        let corpus = Path::new(&summary.pheval_corpus_dir);
+        assert!(corpus.join("vcf/PMID_1_P1.vcf").is_file());
+        let p1: Value = serde_json::from_str(&fs::read_to_string(corpus.join("phenopackets/PMID_1_P1.json")).unwrap()).unwrap();
+        assert_eq!("GRCh38", p1["files"][0]["fileAttributes"]["genomeAssembly"]);
+        write_json(&ppkt_dir.join("P3.json"), &json!({"subject": {"id": "P3"}})).unwrap();
+        let error = write_analysis_bundle(&cohort, &ppkt_dir, &dir.join("bundle")).unwrap_err();
+        assert_eq!("Phenopacket of P3: missing field 'id'", error);
+        // an invalid export leaves the previous bundle in place
+        assert!(corpus.join("vcf/PMID_1_P1.vcf").is_file());
+    }
+}
+
+// endregion: --- Tests
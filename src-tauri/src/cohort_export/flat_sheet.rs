@@ -0,0 +1,477 @@
+//! Flat (spreadsheet) representation of a cohort
+//!
+//! Clinicians who contribute cases do not read the cohort JSON. We therefore export a cohort as a table with
+//! one row per individual and one column per HPO term, either as TSV or as an Excel workbook with an additional
+//! legend sheet (HPO version, term labels and allowed values). After the table was edited, it can be read back
+//! into the cohort: the rows of the table replace the rows of the cohort, whereas diseases, HPO terms and
+//! variants must already exist in the cohort (new variants need to be validated in Phenoboard).
+//!
+//! HPO cells contain `observed`, `excluded`, `na` (or nothing), or an age of onset (e.g., `P3Y` or
+//! `Congenital onset`), optionally followed by modifiers, e.g., `observed;HP:0012828`.
+
+use std::{collections::{HashMap, HashSet}, fs, path::Path, sync::LazyLock};
+
+use calamine::{open_workbook_auto, Reader};
+use ga4ghphetools::dto::{cohort_dto::{CohortData, IndividualData, RowData}, hpo_term_dto::{CellValue, CellValueInner}};
+use regex::Regex;
+use rust_xlsxwriter::{Format, Workbook};
+
+use crate::hpo::onset::is_valid_onset;
+
+/// Demographic columns (in the order of [`individual_fields`])
+const INDIVIDUAL_COLUMNS: [&str; 8] = [
+    "PMID",
+    "title",
+    "individual_id",
+    "comment",
+    "age_of_onset",
+    "age_at_last_encounter",
+    "deceased",
+    "sex",
+];
+const DISEASES_COLUMN: &str = "disease_ids";
+/// Human-readable description of the variants (ignored by the import)
+const VARIANTS_COLUMN: &str = "variants";
+/// Keys of the alleles of the individual (a key is repeated for homozygous variants)
+const VARIANT_KEYS_COLUMN: &str = "variant_keys";
+const SEX_VALUES: [&str; 4] = ["M", "F", "O", "U"];
+const DECEASED_VALUES: [&str; 3] = ["yes", "no", "na"];
+pub const COHORT_SHEET_NAME: &str = "Cohort";
+pub const LEGEND_SHEET_NAME: &str = "Legend";
+
+/// e.g., `Seizure (HP:0001250)`
+static HPO_HEADER_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\((HP:\d{7})\)\s*$").unwrap());
+static HPO_ID_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^HP:\d{7}$").unwrap());
+
+
+fn hpo_header(hpo_label: &str, hpo_id: &str) -> String {
+    format!("{} ({})", hpo_label, hpo_id)
+}
+
+/// Values of the demographic columns (see [`INDIVIDUAL_COLUMNS`])
+fn individual_fields(individual: &IndividualData) -> [&str; 8] {
+    [
+        &individual.pmid,
+        &individual.title,
+        &individual.individual_id,
+        &individual.comment,
+        &individual.age_of_onset,
+        &individual.age_at_last_encounter,
+        &individual.deceased,
+        &individual.sex,
+    ]
+}
+
+/// Text of an HPO cell, e.g., `observed`, `P3Y` or `observed;HP:0012828`
+fn cell_to_text(cell: &CellValue) -> String {
+    let mut text = match &cell.entry {
+        CellValueInner::Observed => "observed".to_string(),
+        CellValueInner::Excluded => "excluded".to_string(),
+        CellValueInner::OnsetAge(onset) => onset.clone(),
+        _ => "na".to_string(),
+    };
+    for modifier in &cell.modifiers {
+        text.push(';');
+        text.push_str(modifier);
+    }
+    text
+}
+
+/// Inverse of [`cell_to_text`]
+fn text_to_cell(text: &str) -> Result<CellValue, String> {
+    let mut parts = text.split(';').map(str::trim);
+    let value = parts.next().unwrap_or_default();
+    let modifiers: Vec<String> = parts.filter(|m| !m.is_empty()).map(String::from).collect();
+    if let Some(m) = modifiers.iter().find(|m| !HPO_ID_RE.is_match(m)) {
+        return Err(format!("Invalid modifier '{}' (expected an HPO id such as HP:0012828)", m));
+    }
+    let entry = match value.to_lowercase().as_str() {
+        "observed" => CellValueInner::Observed,
+        "excluded" => CellValueInner::Excluded,
+        "" | "na" => CellValueInner::Na,
+        _ if is_valid_onset(value) => CellValueInner::OnsetAge(value.to_string()),
+        _ => {
+            return Err(format!(
+                "Invalid value '{}' (expected observed, excluded, na, an ISO 8601 age such as P3Y, or an HPO onset term such as Congenital onset)",
+                value
+            ))
+        }
+    };
+    if !modifiers.is_empty() && matches!(entry, CellValueInner::Na | CellValueInner::Excluded) {
+        return Err(format!("'{}': modifiers are only allowed for observed features", text));
+    }
+    Ok(CellValue { entry, modifiers })
+}
+
+/// Human-readable label of a variant (HGVS or structural variant label)
+fn variant_label(cohort: &CohortData, key: &str) -> String {
+    if let Some(v) = cohort.hgvs_variants.get(key) {
+        return format!("{}:{}", v.transcript(), v.hgvs());
+    }
+    if let Some(v) = cohort.structural_variants.get(key) {
+        return v.label().to_string();
+    }
+    if let Some(v) = cohort.intergenic_variants.get(key) {
+        return v.g_hgvs().to_string();
+    }
+    key.to_string()
+}
+
+fn is_known_variant(cohort: &CohortData, key: &str) -> bool {
+    cohort.hgvs_variants.contains_key(key)
+        || cohort.structural_variants.contains_key(key)
+        || cohort.intergenic_variants.contains_key(key)
+}
+
+/// Convert a cohort to a table (the first row is the header)
+pub fn cohort_to_table(cohort: &CohortData) -> Vec<Vec<String>> {
+    let mut header: Vec<String> = INDIVIDUAL_COLUMNS.iter().map(|column| column.to_string()).collect();
+    header.extend([DISEASES_COLUMN, VARIANTS_COLUMN, VARIANT_KEYS_COLUMN].map(String::from));
+    header.extend(cohort.hpo_headers.iter().map(|h| hpo_header(&h.hpo_label, &h.hpo_id)));
+    let mut table = vec![header];
+    for row in &cohort.rows {
+        let mut fields: Vec<String> = individual_fields(&row.individual_data).iter().map(|f| f.to_string()).collect();
+        fields.push(row.disease_id_list.join(";"));
+        let mut allele_counts: Vec<(&String, &usize)> = row.allele_count_map.iter().collect();
+        allele_counts.sort();
+        let mut allele_keys: Vec<&str> = Vec::new();
+        for (key, count) in allele_counts {
+            for _ in 0..*count {
+                allele_keys.push(key);
+            }
+        }
+        let labels: Vec<String> = allele_keys.iter().map(|key| variant_label(cohort, key)).collect();
+        fields.push(labels.join("; "));
+        fields.push(allele_keys.join(";"));
+        for i in 0..cohort.hpo_headers.len() {
+            fields.push(row.hpo_data.get(i).map(cell_to_text).unwrap_or_else(|| "na".to_string()));
+        }
+        table.push(fields);
+    }
+    table
+}
+
+/// Rows of the legend sheet: HPO version, column descriptions and the labels of the HPO terms
+pub fn legend_table(cohort: &CohortData) -> Vec<Vec<String>> {
+    let mut legend = vec![
+        vec!["Cohort".to_string(), cohort.cohort_acronym.clone().unwrap_or_default()],
+        vec!["HPO version".to_string(), cohort.hpo_version.clone()],
+        vec!["Schema version".to_string(), cohort.phetools_schema_version.clone()],
+        vec![],
+        vec!["Column".to_string(), "Description".to_string()],
+        vec!["PMID".to_string(), "Publication, e.g., PMID:29198722".to_string()],
+        vec!["individual_id".to_string(), "Identifier of the individual in the publication".to_string()],
+        vec!["age_of_onset".to_string(), "ISO 8601 age (e.g., P3Y2M), HPO onset term (e.g., Congenital onset) or na".to_string()],
+        vec!["age_at_last_encounter".to_string(), "ISO 8601 age, HPO onset term or na".to_string()],
+        vec!["deceased".to_string(), DECEASED_VALUES.join(", ")],
+        vec!["sex".to_string(), "M, F, O (other) or U (unknown)".to_string()],
+        vec![DISEASES_COLUMN.to_string(), "Disease identifiers separated by semicolons".to_string()],
+        vec![VARIANTS_COLUMN.to_string(), "Description of the variants (not read back)".to_string()],
+        vec![VARIANT_KEYS_COLUMN.to_string(), "Keys of the alleles; repeat a key for homozygous variants".to_string()],
+        vec!["HP:nnnnnnn".to_string(), "observed, excluded, na or an age of onset (ISO 8601 age or HPO onset term); modifiers may follow, e.g., observed;HP:0012828".to_string()],
+        vec![],
+        vec!["HPO id".to_string(), "HPO label".to_string()],
+    ];
+    for h in &cohort.hpo_headers {
+        legend.push(vec![h.hpo_id.clone(), h.hpo_label.clone()]);
+    }
+    legend
+}
+
+pub fn table_to_tsv(table: &[Vec<String>]) -> String {
+    table
+        .iter()
+        .map(|row| row.iter().map(|f| f.replace(['\t', '\n', '\r'], " ")).collect::<Vec<_>>().join("\t") + "\n")
+        .collect()
+}
+
+/// Write the cohort as TSV (`.tsv`) or as Excel workbook with a legend sheet (any other extension)
+pub fn export_cohort_sheet(cohort: &CohortData, path: &Path) -> Result<(), String> {
+    let table = cohort_to_table(cohort);
+    if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("tsv")) {
+        return fs::write(path, table_to_tsv(&table))
+            .map_err(|e| format!("Could not write {}: {}", path.display(), e));
+    }
+    let mut workbook = Workbook::new();
+    let bold = Format::new().set_bold();
+    let sheet = workbook.add_worksheet().set_name(COHORT_SHEET_NAME).map_err(|e| e.to_string())?;
+    for (r, row) in table.iter().enumerate() {
+        for (c, value) in row.iter().enumerate() {
+            if r == 0 {
+                sheet.write_string_with_format(r as u32, c as u16, value, &bold)
+            } else {
+                sheet.write_string(r as u32, c as u16, value)
+            }.map_err(|e| e.to_string())?;
+        }
+    }
+    sheet.set_freeze_panes(1, 3).map_err(|e| e.to_string())?;
+    sheet.autofit();
+    let legend = workbook.add_worksheet().set_name(LEGEND_SHEET_NAME).map_err(|e| e.to_string())?;
+    for (r, row) in legend_table(cohort).iter().enumerate() {
+        for (c, value) in row.iter().enumerate() {
+            legend.write_string(r as u32, c as u16, value).map_err(|e| e.to_string())?;
+        }
+    }
+    legend.autofit();
+    workbook.save(path).map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+/// Read the table from a TSV file or from the cohort sheet of an Excel workbook
+pub fn read_cohort_sheet(path: &Path) -> Result<Vec<Vec<String>>, String> {
+    if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("tsv")) {
+        let tsv = fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        return Ok(tsv
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.split('\t').map(String::from).collect())
+            .collect());
+    }
+    let mut workbook = open_workbook_auto(path).map_err(|e| format!("Could not open {}: {}", path.display(), e))?;
+    let sheet_name = if workbook.sheet_names().iter().any(|n| n == COHORT_SHEET_NAME) {
+        COHORT_SHEET_NAME.to_string()
+    } else {
+        workbook.sheet_names().first().cloned().ok_or_else(|| format!("{} has no sheets", path.display()))?
+    };
+    let range = workbook.worksheet_range(&sheet_name).map_err(|e| format!("Could not read sheet {}: {}", sheet_name, e))?;
+    Ok(range
+        .rows()
+        .map(|row| row.iter().map(|cell| cell.to_string().trim().to_string()).collect::<Vec<_>>())
+        .filter(|row| row.iter().any(|f| !f.is_empty()))
+        .collect())
+}
+
+/// Replace the rows of `cohort` by the rows of an edited table.
+///
+/// All problems are reported together. The HPO columns, diseases and variant keys of the table must exist in the
+/// cohort, and every HPO term of the cohort must have a column (a deleted column would discard the data of all
+/// individuals for that term).
+pub fn table_to_cohort(cohort: &CohortData, table: &[Vec<String>]) -> Result<CohortData, String> {
+    let header = table.first().ok_or_else(|| "The table is empty".to_string())?;
+    let column = |name: &str| header.iter().position(|h| h.trim() == name);
+    let mut errors = Vec::new();
+    for name in INDIVIDUAL_COLUMNS.into_iter().chain([DISEASES_COLUMN, VARIANT_KEYS_COLUMN]) {
+        if column(name).is_none() {
+            errors.push(format!("Missing column '{}'", name));
+        }
+    }
+    let hpo_ids: Vec<&str> = cohort.hpo_headers.iter().map(|h| h.hpo_id.as_str()).collect();
+    // index of the table column for each HPO header of the cohort
+    let mut hpo_columns: HashMap<String, usize> = HashMap::new();
+    for (i, h) in header.iter().enumerate() {
+        if let Some(caps) = HPO_HEADER_RE.captures(h) {
+            let hpo_id = caps[1].to_string();
+            if !hpo_ids.contains(&hpo_id.as_str()) {
+                errors.push(format!("Column '{}': {} is not an HPO term of the cohort; add it in Phenoboard first", h, hpo_id));
+            } else if hpo_columns.insert(hpo_id, i).is_some() {
+                errors.push(format!("Duplicate column '{}'", h));
+            }
+        }
+    }
+    for h in cohort.hpo_headers.iter().filter(|h| !hpo_columns.contains_key(&h.hpo_id)) {
+        errors.push(format!("Missing column '{}'; HPO terms can only be removed from the cohort in Phenoboard", hpo_header(&h.hpo_label, &h.hpo_id)));
+    }
+    if !errors.is_empty() {
+        return Err(errors.join("\n"));
+    }
+    let disease_ids: HashSet<&str> = cohort.disease_list.iter().map(|d| d.disease_id.as_str()).collect();
+    let mut seen_individuals = HashSet::new();
+    let mut rows = Vec::new();
+    for (r, fields) in table.iter().enumerate().skip(1) {
+        let line = r + 1;
+        let field = |name: &str| column(name).and_then(|i| fields.get(i)).map(|f| f.trim()).unwrap_or_default();
+        let or_default = |name: &str, default: &str| match field(name) {
+            "" => default.to_string(),
+            value => value.to_string(),
+        };
+        let individual = IndividualData {
+            pmid: field("PMID").to_string(),
+            title: field("title").to_string(),
+            individual_id: field("individual_id").to_string(),
+            comment: field("comment").to_string(),
+            age_of_onset: or_default("age_of_onset", "na"),
+            age_at_last_encounter: or_default("age_at_last_encounter", "na"),
+            deceased: or_default("deceased", "na"),
+            sex: or_default("sex", "U"),
+        };
+        let (pmid, individual_id) = (field("PMID"), field("individual_id"));
+        if pmid.is_empty() || individual_id.is_empty() {
+            errors.push(format!("Row {}: PMID and individual_id are required", line));
+        } else if !seen_individuals.insert((pmid.to_string(), individual_id.to_string())) {
+            errors.push(format!("Row {}: duplicate individual {} ({})", line, individual_id, pmid));
+        }
+        if !SEX_VALUES.contains(&individual.sex.as_str()) {
+            errors.push(format!("Row {}: invalid sex '{}' (expected one of {})", line, individual.sex, SEX_VALUES.join(", ")));
+        }
+        if !DECEASED_VALUES.contains(&individual.deceased.as_str()) {
+            errors.push(format!("Row {}: invalid deceased '{}' (expected one of {})", line, individual.deceased, DECEASED_VALUES.join(", ")));
+        }
+        for (name, age) in [("age_of_onset", &individual.age_of_onset), ("age_at_last_encounter", &individual.age_at_last_encounter)] {
+            if age != "na" && !is_valid_onset(age) {
+                errors.push(format!(
+                    "Row {}: invalid {} '{}' (expected an ISO 8601 age such as P3Y, an HPO onset term such as Congenital onset, or na)",
+                    line, name, age
+                ));
+            }
+        }
+        let diseases: Vec<String> = field(DISEASES_COLUMN).split(';').map(str::trim).filter(|d| !d.is_empty()).map(String::from).collect();
+        for d in diseases.iter().filter(|d| !disease_ids.contains(d.as_str())) {
+            errors.push(format!("Row {}: {} is not a disease of the cohort", line, d));
+        }
+        let mut allele_counts: HashMap<String, usize> = HashMap::new();
+        for key in field(VARIANT_KEYS_COLUMN).split(';').map(str::trim).filter(|k| !k.is_empty()) {
+            if !is_known_variant(cohort, key) {
+                errors.push(format!("Row {}: unknown variant key '{}'; add new variants in Phenoboard", line, key));
+            }
+            *allele_counts.entry(key.to_string()).or_default() += 1;
+        }
+        let mut hpo_data = Vec::new();
+        for hpo_id in &hpo_ids {
+            let text = hpo_columns.get(*hpo_id).and_then(|i| fields.get(*i)).map(|f| f.trim()).unwrap_or_default();
+            match text_to_cell(text) {
+                Ok(cell) => hpo_data.push(cell),
+                Err(e) => errors.push(format!("Row {}, {}: {}", line, hpo_id, e)),
+            }
+        }
+        rows.push(RowData {
+            individual_data: individual,
+            disease_id_list: diseases,
+            allele_count_map: allele_counts,
+            hpo_data,
+        });
+    }
+    if !errors.is_empty() {
+        return Err(errors.join("\n"));
+    }
+    let mut imported = cohort.clone();
+    imported.rows = rows;
+    Ok(imported)
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn cohort() -> CohortData {
+        serde_json::from_value(json!({
+            "cohortType": "mendelian",
+            "cohortAcronym": "MFS",
+            "phetoolsSchemaVersion": "0.2",
+            "hpoVersion": "2025-05-06",
+            "diseaseList": [{"diseaseId": "OMIM:154700", "diseaseLabel": "Marfan syndrome",
+                "modeOfInheritanceList": [], "geneTranscriptList": []}],
+            "hpoHeaders": [{"hpoId": "HP:0001166", "hpoLabel": "Arachnodactyly"}, {"hpoId": "HP:0001250", "hpoLabel": "Seizure"}],
+            "hgvsVariants": {"c123AtoG_FBN1_NM_000138v5": {"assembly": "hg38", "chr": "chr15", "position": 48500000,
+                "refAllele": "A", "altAllele": "G", "symbol": "FBN1", "hgncId": "HGNC:3603", "hgvs": "c.123A>G",
+                "transcript": "NM_000138.5", "gHgvs": "NC_000015.10:g.48500000A>G", "pHgvs": null,
+                "variantKey": "c123AtoG_FBN1_NM_000138v5"}},
+            "structuralVariants": {},
+            "intergenicVariants": {},
+            "curationHistory": [],
+            "rows": [{
+                "individualData": {"pmid": "PMID:1", "title": "A case", "individualId": "P1", "comment": "",
+                    "ageOfOnset": "P3Y", "ageAtLastEncounter": "na", "deceased": "no", "sex": "F"},
+                "diseaseIdList": ["OMIM:154700"],
+                "alleleCountMap": {"c123AtoG_FBN1_NM_000138v5": 2},
+                "hpoData": [{"type": "Observed", "modifiers": ["HP:0012828"]}, {"type": "OnsetAge", "data": "P2Y"}]
+            }]
+        })).unwrap()
+    }
+
+    fn to_json<T: serde::Serialize>(value: &T) -> Value {
+        serde_json::to_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let cohort = cohort();
+        let table = cohort_to_table(&cohort);
+        assert_eq!("Seizure (HP:0001250)", table[0][12]);
+        assert_eq!("NM_000138.5:c.123A>G; NM_000138.5:c.123A>G", table[1][9]);
+        assert_eq!("observed;HP:0012828", table[1][11]);
+        let imported = table_to_cohort(&cohort, &table).unwrap();
+        assert_eq!(to_json(&cohort), to_json(&imported));
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("cohort.xlsx");
+        export_cohort_sheet(&cohort, &path).unwrap();
+        assert_eq!(table, read_cohort_sheet(&path).unwrap());
+    }
+
+    #[test]
+    fn test_import_edited_tsv() {
+        let cohort = cohort();
+        let mut table = cohort_to_table(&cohort);
+        // the clinician cleared arachnodactyly, excluded seizures and added an individual
+        table[1][11] = "".to_string();
+        table[1][12] = "excluded".to_string();
+        let mut new_row = table[1].clone();
+        new_row[2] = "P2".to_string();
+        new_row[7] = "".to_string();
+        new_row[10] = "c123AtoG_FBN1_NM_000138v5".to_string();
+        new_row[11] = "Congenital onset".to_string();
+        table.push(new_row);
+        let tsv = table_to_tsv(&table);
+        let parsed: Vec<Vec<String>> = tsv.lines().map(|l| l.split('\t').map(String::from).collect()).collect();
+        let imported = table_to_cohort(&cohort, &parsed).unwrap();
+        let rows = &imported.rows;
+        assert_eq!(2, rows.len());
+        assert!(matches!(rows[0].hpo_data[0].entry, CellValueInner::Na));
+        assert!(matches!(rows[0].hpo_data[1].entry, CellValueInner::Excluded));
+        assert_eq!("U", rows[1].individual_data.sex);
+        assert_eq!(Some(&1), rows[1].allele_count_map.get("c123AtoG_FBN1_NM_000138v5"));
+        assert!(matches!(&rows[1].hpo_data[0].entry, CellValueInner::OnsetAge(onset) if onset == "Congenital onset"));
+    }
+
+    #[test]
+    fn test_onset_values() {
+        assert!(matches!(text_to_cell("P1Y6M").unwrap().entry, CellValueInner::OnsetAge(onset) if onset == "P1Y6M"));
+        assert!(matches!(text_to_cell("Infantile onset").unwrap().entry, CellValueInner::OnsetAge(onset) if onset == "Infantile onset"));
+        assert!(text_to_cell("sometimes").is_err());
+        assert!(text_to_cell("P").is_err());
+        assert!(text_to_cell("obsreved").is_err());
+    }
+
+    #[test]
+    fn test_deleted_hpo_column_is_rejected() {
+        let cohort = cohort();
+        let mut table = cohort_to_table(&cohort);
+        for row in table.iter_mut() {
+            row.remove(11);
+        }
+        let errors = table_to_cohort(&cohort, &table).unwrap_err();
+        assert!(errors.contains("Missing column 'Arachnodactyly (HP:0001166)'"), "{}", errors);
+    }
+
+    #[test]
+    fn test_import_errors() {
+        let cohort = cohort();
+        let mut table = cohort_to_table(&cohort);
+        table[1][7] = "female".to_string();
+        table[1][10] = "unknownKey".to_string();
+        table[1][12] = "excluded;HP:0012828".to_string();
+        let errors = table_to_cohort(&cohort, &table).unwrap_err();
+        assert_eq!(3, errors.lines().count(), "{}", errors);
+        table[0][12] = "Tremor (HP:0001337)".to_string();
+        assert!(table_to_cohort(&cohort, &table).unwrap_err().contains("HP:0001337"));
+    }
+
+    #[test]
+    fn test_invalid_ages_are_rejected() {
+        let cohort = cohort();
+        let mut table = cohort_to_table(&cohort);
+        table[1][4] = "3 years".to_string();
+        table[1][5] = "Childhood onset".to_string();
+        let errors = table_to_cohort(&cohort, &table).unwrap_err();
+        assert_eq!(1, errors.lines().count(), "{}", errors);
+        assert!(errors.contains("invalid age_of_onset '3 years'"), "{}", errors);
+        table[1][4] = "".to_string();
+        let imported = table_to_cohort(&cohort, &table).unwrap();
+        assert_eq!("na", imported.rows[0].individual_data.age_of_onset);
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,5 @@
+//! Exports of cohorts for collaborators and downstream tools (in addition to the individual phenopackets)
+
+pub mod flat_sheet;
+pub mod phenopacket_collections;
+pub mod analysis_bundle;
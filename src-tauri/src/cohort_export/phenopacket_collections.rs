@@ -0,0 +1,368 @@
+//! GA4GH Phenopacket v2 Cohort and Family messages
+//!
+//! `export_ppkt` writes one Phenopacket per row of a cohort. Tools that analyze pedigrees or whole cohorts expect
+//! the other two top-level messages of the schema: a *Cohort* with all individuals as members, and one *Family*
+//! per pedigree with the proband, the relatives and a pedigree.
+//!
+//! Family membership is derived from the individual identifiers used in the publications, e.g., `Family 2 II-1`,
+//! `F2:III-4` or `Kindred 3 proband` (see [`parse_family_id`]). Families are scoped by publication, since many
+//! articles number their families from 1. The proband of a family is the individual whose identifier or comment
+//! contains "proband"; if there is none, the first individual of the family is used and a warning is returned.
+//! Since the templates record affected individuals only and do not record parents, all persons of the pedigree
+//! are affected and have unknown parents (`0`, as in PED files).
+
+use std::{collections::BTreeMap, fs, path::Path, sync::LazyLock};
+
+use ga4ghphetools::dto::cohort_dto::{CohortData, IndividualData};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::util::json_fields::{required_str, str_field};
+
+/// e.g., `Family 2 II-1`, `Fam. 3: IV-2`, `F2-III:4`, `Kindred 1 proband`
+static FAMILY_ID_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^\s*(?:family|fam\.?|kindred|f)\s*[-_:#]?\s*(\d+[A-Za-z]?)\b(?:[\s,:_\-]+(.+))?$").unwrap()
+});
+/// Unknown parent in a pedigree (PED convention)
+const UNKNOWN_PARENT: &str = "0";
+
+
+/// Result of the export of the Cohort and Family messages of one cohort
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhenopacketCollectionSummary {
+    pub out_dir: String,
+    pub cohort_file: String,
+    pub n_members: usize,
+    pub family_files: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Family of an individual derived from its identifier
+#[derive(Clone, Debug, PartialEq)]
+pub struct FamilyMembership {
+    /// e.g., `Family 2`
+    pub family: String,
+    /// Position in the pedigree, e.g., `II-1` (the whole identifier if it has no member part)
+    pub member: String,
+}
+
+/// Derive the family of an individual from its identifier; None if the identifier does not name a family
+pub fn parse_family_id(individual_id: &str) -> Option<FamilyMembership> {
+    let caps = FAMILY_ID_RE.captures(individual_id)?;
+    Some(FamilyMembership {
+        family: format!("Family {}", caps[1].to_uppercase()),
+        member: caps.get(2).map(|m| m.as_str().trim().to_string()).unwrap_or_else(|| individual_id.trim().to_string()),
+    })
+}
+
+/// Identifier usable in file names and message ids, e.g., `PMID:123 Family 2` → `PMID_123_Family_2`
+pub(crate) fn sanitize_id(id: &str) -> String {
+    let sanitized: String = id.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' }).collect();
+    sanitized.split('_').filter(|s| !s.is_empty()).collect::<Vec<_>>().join("_")
+}
+
+fn is_proband(individual: &IndividualData) -> bool {
+    [&individual.individual_id, &individual.comment].iter().any(|field| field.to_lowercase().contains("proband"))
+}
+
+fn phenopacket_pmids(phenopacket: &Value) -> Vec<String> {
+    phenopacket["metaData"]["externalReferences"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|r| str_field(r, "id"))
+        .collect()
+}
+
+/// Find the phenopacket of a row of the cohort (same subject id and, if there are several, same PMID)
+pub(crate) fn find_phenopacket<'a>(individual: &IndividualData, phenopackets: &'a [Value]) -> Option<&'a Value> {
+    let individual_id = individual.individual_id.trim();
+    let pmid = individual.pmid.trim().to_string();
+    let candidates: Vec<&Value> = phenopackets
+        .iter()
+        .filter(|p| str_field(&p["subject"], "id") == individual_id)
+        .collect();
+    candidates
+        .iter()
+        .find(|p| phenopacket_pmids(p).contains(&pmid))
+        .or(candidates.first())
+        .copied()
+}
+
+/// Read all phenopackets (`*.json`) of a directory, sorted by file name. Every phenopacket needs a subject id,
+/// which is used to match it to the rows of the cohort.
+pub fn read_phenopackets(dir: &Path) -> Result<Vec<Value>, String> {
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .map_err(|e| format!("Could not read {}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    paths
+        .iter()
+        .map(|p| {
+            let content = fs::read_to_string(p).map_err(|e| format!("Could not read {}: {}", p.display(), e))?;
+            let phenopacket: Value = serde_json::from_str(&content).map_err(|e| format!("Could not parse {}: {}", p.display(), e))?;
+            required_str(&phenopacket["subject"], "id", &format!("Subject of {}", p.display()))?;
+            Ok(phenopacket)
+        })
+        .collect()
+}
+
+/// MetaData of a collection: that of the first member, with the resources and external references of all members
+fn merged_meta_data(members: &[&Value]) -> Value {
+    let mut meta_data = members.first().map(|m| m["metaData"].clone()).unwrap_or_else(|| json!({}));
+    for key in ["resources", "externalReferences"] {
+        let mut merged: Vec<Value> = Vec::new();
+        for member in members {
+            for item in member["metaData"][key].as_array().into_iter().flatten() {
+                if !merged.iter().any(|m| m["id"] == item["id"]) {
+                    merged.push(item.clone());
+                }
+            }
+        }
+        if !merged.is_empty() {
+            meta_data[key] = Value::Array(merged);
+        }
+    }
+    meta_data
+}
+
+/// Identifier of the cohort: the acronym if there is one, otherwise the disease ids
+pub fn cohort_id(cohort: &CohortData) -> String {
+    match cohort.cohort_acronym.as_deref().map(str::trim) {
+        Some(acronym) if !acronym.is_empty() => sanitize_id(acronym),
+        _ => {
+            let disease_ids: Vec<&str> = cohort.disease_list.iter().map(|d| d.disease_id.as_str()).collect();
+            sanitize_id(&disease_ids.join("_"))
+        },
+    }
+}
+
+/// [`cohort_id`] for file and directory names; an error if the cohort has neither an acronym nor diseases
+pub(crate) fn required_cohort_id(cohort: &CohortData) -> Result<String, String> {
+    let id = cohort_id(cohort);
+    if id.is_empty() {
+        return Err("The cohort has neither an acronym nor diseases, which are needed to name the export".to_string());
+    }
+    Ok(id)
+}
+
+/// Build the Cohort message with the phenopackets of all rows of the cohort
+pub fn build_cohort_message(cohort: &CohortData, phenopackets: &[Value]) -> Value {
+    let members: Vec<&Value> = phenopackets.iter().collect();
+    let description: Vec<String> = cohort.disease_list
+        .iter()
+        .map(|d| format!("{} ({})", d.disease_label, d.disease_id))
+        .collect();
+    json!({
+        "id": cohort_id(cohort),
+        "description": description.join("; "),
+        "members": members,
+        "metaData": merged_meta_data(&members),
+    })
+}
+
+/// Build one Family message for each group of rows that share a family (see the module documentation).
+/// Returns the messages and warnings about missing phenopackets and ambiguous probands.
+pub fn build_family_messages(cohort: &CohortData, phenopackets: &[Value]) -> (Vec<Value>, Vec<String>) {
+    let mut warnings = Vec::new();
+    // (PMID, family) → individuals
+    let mut families: BTreeMap<(String, String), Vec<&IndividualData>> = BTreeMap::new();
+    for row in &cohort.rows {
+        let individual = &row.individual_data;
+        if let Some(membership) = parse_family_id(&individual.individual_id) {
+            families.entry((individual.pmid.trim().to_string(), membership.family)).or_default().push(individual);
+        }
+    }
+    let mut messages = Vec::new();
+    for ((pmid, family), individuals) in families {
+        if individuals.len() < 2 {
+            continue;
+        }
+        let family_id = sanitize_id(&format!("{} {}", pmid, family));
+        let mut members: Vec<(&IndividualData, &Value)> = Vec::new();
+        for individual in &individuals {
+            match find_phenopacket(individual, phenopackets) {
+                Some(phenopacket) => members.push((individual, phenopacket)),
+                None => warnings.push(format!("{}: no phenopacket for {}", family_id, individual.individual_id)),
+            }
+        }
+        if members.is_empty() {
+            continue;
+        }
+        let probands: Vec<usize> = (0..members.len()).filter(|i| is_proband(members[*i].0)).collect();
+        let proband_index = match probands.as_slice() {
+            [i] => *i,
+            [] => {
+                warnings.push(format!("{}: no proband flagged, using {}", family_id, members[0].0.individual_id));
+                0
+            },
+            [i, ..] => {
+                warnings.push(format!("{}: {} probands flagged, using {}", family_id, probands.len(), members[*i].0.individual_id));
+                *i
+            },
+        };
+        let persons: Vec<Value> = members
+            .iter()
+            .map(|(_, phenopacket)| {
+                let sex = phenopacket["subject"]["sex"].as_str().unwrap_or("UNKNOWN_SEX");
+                json!({
+                    "familyId": family_id,
+                    "individualId": str_field(&phenopacket["subject"], "id"),
+                    "paternalId": UNKNOWN_PARENT,
+                    "maternalId": UNKNOWN_PARENT,
+                    "sex": sex,
+                    "affectedStatus": "AFFECTED",
+                })
+            })
+            .collect();
+        let phenopackets: Vec<&Value> = members.iter().map(|(_, p)| *p).collect();
+        let relatives: Vec<&Value> = phenopackets.iter().enumerate().filter(|(i, _)| *i != proband_index).map(|(_, p)| *p).collect();
+        messages.push(json!({
+            "id": family_id,
+            "proband": phenopackets[proband_index],
+            "relatives": relatives,
+            "pedigree": {"persons": persons},
+            "metaData": merged_meta_data(&phenopackets),
+        }));
+    }
+    (messages, warnings)
+}
+
+/// Write the Cohort message (`<cohort id>_cohort.json`) and the Family messages (`<family id>_family.json`)
+/// built from the phenopackets in `phenopacket_dir`
+pub fn write_phenopacket_collections(cohort: &CohortData, phenopacket_dir: &Path, out_dir: &Path) -> Result<PhenopacketCollectionSummary, String> {
+    let phenopackets = read_phenopackets(phenopacket_dir)?;
+    if phenopackets.is_empty() {
+        return Err(format!("No phenopackets found in {}", phenopacket_dir.display()));
+    }
+    let id = required_cohort_id(cohort)?;
+    fs::create_dir_all(out_dir).map_err(|e| format!("Could not create {}: {}", out_dir.display(), e))?;
+    let write = |message: &Value, file_name: String| -> Result<String, String> {
+        let path = out_dir.join(file_name);
+        let content = serde_json::to_string_pretty(message).map_err(|e| e.to_string())?;
+        fs::write(&path, content).map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+        Ok(path.to_string_lossy().to_string())
+    };
+    let cohort_message = build_cohort_message(cohort, &phenopackets);
+    let mut summary = PhenopacketCollectionSummary {
+        out_dir: out_dir.to_string_lossy().to_string(),
+        cohort_file: write(&cohort_message, format!("{}_cohort.json", id))?,
+        n_members: phenopackets.len(),
+        ..Default::default()
+    };
+    let (families, warnings) = build_family_messages(cohort, &phenopackets);
+    for family in &families {
+        summary.family_files.push(write(family, format!("{}_family.json", str_field(family, "id")))?);
+    }
+    summary.warnings = warnings;
+    Ok(summary)
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phenopacket(id: &str, sex: &str, pmid: &str) -> Value {
+        json!({
+            "id": format!("{}_{}", pmid.replace(':', "_"), id.replace(' ', "_")),
+            "subject": {"id": id, "sex": sex},
+            "metaData": {
+                "createdBy": "ORCID:0000-0002-0736-9199",
+                "resources": [{"id": "hp", "version": "2025-05-06"}],
+                "externalReferences": [{"id": pmid}],
+                "phenopacketSchemaVersion": "2.0",
+            }
+        })
+    }
+
+    fn row(pmid: &str, individual_id: &str, comment: &str) -> Value {
+        json!({
+            "individualData": {"pmid": pmid, "title": "", "individualId": individual_id, "comment": comment,
+                "ageOfOnset": "na", "ageAtLastEncounter": "na", "deceased": "na", "sex": "U"},
+            "diseaseIdList": ["OMIM:154700"],
+            "alleleCountMap": {},
+            "hpoData": []
+        })
+    }
+
+    fn cohort(rows: Vec<Value>) -> CohortData {
+        serde_json::from_value(json!({
+            "cohortType": "mendelian",
+            "cohortAcronym": "MFS",
+            "phetoolsSchemaVersion": "0.2",
+            "hpoVersion": "2025-05-06",
+            "diseaseList": [{"diseaseId": "OMIM:154700", "diseaseLabel": "Marfan syndrome",
+                "modeOfInheritanceList": [], "geneTranscriptList": []}],
+            "hpoHeaders": [],
+            "hgvsVariants": {},
+            "structuralVariants": {},
+            "intergenicVariants": {},
+            "curationHistory": [],
+            "rows": rows
+        })).unwrap()
+    }
+
+    #[test]
+    fn test_parse_family_id() {
+        let family = parse_family_id("Family 2 II-1").unwrap();
+        assert_eq!("Family 2", family.family);
+        assert_eq!("II-1", family.member);
+        assert_eq!("Family 3", parse_family_id("F3:III-4").unwrap().family);
+        assert_eq!("Family 1A", parse_family_id("kindred 1a proband").unwrap().family);
+        assert_eq!("Fam. 7", parse_family_id("Fam. 7").unwrap().member);
+        assert!(parse_family_id("P1").is_none());
+        assert!(parse_family_id("Female patient").is_none());
+    }
+
+    #[test]
+    fn test_build_messages() {
+        let cohort = cohort(vec![
+            row("PMID:1", "Family 1 II-1", "proband"),
+            row("PMID:1", "Family 1 I-2", ""),
+            row("PMID:2", "Family 1 II-1", ""),
+            row("PMID:2", "Family 1 II-2", ""),
+            row("PMID:2", "P3", ""),
+        ]);
+        let phenopackets = vec![
+            phenopacket("Family 1 II-1", "MALE", "PMID:1"),
+            phenopacket("Family 1 I-2", "FEMALE", "PMID:1"),
+            phenopacket("Family 1 II-1", "FEMALE", "PMID:2"),
+            phenopacket("Family 1 II-2", "MALE", "PMID:2"),
+            phenopacket("P3", "MALE", "PMID:2"),
+        ];
+        let cohort_message = build_cohort_message(&cohort, &phenopackets);
+        assert_eq!("MFS", cohort_message["id"]);
+        assert_eq!(5, cohort_message["members"].as_array().unwrap().len());
+        assert_eq!(2, cohort_message["metaData"]["externalReferences"].as_array().unwrap().len());
+        let (families, warnings) = build_family_messages(&cohort, &phenopackets);
+        assert_eq!(2, families.len());
+        assert_eq!("PMID_1_Family_1", families[0]["id"]);
+        assert_eq!("Family 1 II-1", families[0]["proband"]["subject"]["id"]);
+        assert_eq!("FEMALE", families[0]["relatives"][0]["subject"]["sex"]);
+        assert_eq!("PMID_1_Family_1", families[0]["pedigree"]["persons"][1]["familyId"]);
+        // the family of the second publication has no flagged proband
+        assert_eq!("FEMALE", families[1]["proband"]["subject"]["sex"]);
+        assert_eq!(1, warnings.len());
+    }
+
+    #[test]
+    fn test_phenopacket_without_subject_id() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        fs::write(tmp.path().join("a.json"), phenopacket("P1", "MALE", "PMID:1").to_string()).unwrap();
+        assert_eq!(1, read_phenopackets(tmp.path()).unwrap().len());
+        fs::write(tmp.path().join("b.json"), json!({"subject": {"sex": "MALE"}}).to_string()).unwrap();
+        let error = read_phenopackets(tmp.path()).unwrap_err();
+        assert!(error.ends_with("b.json: missing field 'id'"), "{}", error);
+    }
+}
+
+// endregion: --- Tests
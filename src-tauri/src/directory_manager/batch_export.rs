@@ -0,0 +1,231 @@
+//! Regenerate the phenopackets of all cohorts in Phenopacket Store
+//!
+//! After an update of ga4ghphetools or of the HPO, we need to regenerate every phenopacket. For each
+//! cohort template found by the [`DirectoryManager`](crate::directory_manager::DirectoryManager), we load
+//! and validate the template and export its phenopackets into a temporary directory. We then compare each
+//! exported phenopacket with the existing file and only replace files whose content changed (ignoring the
+//! creation timestamp, which differs for every export, and the exporting curator). In dry-run mode, nothing is written.
+
+use std::{fs, path::{Path, PathBuf}, sync::Arc};
+
+use ontolius::ontology::csr::FullCsrOntology;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tempfile::TempDir;
+
+use crate::directory_manager::cohort_metadata::read_json_file;
+
+
+/// One cohort template whose phenopackets should be exported
+#[derive(Clone, Debug)]
+pub struct ExportJob {
+    pub cohort_name: String,
+    pub template_path: PathBuf,
+    pub phenopacket_dir: PathBuf,
+}
+
+/// Result of exporting the phenopackets of one cohort template
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CohortExportSummary {
+    pub cohort_name: String,
+    pub template_file: String,
+    /// Phenopackets that are new or whose content changed (would be written in dry-run mode)
+    pub written: Vec<String>,
+    /// Phenopackets whose content did not change
+    pub unchanged: Vec<String>,
+    /// Reason for failure (e.g., template could not be loaded or did not pass QC)
+    pub error: Option<String>,
+}
+
+/// Summary of a batch export over the entire repository
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchExportReport {
+    pub dry_run: bool,
+    pub cohorts: Vec<CohortExportSummary>,
+    pub n_written: usize,
+    pub n_unchanged: usize,
+    pub n_failed_cohorts: usize,
+}
+
+
+/// Remove the indicated fields of the `metaData` element
+fn strip_metadata_fields(json: &Value, fields: &[&str]) -> Value {
+    let mut json = json.clone();
+    if let Some(meta_data) = json.get_mut("metaData").and_then(|m| m.as_object_mut()) {
+        for field in fields {
+            meta_data.remove(*field);
+        }
+    }
+    json
+}
+
+/// Two phenopackets have the same content if they are identical except for the creation timestamp and
+/// the ORCID of the biocurator who exported them. This is used both by the batch export and the
+/// repository QC, so that a phenopacket re-exported by another curator is not rewritten.
+pub fn phenopacket_contents_equivalent(a: &Value, b: &Value) -> bool {
+    let fields = ["created", "createdBy"];
+    strip_metadata_fields(a, &fields) == strip_metadata_fields(b, &fields)
+}
+
+/// Fresh temporary directory for the export of one cohort; it is removed when the returned value is dropped
+pub fn temp_export_dir(cohort_name: &str) -> Result<TempDir, String> {
+    tempfile::Builder::new()
+        .prefix(&format!("phenoboard_export_{}_", cohort_name))
+        .tempdir()
+        .map_err(|e| format!("Could not create temporary directory for {}: {}", cohort_name, e))
+}
+
+/// Load and validate the template and write its phenopackets into `out_dir`
+pub fn export_to_directory(
+    template_path: &Path,
+    out_dir: &Path,
+    hpo: Arc<FullCsrOntology>,
+    orcid: &str,
+) -> Result<(), String> {
+    let cohort = ga4ghphetools::factory::load_json_cohort(&template_path.to_string_lossy())?;
+    ga4ghphetools::factory::qc_assessment(hpo.clone(), &cohort).map_err(|e| e.to_string())?;
+    fs::create_dir_all(out_dir)
+        .map_err(|e| format!("Could not create {}: {}", out_dir.display(), e))?;
+    ga4ghphetools::ppkt::write_phenopackets(cohort, out_dir.to_path_buf(), orcid.to_string(), hpo, true)?;
+    Ok(())
+}
+
+/// Export the phenopackets of one template and update the changed files in the phenopacket directory
+fn export_cohort(
+    job: &ExportJob,
+    hpo: Arc<FullCsrOntology>,
+    orcid: &str,
+    dry_run: bool,
+) -> CohortExportSummary {
+    let mut summary = CohortExportSummary {
+        cohort_name: job.cohort_name.clone(),
+        template_file: job.template_path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default(),
+        ..Default::default()
+    };
+    let result = temp_export_dir(&job.cohort_name).and_then(|tmp_dir| {
+        export_to_directory(&job.template_path, tmp_dir.path(), hpo, orcid)
+            .and_then(|_| compare_and_copy(tmp_dir.path(), &job.phenopacket_dir, dry_run, &mut summary))
+    });
+    if let Err(e) = result {
+        summary.error = Some(e);
+    }
+    summary
+}
+
+/// Compare the freshly exported phenopackets with the existing ones and copy new or changed files
+fn compare_and_copy(
+    export_dir: &Path,
+    phenopacket_dir: &Path,
+    dry_run: bool,
+    summary: &mut CohortExportSummary,
+) -> Result<(), String> {
+    let mut exported: Vec<PathBuf> = fs::read_dir(export_dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    exported.sort();
+    if !dry_run {
+        fs::create_dir_all(phenopacket_dir)
+            .map_err(|e| format!("Could not create {}: {}", phenopacket_dir.display(), e))?;
+    }
+    for path in exported {
+        let file_name = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+        let target = phenopacket_dir.join(&file_name);
+        let unchanged = target.is_file()
+            && match (read_json_file(&path), read_json_file(&target)) {
+                (Ok(new), Ok(old)) => phenopacket_contents_equivalent(&new, &old),
+                _ => false,
+            };
+        if unchanged {
+            summary.unchanged.push(file_name);
+            continue;
+        }
+        if !dry_run {
+            fs::copy(&path, &target)
+                .map_err(|e| format!("Could not write {}: {}", target.display(), e))?;
+        }
+        summary.written.push(file_name);
+    }
+    Ok(())
+}
+
+/// Export the phenopackets of all jobs.
+///
+/// # Arguments
+/// * `jobs` - the cohort templates to export
+/// * `hpo` - reference to the HPO
+/// * `orcid` - ORCID of the biocurator, written into the metadata of each phenopacket
+/// * `dry_run` - if true, determine which files would change but do not write anything
+/// * `progress_cb` - called with (current, total) after each cohort
+///
+/// A failure in one cohort is recorded in its summary and does not stop the export of the others.
+pub fn run_batch_export<F>(
+    jobs: &[ExportJob],
+    hpo: Arc<FullCsrOntology>,
+    orcid: &str,
+    dry_run: bool,
+    mut progress_cb: F,
+) -> BatchExportReport
+where
+    F: FnMut(u32, u32),
+{
+    let mut report = BatchExportReport { dry_run, ..Default::default() };
+    let total = jobs.len() as u32;
+    for (i, job) in jobs.iter().enumerate() {
+        let summary = export_cohort(job, hpo.clone(), orcid, dry_run);
+        report.n_written += summary.written.len();
+        report.n_unchanged += summary.unchanged.len();
+        if summary.error.is_some() {
+            report.n_failed_cohorts += 1;
+        }
+        report.cohorts.push(summary);
+        progress_cb(i as u32 + 1, total);
+    }
+    report
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_creation_timestamp_is_ignored() {
+        let a = serde_json::json!({"id": "P1", "metaData": {"created": "2024-01-01T00:00:00Z", "createdBy": "0000-0002-0736-9199"}});
+        let b = serde_json::json!({"id": "P1", "metaData": {"created": "2025-06-01T00:00:00Z", "createdBy": "0000-0002-0736-9199"}});
+        let c = serde_json::json!({"id": "P2", "metaData": {"created": "2024-01-01T00:00:00Z", "createdBy": "0000-0002-0736-9199"}});
+        assert!(phenopacket_contents_equivalent(&a, &b));
+        assert!(!phenopacket_contents_equivalent(&a, &c));
+        let d = serde_json::json!({"id": "P1", "metaData": {"created": "2025-06-01T00:00:00Z", "createdBy": "0000-0003-0986-4123"}});
+        assert!(phenopacket_contents_equivalent(&a, &d));
+    }
+
+    #[test]
+    fn test_compare_and_copy() {
+        let base = TempDir::new().unwrap();
+        let export_dir = base.path().join("export");
+        let ppkt_dir = base.path().join("phenopackets");
+        fs::create_dir_all(&export_dir).unwrap();
+        fs::create_dir_all(&ppkt_dir).unwrap();
+        fs::write(export_dir.join("P1.json"), r#"{"id": "P1", "metaData": {"created": "new"}}"#).unwrap();
+        fs::write(export_dir.join("P2.json"), r#"{"id": "P2"}"#).unwrap();
+        fs::write(ppkt_dir.join("P1.json"), r#"{"id": "P1", "metaData": {"created": "old", "createdBy": "0000-0003-0986-4123"}}"#).unwrap();
+        let mut summary = CohortExportSummary::default();
+        compare_and_copy(&export_dir, &ppkt_dir, true, &mut summary).unwrap();
+        assert_eq!(vec!["P2.json"], summary.written);
+        assert_eq!(vec!["P1.json"], summary.unchanged);
+        assert!(!ppkt_dir.join("P2.json").exists(), "dry run must not write files");
+        let mut summary = CohortExportSummary::default();
+        compare_and_copy(&export_dir, &ppkt_dir, false, &mut summary).unwrap();
+        assert!(ppkt_dir.join("P2.json").exists());
+    }
+}
+
+// endregion: --- Tests
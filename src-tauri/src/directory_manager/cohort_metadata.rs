@@ -0,0 +1,209 @@
+//! Metadata about the cohorts in Phenopacket Store
+//!
+//! We extract a summary of each cohort template (`*_individuals.json`) and of the phenopackets
+//! of a cohort so that the repository page can show an overview table without opening each cohort.
+//! The files are parsed as generic JSON so that templates written with older schema versions can
+//! still be summarized.
+
+use std::{collections::BTreeMap, fs, path::Path, time::UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Suffix of the cohort template files in phenopacket-store, e.g., `FBN1_MFS_individuals.json`
+pub const TEMPLATE_SUFFIX: &str = "_individuals.json";
+
+
+/// Summary of one cohort template (`*_individuals.json`)
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateMetadata {
+    pub file_name: String,
+    pub cohort_type: String,
+    pub cohort_acronym: Option<String>,
+    pub gene_symbols: Vec<String>,
+    pub disease_ids: Vec<String>,
+    pub disease_labels: Vec<String>,
+    pub n_rows: usize,
+    pub hpo_version: String,
+    /// Seconds since the Unix epoch
+    pub last_modified: Option<u64>,
+    /// ORCID identifiers of the biocurators, in chronological order of curation
+    pub curator_orcids: Vec<String>,
+}
+
+impl TemplateMetadata {
+    /// Parse the metadata from the JSON of a cohort template
+    pub fn from_json(file_name: &str, json: &Value) -> Self {
+        let diseases = json["diseaseList"].as_array().cloned().unwrap_or_default();
+        let mut gene_symbols: Vec<String> = diseases
+            .iter()
+            .flat_map(|d| d["geneTranscriptList"].as_array().cloned().unwrap_or_default())
+            .filter_map(|g| g["geneSymbol"].as_str().map(String::from))
+            .collect();
+        gene_symbols.sort();
+        gene_symbols.dedup();
+        let mut curator_orcids: Vec<String> = Vec::new();
+        for event in json["curationHistory"].as_array().cloned().unwrap_or_default() {
+            if let Some(orcid) = event["orcid"].as_str() {
+                if !curator_orcids.iter().any(|o| o == orcid) {
+                    curator_orcids.push(orcid.to_string());
+                }
+            }
+        }
+        Self {
+            file_name: file_name.to_string(),
+            cohort_type: json["cohortType"].as_str().unwrap_or("mendelian").to_string(),
+            cohort_acronym: json["cohortAcronym"].as_str().map(String::from),
+            gene_symbols,
+            disease_ids: diseases.iter().filter_map(|d| d["diseaseId"].as_str().map(String::from)).collect(),
+            disease_labels: diseases.iter().filter_map(|d| d["diseaseLabel"].as_str().map(String::from)).collect(),
+            n_rows: json["rows"].as_array().map_or(0, |rows| rows.len()),
+            hpo_version: json["hpoVersion"].as_str().unwrap_or_default().to_string(),
+            last_modified: None,
+            curator_orcids,
+        }
+    }
+
+    /// Read and parse a cohort template file
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let json = read_json_file(path)?;
+        Ok(Self::from_template_json(path, &json))
+    }
+
+    /// Create the metadata of the template at `path` that has already been parsed into `json`
+    pub fn from_template_json(path: &Path, json: &Value) -> Self {
+        let file_name = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+        let mut metadata = Self::from_json(&file_name, json);
+        metadata.last_modified = last_modified(path);
+        metadata
+    }
+}
+
+/// Read and parse a JSON file; the error message includes the file name
+pub fn read_json_file(path: &Path) -> Result<Value, String> {
+    let file_name = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Could not read {}: {}", file_name, e))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| format!("Could not parse {}: {}", file_name, e))
+}
+
+/// Get the modification time of a file in seconds since the Unix epoch
+pub fn last_modified(path: &Path) -> Option<u64> {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+}
+
+/// Get the disease identifiers of a GA4GH phenopacket (`diseases[].term.id`)
+pub fn phenopacket_disease_ids(json: &Value) -> Vec<String> {
+    json["diseases"]
+        .as_array()
+        .map(|diseases| {
+            diseases
+                .iter()
+                .filter_map(|d| d["term"]["id"].as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Get the gene symbols of the interpretations of a GA4GH phenopacket (gene descriptors and gene contexts of variants)
+pub fn phenopacket_gene_symbols(json: &Value) -> Vec<String> {
+    let mut symbols: Vec<String> = Vec::new();
+    for interpretation in json["interpretations"].as_array().into_iter().flatten() {
+        for genomic in interpretation["diagnosis"]["genomicInterpretations"].as_array().into_iter().flatten() {
+            let symbol = genomic["gene"]["symbol"]
+                .as_str()
+                .or_else(|| genomic["variantInterpretation"]["variationDescriptor"]["geneContext"]["symbol"].as_str());
+            if let Some(symbol) = symbol {
+                if !symbols.iter().any(|s| s == symbol) {
+                    symbols.push(symbol.to_string());
+                }
+            }
+        }
+    }
+    symbols
+}
+
+/// Count the phenopackets per disease identifier.
+///
+/// `phenopackets` are pairs of file name and parsed JSON. Phenopackets without a disease
+/// are counted under `"unknown"`.
+pub fn count_phenopackets_by_disease(phenopackets: &[(String, Value)]) -> BTreeMap<String, usize> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for (_, json) in phenopackets {
+        let disease_ids = phenopacket_disease_ids(json);
+        if disease_ids.is_empty() {
+            *counts.entry("unknown".to_string()).or_default() += 1;
+        }
+        for disease_id in disease_ids {
+            *counts.entry(disease_id).or_default() += 1;
+        }
+    }
+    counts
+}
+
+
+/// One row of the overview table of the repository
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CohortOverviewRow {
+    pub cohort_name: String,
+    pub template_file: Option<String>,
+    pub cohort_type: String,
+    pub gene_symbols: Vec<String>,
+    pub disease_ids: Vec<String>,
+    pub n_rows: usize,
+    pub n_phenopackets: usize,
+    pub hpo_version: String,
+    pub last_modified: Option<u64>,
+    pub curator_orcids: Vec<String>,
+    pub n_warnings: usize,
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_metadata() {
+        let json = serde_json::json!({
+            "cohortType": "melded",
+            "diseaseList": [
+                {"diseaseId": "OMIM:154700", "diseaseLabel": "Marfan syndrome",
+                 "geneTranscriptList": [{"geneSymbol": "FBN1"}]},
+                {"diseaseId": "OMIM:616914", "diseaseLabel": "Stiff skin syndrome",
+                 "geneTranscriptList": [{"geneSymbol": "FBN1"}]}
+            ],
+            "rows": [{}, {}, {}],
+            "hpoVersion": "2025-05-06",
+            "curationHistory": [
+                {"orcid": "0000-0002-0736-9199", "date": "2024-01-01"},
+                {"orcid": "0000-0002-0736-9199", "date": "2025-01-01"}
+            ]
+        });
+        let metadata = TemplateMetadata::from_json("FBN1_individuals.json", &json);
+        assert_eq!("melded", metadata.cohort_type);
+        assert_eq!(vec!["FBN1"], metadata.gene_symbols);
+        assert_eq!(vec!["OMIM:154700", "OMIM:616914"], metadata.disease_ids);
+        assert_eq!(3, metadata.n_rows);
+        assert_eq!(vec!["0000-0002-0736-9199"], metadata.curator_orcids);
+    }
+
+    #[test]
+    fn test_phenopacket_disease_ids() {
+        let json = serde_json::json!({"diseases": [{"term": {"id": "OMIM:154700", "label": "Marfan syndrome"}}]});
+        assert_eq!(vec!["OMIM:154700"], phenopacket_disease_ids(&json));
+        assert!(phenopacket_disease_ids(&serde_json::json!({})).is_empty());
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,252 @@
+//! Compare the template rows of a cohort with the files in its `phenopackets` directory
+//!
+//! The repository QC of ga4ghphetools checks for unexpected files, mode of inheritance mismatches,
+//! export errors and missing HPO terms. Here, we additionally check whether the phenopackets of a cohort
+//! are in sync with its template(s). To determine the expected phenopackets, we export each template
+//! into a temporary directory (the same way as [`batch_export`](crate::directory_manager::batch_export))
+//! and compare the result with the existing files. We report
+//! - orphaned phenopackets (no corresponding template row)
+//! - missing phenopackets (a template row without a phenopacket)
+//! - stale phenopackets (older than the template)
+//! - phenopackets whose content differs from what a fresh export would produce
+
+use std::{collections::{BTreeMap, BTreeSet}, fs, path::{Path, PathBuf}, sync::Arc};
+
+use ontolius::ontology::csr::FullCsrOntology;
+use serde::{Deserialize, Serialize};
+
+use crate::directory_manager::{batch_export::{self, ExportJob}, cohort_metadata::{last_modified, read_json_file}};
+
+
+/// Result of the phenopacket QC of one cohort directory
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhenopacketQcReport {
+    pub cohort_name: String,
+    /// Phenopackets that do not correspond to any row of the template(s)
+    pub orphaned: Vec<String>,
+    /// Phenopackets that would be created from the template(s) but do not exist
+    pub missing: Vec<String>,
+    /// Phenopackets that are older than the template they were created from
+    pub stale: Vec<String>,
+    /// Phenopackets whose content differs from a fresh export
+    pub differing: Vec<String>,
+    /// Errors that prevented a template from being checked
+    pub errors: Vec<String>,
+}
+
+impl PhenopacketQcReport {
+    pub fn is_clean(&self) -> bool {
+        self.orphaned.is_empty()
+            && self.missing.is_empty()
+            && self.stale.is_empty()
+            && self.differing.is_empty()
+            && self.errors.is_empty()
+    }
+}
+
+/// Summary of the phenopacket QC over the entire repository (only cohorts with findings are listed)
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepositoryPhenopacketQc {
+    pub n_cohorts_checked: usize,
+    pub cohorts: Vec<PhenopacketQcReport>,
+}
+
+
+/// A freshly exported phenopacket together with the modification time of its template
+struct ExpectedPhenopacket {
+    path: PathBuf,
+    template_modified: Option<u64>,
+}
+
+/// Check one cohort. All jobs must refer to the same cohort (a cohort can have more than one template).
+pub fn check_cohort(
+    cohort_name: &str,
+    jobs: &[&ExportJob],
+    hpo: Arc<FullCsrOntology>,
+    orcid: &str,
+) -> PhenopacketQcReport {
+    let mut report = PhenopacketQcReport { cohort_name: cohort_name.to_string(), ..Default::default() };
+    let tmp_dir = match batch_export::temp_export_dir(&format!("qc_{}", cohort_name)) {
+        Ok(tmp_dir) => tmp_dir,
+        Err(e) => {
+            report.errors.push(e);
+            return report;
+        }
+    };
+    let mut expected: BTreeMap<String, ExpectedPhenopacket> = BTreeMap::new();
+    for (i, job) in jobs.iter().enumerate() {
+        let out_dir = tmp_dir.path().join(i.to_string());
+        if let Err(e) = batch_export::export_to_directory(&job.template_path, &out_dir, hpo.clone(), orcid) {
+            report.errors.push(format!("{}: {}", job.template_path.display(), e));
+            continue;
+        }
+        let template_modified = last_modified(&job.template_path);
+        for file_name in json_files(&out_dir) {
+            let path = out_dir.join(&file_name);
+            expected.insert(file_name, ExpectedPhenopacket { path, template_modified });
+        }
+    }
+    // If a template could not be exported, we do not know which phenopackets are expected
+    let can_detect_orphans = report.errors.is_empty();
+    if let Some(ppkt_dir) = jobs.first().map(|job| &job.phenopacket_dir) {
+        compare_with_expected(ppkt_dir, &expected, can_detect_orphans, &mut report);
+    }
+    report
+}
+
+/// Compare the existing phenopackets in `ppkt_dir` with the expected (freshly exported) phenopackets
+fn compare_with_expected(
+    ppkt_dir: &Path,
+    expected: &BTreeMap<String, ExpectedPhenopacket>,
+    can_detect_orphans: bool,
+    report: &mut PhenopacketQcReport,
+) {
+    let existing: BTreeSet<String> = json_files(ppkt_dir).into_iter().collect();
+    for (file_name, fresh) in expected {
+        if !existing.contains(file_name) {
+            report.missing.push(file_name.clone());
+            continue;
+        }
+        let path = ppkt_dir.join(file_name);
+        if let (Some(ppkt_modified), Some(template_modified)) = (last_modified(&path), fresh.template_modified) {
+            if ppkt_modified < template_modified {
+                report.stale.push(file_name.clone());
+            }
+        }
+        match (read_json_file(&path), read_json_file(&fresh.path)) {
+            (Ok(old), Ok(new)) => {
+                if !batch_export::phenopacket_contents_equivalent(&old, &new) {
+                    report.differing.push(file_name.clone());
+                }
+            }
+            (Err(e), _) | (_, Err(e)) => report.errors.push(e),
+        }
+    }
+    if can_detect_orphans {
+        report.orphaned = existing
+            .into_iter()
+            .filter(|file_name| !expected.contains_key(file_name))
+            .collect();
+    }
+}
+
+/// Names of the JSON files in a directory (sorted); an unreadable directory is treated as empty
+fn json_files(dir: &Path) -> Vec<String> {
+    let mut files: Vec<String> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .filter(|f| f.ends_with(".json") && !f.starts_with('.'))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// Group export jobs by cohort, preserving the order of the cohorts
+fn jobs_by_cohort(jobs: &[ExportJob]) -> Vec<(String, Vec<&ExportJob>)> {
+    let mut grouped: Vec<(String, Vec<&ExportJob>)> = Vec::new();
+    for job in jobs {
+        match grouped.last_mut() {
+            Some((name, cohort_jobs)) if name == &job.cohort_name => cohort_jobs.push(job),
+            _ => grouped.push((job.cohort_name.clone(), vec![job])),
+        }
+    }
+    grouped
+}
+
+/// Check the phenopackets of all cohorts; `progress_cb` is called with (current, total) after each cohort
+pub fn check_repository<F>(
+    jobs: &[ExportJob],
+    hpo: Arc<FullCsrOntology>,
+    orcid: &str,
+    mut progress_cb: F,
+) -> RepositoryPhenopacketQc
+where
+    F: FnMut(u32, u32),
+{
+    let grouped = jobs_by_cohort(jobs);
+    let total = grouped.len() as u32;
+    let mut result = RepositoryPhenopacketQc { n_cohorts_checked: grouped.len(), ..Default::default() };
+    for (i, (cohort_name, cohort_jobs)) in grouped.iter().enumerate() {
+        let report = check_cohort(cohort_name, cohort_jobs, hpo.clone(), orcid);
+        if !report.is_clean() {
+            result.cohorts.push(report);
+        }
+        progress_cb(i as u32 + 1, total);
+    }
+    result
+}
+
+/// Delete orphaned phenopackets of a cohort.
+///
+/// The QC is repeated first, and only those `file_names` that are still orphans are deleted, so that a
+/// phenopacket is never removed if the template changed in the meantime. Returns the deleted file names.
+pub fn remove_orphaned_phenopackets(
+    cohort_name: &str,
+    jobs: &[ExportJob],
+    file_names: &[String],
+    hpo: Arc<FullCsrOntology>,
+    orcid: &str,
+) -> Result<Vec<String>, String> {
+    let cohort_jobs: Vec<&ExportJob> = jobs.iter().filter(|job| job.cohort_name == cohort_name).collect();
+    let ppkt_dir = match cohort_jobs.first() {
+        Some(job) => job.phenopacket_dir.clone(),
+        None => { return Err(format!("No template found for cohort '{}'", cohort_name)); }
+    };
+    let report = check_cohort(cohort_name, &cohort_jobs, hpo, orcid);
+    if !report.errors.is_empty() {
+        return Err(format!("Cannot determine orphaned phenopackets of '{}': {}", cohort_name, report.errors.join("; ")));
+    }
+    let mut removed: Vec<String> = Vec::new();
+    for file_name in file_names.iter().filter(|f| report.orphaned.contains(f)) {
+        let path = ppkt_dir.join(file_name);
+        fs::remove_file(&path).map_err(|e| format!("Could not remove {}: {}", path.display(), e))?;
+        removed.push(file_name.clone());
+    }
+    Ok(removed)
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_compare_with_expected() {
+        let base = TempDir::new().unwrap();
+        let fresh_dir = base.path().join("fresh");
+        let ppkt_dir = base.path().join("phenopackets");
+        fs::create_dir_all(&fresh_dir).unwrap();
+        fs::create_dir_all(&ppkt_dir).unwrap();
+        fs::write(fresh_dir.join("P1.json"), r#"{"id": "P1", "metaData": {"createdBy": "A"}}"#).unwrap();
+        fs::write(fresh_dir.join("P2.json"), r#"{"id": "P2", "subject": {"sex": "FEMALE"}}"#).unwrap();
+        fs::write(fresh_dir.join("P3.json"), r#"{"id": "P3"}"#).unwrap();
+        fs::write(ppkt_dir.join("P1.json"), r#"{"id": "P1", "metaData": {"createdBy": "B"}}"#).unwrap();
+        fs::write(ppkt_dir.join("P2.json"), r#"{"id": "P2", "subject": {"sex": "MALE"}}"#).unwrap();
+        fs::write(ppkt_dir.join("P4.json"), r#"{"id": "P4"}"#).unwrap();
+        let expected: BTreeMap<String, ExpectedPhenopacket> = json_files(&fresh_dir)
+            .into_iter()
+            .map(|f| (f.clone(), ExpectedPhenopacket { path: fresh_dir.join(&f), template_modified: None }))
+            .collect();
+        let mut report = PhenopacketQcReport::default();
+        compare_with_expected(&ppkt_dir, &expected, true, &mut report);
+        assert_eq!(vec!["P4.json"], report.orphaned);
+        assert_eq!(vec!["P3.json"], report.missing);
+        assert_eq!(vec!["P2.json"], report.differing);
+        assert!(report.stale.is_empty());
+        let mut report = PhenopacketQcReport::default();
+        compare_with_expected(&ppkt_dir, &expected, false, &mut report);
+        assert!(report.orphaned.is_empty());
+    }
+}
+
+// endregion: --- Tests
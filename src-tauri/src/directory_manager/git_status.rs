@@ -0,0 +1,271 @@
+//! Git-aware operations on Phenopacket Store
+//!
+//! Curation of a cohort ends with committing the edited template and the regenerated phenopackets to
+//! phenopacket-store. We use libgit2 (via the git2 crate) rather than calling the git executable, so that
+//! this works offline and does not depend on the git installation of the user. We only create local commits;
+//! pushing and opening pull requests are left to the usual git tools.
+
+use std::{collections::{BTreeMap, BTreeSet}, path::{Path, PathBuf}};
+
+use git2::{Repository, Status, StatusOptions};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{directory_manager::{cohort_metadata, layout::RepositoryLayout, DirectoryManager}, util::pubmed_retrieval::PubmedRetriever};
+
+
+/// Uncommitted changes of one cohort directory (paths are relative to the cohort directory)
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CohortGitStatus {
+    pub cohort_name: String,
+    pub modified: Vec<String>,
+    /// Files that are not yet tracked (or newly staged)
+    pub untracked: Vec<String>,
+    pub deleted: Vec<String>,
+}
+
+/// Git status of Phenopacket Store
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepositoryGitStatus {
+    /// False if the store directory is not inside a git work tree (all other fields are then empty)
+    pub is_git_repository: bool,
+    pub branch: Option<String>,
+    /// Only cohorts with uncommitted changes are listed
+    pub cohorts: Vec<CohortGitStatus>,
+}
+
+/// Result of committing the changes of a cohort
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CohortCommit {
+    pub commit_id: String,
+    pub message: String,
+    /// Committed files, relative to the root of the git repository
+    pub files: Vec<String>,
+}
+
+/// One changed file of the work tree
+struct ChangedFile {
+    cohort_name: String,
+    /// Path relative to the root of the git repository
+    repo_path: String,
+    status: Status,
+}
+
+/// The git repository that contains `base_dir` and the path of `base_dir` relative to its work tree
+fn open_repository(base_dir: &Path) -> Result<(Repository, PathBuf), String> {
+    let repo = Repository::discover(base_dir)
+        .map_err(|e| format!("{} is not in a git repository: {}", base_dir.display(), e.message()))?;
+    let workdir = repo.workdir()
+        .ok_or_else(|| "Git repository has no work tree".to_string())?
+        .canonicalize()
+        .map_err(|e| e.to_string())?;
+    let base_dir = base_dir.canonicalize().map_err(|e| e.to_string())?;
+    let relative = base_dir.strip_prefix(&workdir)
+        .map_err(|_| format!("{} is outside of the git work tree", base_dir.display()))?
+        .to_path_buf();
+    Ok((repo, relative))
+}
+
+/// Get all changed files of the work tree that belong to a cohort directory
+fn changed_files(repo: &Repository, base_relative: &Path, layout: &RepositoryLayout) -> Result<Vec<ChangedFile>, String> {
+    let mut options = StatusOptions::new();
+    options.include_untracked(true).recurse_untracked_dirs(true).include_ignored(false);
+    let statuses = repo.statuses(Some(&mut options)).map_err(|e| e.message().to_string())?;
+    let mut changed = Vec::new();
+    for entry in statuses.iter() {
+        let repo_path = match entry.path() {
+            Some(path) => path.to_string(),
+            None => continue,
+        };
+        if let Some(cohort_name) = DirectoryManager::cohort_name_for_path(base_relative, Path::new(&repo_path)) {
+            if layout.is_ignored_folder(&cohort_name) {
+                continue;
+            }
+            changed.push(ChangedFile { cohort_name, repo_path, status: entry.status() });
+        }
+    }
+    Ok(changed)
+}
+
+/// Get the per-cohort git status of the store in `base_dir`
+pub fn get_git_status(base_dir: &Path, layout: &RepositoryLayout) -> Result<RepositoryGitStatus, String> {
+    let (repo, base_relative) = match open_repository(base_dir) {
+        Ok(opened) => opened,
+        Err(_) => { return Ok(RepositoryGitStatus::default()); }
+    };
+    let branch = repo.head().ok().and_then(|head| head.shorthand().map(String::from));
+    let mut cohorts: BTreeMap<String, CohortGitStatus> = BTreeMap::new();
+    for file in changed_files(&repo, &base_relative, layout)? {
+        let cohort = cohorts.entry(file.cohort_name.clone()).or_insert_with(|| CohortGitStatus {
+            cohort_name: file.cohort_name.clone(),
+            ..Default::default()
+        });
+        let cohort_prefix = base_relative.join(&file.cohort_name);
+        let path = Path::new(&file.repo_path)
+            .strip_prefix(&cohort_prefix)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| file.repo_path.clone());
+        if file.status.intersects(Status::WT_DELETED | Status::INDEX_DELETED) {
+            cohort.deleted.push(path);
+        } else if file.status.intersects(Status::WT_NEW | Status::INDEX_NEW) {
+            cohort.untracked.push(path);
+        } else {
+            cohort.modified.push(path);
+        }
+    }
+    Ok(RepositoryGitStatus {
+        is_git_repository: true,
+        branch,
+        cohorts: cohorts.into_values().collect(),
+    })
+}
+
+/// PMIDs of the rows of a cohort template
+fn template_pmids(json: &Value) -> BTreeSet<String> {
+    json["rows"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|row| row["individualData"]["pmid"].as_str())
+        .filter_map(PubmedRetriever::extract_pmid)
+        .map(|n| format!("PMID:{}", n))
+        .collect()
+}
+
+/// Parse the version of a file in the HEAD commit (None if the file or HEAD does not exist)
+fn head_json(repo: &Repository, repo_path: &str) -> Option<Value> {
+    let tree = repo.head().ok()?.peel_to_commit().ok()?.tree().ok()?;
+    let blob = tree.get_path(Path::new(repo_path)).ok()?.to_object(repo).ok()?.peel_to_blob().ok()?;
+    serde_json::from_slice(blob.content()).ok()
+}
+
+/// Create a commit message such as
+/// `FBN1: update cohort (42 rows)` followed by the added PMIDs and the number of changed files
+fn create_commit_message(repo: &Repository, layout: &RepositoryLayout, cohort_name: &str, files: &[ChangedFile]) -> String {
+    let workdir = repo.workdir().map(Path::to_path_buf).unwrap_or_default();
+    let mut n_rows: Option<usize> = None;
+    let mut added_pmids: BTreeSet<String> = BTreeSet::new();
+    for file in files.iter().filter(|f| !f.status.intersects(Status::WT_DELETED | Status::INDEX_DELETED)) {
+        let file_name = Path::new(&file.repo_path).file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+        if !layout.is_template_file(&file_name) {
+            continue;
+        }
+        let current = match cohort_metadata::read_json_file(&workdir.join(&file.repo_path)) {
+            Ok(json) => json,
+            Err(_) => continue,
+        };
+        let previous = head_json(repo, &file.repo_path).map(|json| template_pmids(&json)).unwrap_or_default();
+        added_pmids.extend(template_pmids(&current).difference(&previous).cloned());
+        n_rows = Some(n_rows.unwrap_or(0) + current["rows"].as_array().map_or(0, |rows| rows.len()));
+    }
+    let mut message = match n_rows {
+        Some(n) => format!("{}: update cohort ({} rows)", cohort_name, n),
+        None => format!("{}: update cohort", cohort_name),
+    };
+    message.push_str("\n\n");
+    if !added_pmids.is_empty() {
+        message.push_str(&format!("Added {}\n", added_pmids.into_iter().collect::<Vec<_>>().join(", ")));
+    }
+    message.push_str(&format!("{} file(s) changed\n", files.len()));
+    message
+}
+
+/// Stage all changes of the cohort directory and create a local commit.
+///
+/// If `message` is None, a message is generated from the cohort name, the PMIDs added to the template,
+/// and the number of rows. The author is taken from the git configuration (user.name, user.email).
+/// An error is returned if changes outside of the cohort directory are already staged, because they
+/// would otherwise be included in the commit.
+pub fn commit_cohort(
+    base_dir: &Path,
+    layout: &RepositoryLayout,
+    cohort_name: &str,
+    message: Option<String>,
+) -> Result<CohortCommit, String> {
+    let (repo, base_relative) = open_repository(base_dir)?;
+    let all_changes = changed_files(&repo, &base_relative, layout)?;
+    let staged = Status::INDEX_NEW | Status::INDEX_MODIFIED | Status::INDEX_DELETED | Status::INDEX_RENAMED | Status::INDEX_TYPECHANGE;
+    if let Some(other) = all_changes.iter().find(|f| f.cohort_name != cohort_name && f.status.intersects(staged)) {
+        return Err(format!("Cannot commit '{}' because {} is already staged", cohort_name, other.repo_path));
+    }
+    let files: Vec<ChangedFile> = all_changes.into_iter().filter(|f| f.cohort_name == cohort_name).collect();
+    if files.is_empty() {
+        return Err(format!("No changes to commit in '{}'", cohort_name));
+    }
+    let signature = repo.signature()
+        .map_err(|e| format!("Could not determine git author (set user.name and user.email): {}", e.message()))?;
+    let mut index = repo.index().map_err(|e| e.message().to_string())?;
+    for file in &files {
+        let path = Path::new(&file.repo_path);
+        if file.status.contains(Status::WT_DELETED) {
+            index.remove_path(path).map_err(|e| e.message().to_string())?;
+        } else if !file.status.contains(Status::INDEX_DELETED) {
+            index.add_path(path).map_err(|e| e.message().to_string())?;
+        }
+    }
+    index.write().map_err(|e| e.message().to_string())?;
+    let tree_id = index.write_tree().map_err(|e| e.message().to_string())?;
+    let tree = repo.find_tree(tree_id).map_err(|e| e.message().to_string())?;
+    let message = message
+        .filter(|m| !m.trim().is_empty())
+        .unwrap_or_else(|| create_commit_message(&repo, layout, cohort_name, &files));
+    let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    let commit_id = repo.commit(Some("HEAD"), &signature, &signature, &message, &tree, &parents)
+        .map_err(|e| e.message().to_string())?;
+    Ok(CohortCommit {
+        commit_id: commit_id.to_string(),
+        message,
+        files: files.into_iter().map(|f| f.repo_path).collect(),
+    })
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_status_and_commit() {
+        let tmp = TempDir::new().unwrap();
+        let workdir = tmp.path();
+        let base_dir = workdir.join("notebooks");
+        fs::create_dir_all(base_dir.join("FBN1/input")).unwrap();
+        fs::create_dir_all(base_dir.join("FBN1/phenopackets")).unwrap();
+        fs::create_dir_all(base_dir.join("TTN/input")).unwrap();
+        let repo = Repository::init(workdir).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test Curator").unwrap();
+        config.set_str("user.email", "curator@example.org").unwrap();
+        let template = serde_json::json!({"rows": [
+            {"individualData": {"pmid": "PMID:123", "individualId": "A"}},
+            {"individualData": {"pmid": "PMID:456", "individualId": "B"}}
+        ]});
+        fs::write(base_dir.join("FBN1/input/FBN1_individuals.json"), template.to_string()).unwrap();
+        fs::write(base_dir.join("FBN1/phenopackets/PMID_123_A.json"), "{}").unwrap();
+        fs::write(base_dir.join("TTN/input/TTN_individuals.json"), "{}").unwrap();
+
+        let status = get_git_status(&base_dir, &RepositoryLayout::default()).unwrap();
+        assert!(status.is_git_repository);
+        assert_eq!(2, status.cohorts.len());
+        assert_eq!("FBN1", status.cohorts[0].cohort_name);
+        assert_eq!(vec!["input/FBN1_individuals.json", "phenopackets/PMID_123_A.json"], status.cohorts[0].untracked);
+
+        let commit = commit_cohort(&base_dir, &RepositoryLayout::default(), "FBN1", None).unwrap();
+        assert_eq!(2, commit.files.len());
+        assert!(commit.message.starts_with("FBN1: update cohort (2 rows)"));
+        assert!(commit.message.contains("Added PMID:123, PMID:456"));
+        let status = get_git_status(&base_dir, &RepositoryLayout::default()).unwrap();
+        assert_eq!(vec!["TTN"], status.cohorts.iter().map(|c| c.cohort_name.as_str()).collect::<Vec<_>>());
+    }
+}
+
+// endregion: --- Tests
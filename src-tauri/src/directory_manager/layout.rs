@@ -0,0 +1,150 @@
+//! Layout of the cohort directories of a phenopacket repository
+//!
+//! Phenopacket Store has one directory per cohort (e.g., `notebooks/FBN1`) with an `input` subdirectory for
+//! the template and a `phenopackets` subdirectory for the generated phenopackets. Other groups organize their
+//! collections differently, so the names of the subdirectories, the pattern of the template file names, and
+//! folders that are not cohorts can be configured (see the `repositories` entry of the settings).
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::directory_manager::cohort_metadata::TEMPLATE_SUFFIX;
+
+
+/// Where the templates and phenopackets are found within each cohort directory
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RepositoryLayout {
+    /// Subdirectory with the curation input (templates, Excel, TSV)
+    pub input_subdir: String,
+    /// Glob pattern for the file names of the cohort templates within the input subdirectory
+    pub template_glob: String,
+    /// Subdirectory with the generated phenopackets
+    pub phenopacket_subdir: String,
+    /// Names of directories in the base directory that are not cohorts (e.g., `scripts`)
+    pub ignored_folders: Vec<String>,
+}
+
+impl Default for RepositoryLayout {
+    /// The layout of phenopacket-store
+    fn default() -> Self {
+        Self {
+            input_subdir: "input".to_string(),
+            template_glob: format!("*{}", TEMPLATE_SUFFIX),
+            phenopacket_subdir: "phenopackets".to_string(),
+            ignored_folders: vec![],
+        }
+    }
+}
+
+impl RepositoryLayout {
+    /// Check that the subdirectories are relative paths within the cohort directory and that the glob is valid
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, subdir) in [("input", &self.input_subdir), ("phenopacket", &self.phenopacket_subdir)] {
+            let path = Path::new(subdir);
+            if subdir.trim().is_empty() {
+                return Err(format!("The {} subdirectory must not be empty", name));
+            }
+            if path.is_absolute() || path.components().any(|c| c.as_os_str() == "..") {
+                return Err(format!("The {} subdirectory must be inside the cohort directory: '{}'", name, subdir));
+            }
+        }
+        if self.input_subdir == self.phenopacket_subdir {
+            return Err("The input and phenopacket subdirectories must be different".to_string());
+        }
+        glob::Pattern::new(&self.template_glob)
+            .map_err(|e| format!("Invalid template pattern '{}': {}", self.template_glob, e))?;
+        Ok(())
+    }
+
+    /// Does the file name match the template pattern?
+    pub fn is_template_file(&self, file_name: &str) -> bool {
+        glob::Pattern::new(&self.template_glob)
+            .map(|pattern| pattern.matches(file_name))
+            .unwrap_or(false)
+    }
+
+    pub fn is_ignored_folder(&self, folder_name: &str) -> bool {
+        self.ignored_folders.iter().any(|f| f == folder_name)
+    }
+
+    /// Is this top-level entry of a cohort directory one of the configured subdirectories?
+    pub fn is_cohort_subdir(&self, entry_name: &str) -> bool {
+        first_component(&self.input_subdir) == entry_name || first_component(&self.phenopacket_subdir) == entry_name
+    }
+}
+
+/// First component of a relative path such as `data/input`
+fn first_component(subdir: &str) -> String {
+    Path::new(subdir)
+        .components()
+        .next()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+
+/// A phenopacket repository registered in the settings
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepositoryConfig {
+    /// Name used to select the repository, e.g., `phenopacket-store`
+    pub name: String,
+    /// Directory that contains one subdirectory per cohort (e.g., `phenopacket-store/notebooks`)
+    pub base_dir: String,
+    #[serde(default)]
+    pub layout: RepositoryLayout,
+}
+
+impl RepositoryConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Repository name must not be empty".to_string());
+        }
+        if !Path::new(&self.base_dir).is_dir() {
+            return Err(format!("Repository directory not found: '{}'", self.base_dir));
+        }
+        self.layout.validate()
+    }
+}
+
+/// The registered repositories and the name of the selected one
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisteredRepositories {
+    pub repositories: Vec<RepositoryConfig>,
+    /// None if the repository is derived from the path of the current template
+    pub active_repository: Option<String>,
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        let layout = RepositoryLayout::default();
+        assert!(layout.validate().is_ok());
+        assert!(layout.is_template_file("FBN1_MFS_individuals.json"));
+        assert!(!layout.is_template_file("PMID_123_A.json"));
+        let custom = RepositoryLayout {
+            input_subdir: "curation/templates".to_string(),
+            template_glob: "*.template.json".to_string(),
+            phenopacket_subdir: "ppkt".to_string(),
+            ignored_folders: vec!["scripts".to_string()],
+        };
+        assert!(custom.validate().is_ok());
+        assert!(custom.is_template_file("cohort.template.json"));
+        assert!(custom.is_cohort_subdir("curation"));
+        assert!(custom.is_ignored_folder("scripts"));
+        let invalid = RepositoryLayout { phenopacket_subdir: "../elsewhere".to_string(), ..Default::default() };
+        assert!(invalid.validate().is_err());
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,582 @@
+//! Module to manage the various directories with phenopacket templates
+//! 
+//! This module is meant to manage the directory structure of the 
+//! phenopacket store, in which we have one directory for each cohort, and
+//! each directory has the following structure
+//! - phenopackets (directory with the generated phenopackets)
+//! - input (directory with Excel or TSV files with the curation data)
+//! - currently, Jupyter notebooks to create phenopackets from the input (this will be replaced once this software is mature)
+//!
+
+pub mod batch_export;
+pub mod cohort_metadata;
+pub mod cohort_qc;
+pub mod git_status;
+pub mod layout;
+pub mod release;
+pub mod search;
+pub mod statistics;
+pub mod watcher;
+
+use std::{collections::{BTreeMap, BTreeSet}, fmt, fs, path::{Component, Path, PathBuf}};
+
+use serde_json::Value;
+
+use crate::directory_manager::{batch_export::ExportJob, cohort_metadata::{CohortOverviewRow, TemplateMetadata}, layout::RepositoryLayout, search::{IndexedRecord, SearchQuery, SearchResult}, statistics::{RepositoryStatistics, TemplateStatistics}, watcher::RepositoryChangeEvent};
+
+/// Extensions of files that are expected in the cohort directory itself (notebooks, README)
+const EXPECTED_COHORT_FILE_EXTENSIONS: [&str; 2] = ["ipynb", "md"];
+
+
+/// Problems found while scanning a cohort directory.
+///
+/// These are reported to the user but do not prevent the rest of the repository from being indexed.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(tag = "kind", content = "detail", rename_all = "camelCase")]
+pub enum CohortWarning {
+    /// The cohort has no input subdirectory (`input` in phenopacket-store)
+    MissingInputDirectory,
+    /// The cohort has no phenopacket subdirectory (`phenopackets` in phenopacket-store)
+    MissingPhenopacketDirectory,
+    /// A file or directory that does not belong in the cohort directory
+    StrayFile(String),
+    /// Neither input files nor phenopackets were found
+    EmptyCohort,
+    /// A directory could not be read (e.g., permissions)
+    Unreadable(String),
+    /// A template or phenopacket file could not be parsed
+    InvalidFile(String),
+}
+
+impl fmt::Display for CohortWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CohortWarning::MissingInputDirectory => write!(f, "Missing input directory"),
+            CohortWarning::MissingPhenopacketDirectory => write!(f, "Missing phenopacket directory"),
+            CohortWarning::StrayFile(name) => write!(f, "Unexpected file or directory '{}'", name),
+            CohortWarning::EmptyCohort => write!(f, "No input files or phenopackets found"),
+            CohortWarning::Unreadable(msg) => write!(f, "Could not read directory: {}", msg),
+            CohortWarning::InvalidFile(msg) => write!(f, "Invalid file: {}", msg),
+        }
+    }
+}
+
+
+/// Representation of one of the directories in Phenopacket Store (e.g., FBN1)
+/// and of the contents of this directory
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct CohortDirectory {
+    cohort_name: String,
+    cohort_directory_path: PathBuf,
+    /// Subdirectories of the layout with which the directory was scanned
+    #[serde(skip)]
+    input_subdir: String,
+    #[serde(skip)]
+    phenopacket_subdir: String,
+    input_files: Vec<String>,
+    phenopacket_files: Vec<String>,
+    /// Metadata of the cohort templates (`*_individuals.json`) in the input directory
+    templates: Vec<TemplateMetadata>,
+    /// Number of phenopackets per disease identifier
+    phenopacket_counts: BTreeMap<String, usize>,
+    /// Problems found while scanning the directory
+    warnings: Vec<CohortWarning>,
+    /// Searchable records for the template rows and phenopackets (not part of the JSON representation)
+    #[serde(skip)]
+    search_records: Vec<IndexedRecord>,
+    /// Data of the templates needed for the repository statistics (not part of the JSON representation)
+    #[serde(skip)]
+    statistics: Vec<TemplateStatistics>,
+}
+
+impl CohortDirectory {
+
+    /// Scan the cohort directory using the layout of phenopacket-store (see [`Self::with_layout`])
+    pub fn new(cohort_name: String, base_dir: &PathBuf) -> Result<Self, String> {
+        Self::with_layout(cohort_name, base_dir, &RepositoryLayout::default())
+    }
+
+    /// Scan the cohort directory. Missing subdirectories and unexpected files are recorded as
+    /// warnings; an error is only returned if the cohort directory itself does not exist.
+    pub fn with_layout(cohort_name: String, base_dir: &Path, layout: &RepositoryLayout) -> Result<Self, String> {
+        let cohort_dir = base_dir.join(&cohort_name);
+        if ! cohort_dir.exists() {
+            return Err(format!("Could not find cohort directory for '{}'", &cohort_name));
+        }
+        let mut warnings: Vec<CohortWarning> = Vec::new();
+        let input_files = Self::get_input_files(&cohort_dir, layout).unwrap_or_else(|w| {
+            warnings.push(w);
+            vec![]
+        });
+        let ppkt_files = Self::get_phenopacket_files(&cohort_dir, layout).unwrap_or_else(|w| {
+            warnings.push(w);
+            vec![]
+        });
+        match Self::get_stray_entries(&cohort_dir, layout) {
+            Ok(stray) => warnings.extend(stray.into_iter().map(CohortWarning::StrayFile)),
+            Err(e) => warnings.push(CohortWarning::Unreadable(e)),
+        }
+        if input_files.is_empty() && ppkt_files.is_empty() {
+            warnings.push(CohortWarning::EmptyCohort);
+        }
+        let mut templates: Vec<TemplateMetadata> = Vec::new();
+        let mut search_records: Vec<IndexedRecord> = Vec::new();
+        let mut statistics: Vec<TemplateStatistics> = Vec::new();
+        for file_name in input_files.iter().filter(|f| layout.is_template_file(f)) {
+            let path = cohort_dir.join(&layout.input_subdir).join(file_name);
+            match cohort_metadata::read_json_file(&path) {
+                Ok(json) => {
+                    let metadata = TemplateMetadata::from_template_json(&path, &json);
+                    statistics.push(TemplateStatistics::from_json(&cohort_name, &json, metadata.last_modified));
+                    templates.push(metadata);
+                    search_records.extend(IndexedRecord::from_template(&cohort_name, file_name, &json));
+                },
+                Err(e) => warnings.push(CohortWarning::InvalidFile(e)),
+            }
+        }
+        let mut phenopackets: Vec<(String, Value)> = Vec::new();
+        for file_name in ppkt_files.iter().filter(|f| f.ends_with(".json")) {
+            match cohort_metadata::read_json_file(&cohort_dir.join(&layout.phenopacket_subdir).join(file_name)) {
+                Ok(json) => phenopackets.push((file_name.clone(), json)),
+                Err(e) => warnings.push(CohortWarning::InvalidFile(e)),
+            }
+        }
+        let phenopacket_counts = cohort_metadata::count_phenopackets_by_disease(&phenopackets);
+        search_records.extend(phenopackets
+            .iter()
+            .map(|(file_name, json)| IndexedRecord::from_phenopacket(&cohort_name, file_name, json)));
+        Ok(Self { 
+            cohort_name: cohort_name, 
+            cohort_directory_path: cohort_dir,
+            input_subdir: layout.input_subdir.clone(),
+            phenopacket_subdir: layout.phenopacket_subdir.clone(),
+            input_files: input_files,
+            phenopacket_files: ppkt_files,
+            templates,
+            phenopacket_counts,
+            warnings,
+            search_records,
+            statistics,
+        })
+    }
+
+    pub fn get_input_files(base_dir: &Path, layout: &RepositoryLayout) -> Result<Vec<String>, CohortWarning> {
+        let input_file_dir = base_dir.join(&layout.input_subdir);
+        if !input_file_dir.is_dir() {
+            return Err(CohortWarning::MissingInputDirectory);
+        }
+        Self::get_non_hidden_files_in_directory(&input_file_dir).map_err(CohortWarning::Unreadable)
+    }
+
+    pub fn get_phenopacket_files(base_dir: &Path, layout: &RepositoryLayout) -> Result<Vec<String>, CohortWarning> {
+        let ppkt_file_dir = base_dir.join(&layout.phenopacket_subdir);
+        if !ppkt_file_dir.is_dir() {
+            return Err(CohortWarning::MissingPhenopacketDirectory);
+        }
+        Self::get_non_hidden_files_in_directory(&ppkt_file_dir).map_err(CohortWarning::Unreadable)
+    }
+
+    /// Get the names of entries in the cohort directory that are neither one of the expected
+    /// subdirectories nor a notebook/README file.
+    fn get_stray_entries(cohort_dir: &Path, layout: &RepositoryLayout) -> Result<Vec<String>, String> {
+        let mut stray: Vec<String> = Vec::new();
+        for entry in fs::read_dir(cohort_dir).map_err(|e|e.to_string())? {
+            let entry = entry.map_err(|e|e.to_string())?;
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if file_name.starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            let expected = if path.is_dir() {
+                layout.is_cohort_subdir(&file_name)
+            } else {
+                path.extension().map_or(false, |ext| {
+                    EXPECTED_COHORT_FILE_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str())
+                })
+            };
+            if !expected {
+                stray.push(file_name);
+            }
+        }
+        stray.sort();
+        Ok(stray)
+    }
+
+    pub fn warnings(&self) -> &[CohortWarning] {
+        &self.warnings
+    }
+
+    pub fn templates(&self) -> &[TemplateMetadata] {
+        &self.templates
+    }
+
+    /// Summarize the cohort as one row of the repository overview table.
+    ///
+    /// If there is more than one template in the input directory (which should not happen), the
+    /// most recently modified one is used.
+    pub fn overview_row(&self) -> CohortOverviewRow {
+        let template = self.templates.iter().max_by_key(|t| t.last_modified);
+        CohortOverviewRow {
+            cohort_name: self.cohort_name.clone(),
+            template_file: template.map(|t| t.file_name.clone()),
+            cohort_type: template.map(|t| t.cohort_type.clone()).unwrap_or_default(),
+            gene_symbols: template.map(|t| t.gene_symbols.clone()).unwrap_or_default(),
+            disease_ids: template.map(|t| t.disease_ids.clone()).unwrap_or_default(),
+            n_rows: template.map_or(0, |t| t.n_rows),
+            n_phenopackets: self.phenopacket_files.iter().filter(|f| f.ends_with(".json")).count(),
+            hpo_version: template.map(|t| t.hpo_version.clone()).unwrap_or_default(),
+            last_modified: template.and_then(|t| t.last_modified),
+            curator_orcids: template.map(|t| t.curator_orcids.clone()).unwrap_or_default(),
+            n_warnings: self.warnings.len(),
+        }
+    }
+
+
+    /// Get all file paths in the indicated directory, skipping hidden files
+    fn get_non_hidden_files_in_directory(dirpath: &Path) -> Result<Vec<String>, String> {
+        if ! dirpath.exists() {
+            return Err(format!("Could not find directory '{}'", &dirpath.to_string_lossy()));
+        } 
+        let mut file_list: Vec<String> = Vec::new();
+        for entry in fs::read_dir(dirpath).map_err(|e|e.to_string())? {
+            let entry = entry.map_err(|e|e.to_string())?;
+            let metadata = entry.metadata().map_err(|e|e.to_string())?;
+            let file_name = entry.file_name();
+            let file_name_str = file_name.to_string_lossy();
+            if file_name_str.starts_with('.') {
+                continue;
+            }
+            if metadata.is_file() {
+                file_list.push(file_name_str.to_string());
+            }
+        }
+        file_list.sort();
+        Ok(file_list)
+    }
+}
+
+
+impl fmt::Display for CohortDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Cohort: {}, Path: {}",
+            self.cohort_name,
+            self.cohort_directory_path.display()
+        )
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct DirectoryManager {
+    /// Base directory where all cohort subdirectories are contained
+    /// In phenopacket-store, this would be `phenopacket-store/notebooks``
+    base_dir: PathBuf,
+    cohort_directories: Vec<CohortDirectory>,
+    /// Number of cohorts with at least one warning
+    n_cohorts_with_warnings: usize,
+    /// Problems that do not belong to a single cohort (e.g., an unreadable entry of `base_dir`)
+    scan_warnings: Vec<String>,
+    #[serde(skip)]
+    layout: RepositoryLayout,
+}
+
+
+
+
+
+
+impl DirectoryManager {
+    /// Scan all cohort subdirectories of `ppkt_directory` using the layout of phenopacket-store
+    pub fn new(ppkt_directory: impl Into<String>) -> Result<Self, String> {
+        Self::with_layout(ppkt_directory, RepositoryLayout::default())
+    }
+
+    /// Scan all cohort subdirectories of `ppkt_directory`.
+    ///
+    /// Problems with individual cohorts are recorded as warnings in the corresponding [`CohortDirectory`],
+    /// so that one malformed folder does not prevent the rest of the repository from being indexed.
+    /// Problems that cannot be attributed to a cohort are recorded in [`Self::scan_warnings`].
+    pub fn with_layout(ppkt_directory: impl Into<String>, layout: RepositoryLayout) -> Result<Self, String> {
+        let dirpath: PathBuf = Path::new(&ppkt_directory.into()).to_path_buf();
+        let mut scan_warnings: Vec<String> = Vec::new();
+        let mut subdirs: Vec<String> = Self::get_subdirectories(&dirpath, &mut scan_warnings)?;
+        subdirs.retain(|dir| !layout.is_ignored_folder(dir));
+        subdirs.sort();
+        let mut cohorts: Vec<CohortDirectory> = Vec::new();
+        for dir in subdirs {
+            match CohortDirectory::with_layout(dir.clone(), &dirpath, &layout) {
+                Ok(cohort) => cohorts.push(cohort),
+                Err(e) => scan_warnings.push(format!("Skipping cohort '{}': {}", dir, e)),
+            }
+        }
+        let n_cohorts_with_warnings = cohorts.iter().filter(|c| !c.warnings.is_empty()).count();
+        Ok(Self{ 
+            base_dir: dirpath,
+            cohort_directories: cohorts,
+            n_cohorts_with_warnings,
+            scan_warnings,
+            layout,
+        })
+    }
+
+    pub fn cohort_directories(&self) -> &[CohortDirectory] {
+        &self.cohort_directories
+    }
+
+    pub fn base_dir(&self) -> &Path {
+        &self.base_dir
+    }
+
+    pub fn layout(&self) -> &RepositoryLayout {
+        &self.layout
+    }
+
+    /// Problems found while scanning the repository that do not belong to a single cohort
+    pub fn scan_warnings(&self) -> &[String] {
+        &self.scan_warnings
+    }
+
+    pub fn add_scan_warning(&mut self, warning: String) {
+        self.scan_warnings.push(warning);
+    }
+
+    /// Get the name of the cohort directory that contains `path` (the first component of
+    /// `path` relative to `base_dir`), or None if `path` is outside of `base_dir` or hidden.
+    pub fn cohort_name_for_path(base_dir: &Path, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(base_dir).ok()?;
+        if relative.components().any(|c| c.as_os_str().to_string_lossy().starts_with('.')) {
+            return None;
+        }
+        match relative.components().next()? {
+            Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+            _ => None,
+        }
+    }
+
+    /// Rescan the indicated cohort directories, e.g., after a file system notification.
+    ///
+    /// Cohorts whose directory no longer exists are removed from the index, and new
+    /// cohort directories are added. Returns a summary of what changed.
+    pub fn refresh_cohorts(&mut self, cohort_names: &BTreeSet<String>) -> RepositoryChangeEvent {
+        let mut change = RepositoryChangeEvent::default();
+        for name in cohort_names.iter().filter(|name| !self.layout.is_ignored_folder(name)) {
+            let position = self.cohort_directories.iter().position(|c| &c.cohort_name == name);
+            if self.base_dir.join(name).is_dir() {
+                let cohort = match CohortDirectory::with_layout(name.clone(), &self.base_dir, &self.layout) {
+                    Ok(cohort) => cohort,
+                    Err(e) => {
+                        let warning = format!("Could not rescan cohort '{}': {}", name, e);
+                        self.scan_warnings.push(warning.clone());
+                        change.warnings.push(warning);
+                        continue;
+                    }
+                };
+                match position {
+                    Some(i) => self.cohort_directories[i] = cohort,
+                    None => {
+                        let i = self.cohort_directories.partition_point(|c| &c.cohort_name < name);
+                        self.cohort_directories.insert(i, cohort);
+                    }
+                }
+                change.changed_cohorts.push(name.clone());
+            } else if let Some(i) = position {
+                self.cohort_directories.remove(i);
+                change.removed_cohorts.push(name.clone());
+            }
+        }
+        self.n_cohorts_with_warnings = self.cohort_directories.iter().filter(|c| !c.warnings.is_empty()).count();
+        change
+    }
+
+    /// Get the names of the (non-hidden) subdirectories; entries that cannot be read are reported in `warnings`
+    fn get_subdirectories(dirpath: &Path, warnings: &mut Vec<String>) -> Result<Vec<String>, String> {
+        let mut dir_list: Vec<String> = Vec::new();
+        for entry in fs::read_dir(dirpath).map_err(|e|e.to_string())? {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    warnings.push(format!("Could not read entry in {}: {}", dirpath.display(), e));
+                    continue;
+                }
+            };
+            let file_name = entry.file_name();
+            let file_name_str = file_name.to_string_lossy();
+            if file_name_str.starts_with('.') {
+                continue;
+            }
+            if entry.path().is_dir() {
+                dir_list.push(file_name_str.to_string());
+            }
+        }
+        Ok(dir_list)
+    }
+
+    /// Get a JSON representation of all input files currently in the Phenopacket Store
+    /// 
+    /// Assuming we point at the directory with all of the cohorts as subdirectory (currently, "notebooks"),
+    /// then there is one directory for each cohort (usually a gene). Each of these directories may have
+    /// a Jupyter notebook for the pyphetools project (which we ignore), as well as an 'input' directory with
+    /// Excel templates (or TSV). a directory called 'phenopackets' with the generate GA4GH phenopackets
+    /// 
+    /// - *Returns
+    ///    a JSON Value object representing the hierarchy of these files
+    pub fn get_json(&self) -> Result<serde_json::Value, String> {
+        serde_json::to_value(self).map_err(|e| e.to_string())
+    }
+
+    /// Search the template rows (and optionally phenopackets) of all cohorts.
+    ///
+    /// See [`IndexedRecord::matches`] for the meaning of `term_matches`.
+    pub fn search<F>(&self, query: &SearchQuery, term_matches: &F) -> SearchResult
+    where
+        F: Fn(&str, &str) -> bool,
+    {
+        let records = self.cohort_directories.iter().flat_map(|c| c.search_records.iter());
+        search::search(records, query, term_matches)
+    }
+
+    /// Get one summary row per cohort for the overview table of the repository page
+    pub fn get_overview(&self) -> Vec<CohortOverviewRow> {
+        self.cohort_directories.iter().map(CohortDirectory::overview_row).collect()
+    }
+
+    /// Aggregate the statistics of all cohort templates
+    pub fn get_statistics(&self) -> RepositoryStatistics {
+        RepositoryStatistics::from_templates(self.cohort_directories.iter().flat_map(|c| c.statistics.iter()))
+    }
+
+    /// Get the cohort name and path of each phenopacket in the repository
+    pub fn phenopacket_paths(&self) -> Vec<(String, PathBuf)> {
+        self.cohort_directories
+            .iter()
+            .flat_map(|c| c.phenopacket_files.iter().filter(|f| f.ends_with(".json")).map(move |f| {
+                (c.cohort_name.clone(), c.cohort_directory_path.join(&c.phenopacket_subdir).join(f))
+            }))
+            .collect()
+    }
+
+    /// Get one export job for each cohort template in the repository (in the order of the cohorts)
+    pub fn export_jobs(&self) -> Vec<ExportJob> {
+        self.cohort_directories
+            .iter()
+            .flat_map(|c| c.templates.iter().map(move |t| ExportJob {
+                cohort_name: c.cohort_name.clone(),
+                template_path: c.cohort_directory_path.join(&c.input_subdir).join(&t.file_name),
+                phenopacket_dir: c.cohort_directory_path.join(&c.phenopacket_subdir),
+            }))
+            .collect()
+    }
+}
+
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::to_string_pretty;
+    use tempfile::TempDir;
+
+    #[test]
+    #[ignore = "directory path needs to be adjusted to visualize JSON (but function/test is working)"]
+    fn show_ppkt_store_directories()  {
+        let dirpath = "/../phenopacket-store/notebooks/"; // adjust as needed to test/visualize the JSON
+        let dirman = DirectoryManager::new(dirpath).unwrap(); 
+        let json = dirman.get_json();
+        assert!(json.is_ok());
+        let json = json.unwrap();
+        let pretty_json = to_string_pretty(&json).unwrap();
+        print!("{}", pretty_json);
+    }
+
+    /// Create a small phenopacket-store-like directory that is deleted when the returned value is dropped
+    fn create_test_store() -> TempDir {
+        let store = TempDir::new().unwrap();
+        let base = store.path();
+        fs::create_dir_all(base.join("FBN1/input")).unwrap();
+        fs::create_dir_all(base.join("FBN1/phenopackets")).unwrap();
+        fs::write(base.join("FBN1/input/FBN1_MFS_individuals.json"), r#"{"rows": [{}, {}]}"#).unwrap();
+        fs::write(base.join("FBN1/phenopackets/PMID_1_P1.json"), r#"{"diseases": [{"term": {"id": "OMIM:154700"}}]}"#).unwrap();
+        fs::write(base.join("FBN1/FBN1.ipynb"), "{}").unwrap();
+        fs::create_dir_all(base.join("MALFORMED")).unwrap();
+        fs::write(base.join("MALFORMED/notes.xlsx"), "").unwrap();
+        store
+    }
+
+    #[test]
+    fn test_malformed_cohort_is_reported() {
+        let store = create_test_store();
+        let dirman = DirectoryManager::new(store.path().to_string_lossy()).unwrap();
+        assert_eq!(2, dirman.cohort_directories().len());
+        let fbn1 = &dirman.cohort_directories()[0];
+        assert!(fbn1.warnings().is_empty());
+        assert_eq!(vec!["FBN1_MFS_individuals.json".to_string()], fbn1.input_files);
+        let malformed = &dirman.cohort_directories()[1];
+        assert_eq!(
+            vec![
+                CohortWarning::MissingInputDirectory,
+                CohortWarning::MissingPhenopacketDirectory,
+                CohortWarning::StrayFile("notes.xlsx".to_string()),
+                CohortWarning::EmptyCohort,
+            ],
+            malformed.warnings()
+        );
+        assert_eq!(1, dirman.n_cohorts_with_warnings);
+        let overview = dirman.get_overview();
+        assert_eq!(2, overview[0].n_rows);
+        assert_eq!(1, overview[0].n_phenopackets);
+        assert_eq!(Some(&1), fbn1.phenopacket_counts.get("OMIM:154700"));
+        assert!(dirman.scan_warnings().is_empty());
+    }
+
+    #[test]
+    fn test_custom_layout() {
+        let store = TempDir::new().unwrap();
+        let base = store.path();
+        fs::create_dir_all(base.join("cohortA/templates")).unwrap();
+        fs::create_dir_all(base.join("cohortA/ppkt")).unwrap();
+        fs::create_dir_all(base.join("scripts")).unwrap();
+        fs::write(base.join("cohortA/templates/cohortA.template.json"), r#"{"rows": [{}]}"#).unwrap();
+        fs::write(base.join("cohortA/ppkt/P1.json"), "{}").unwrap();
+        let layout = RepositoryLayout {
+            input_subdir: "templates".to_string(),
+            template_glob: "*.template.json".to_string(),
+            phenopacket_subdir: "ppkt".to_string(),
+            ignored_folders: vec!["scripts".to_string()],
+        };
+        let dirman = DirectoryManager::with_layout(base.to_string_lossy(), layout).unwrap();
+        assert_eq!(1, dirman.cohort_directories().len());
+        let cohort = &dirman.cohort_directories()[0];
+        assert!(cohort.warnings().is_empty());
+        assert_eq!(1, cohort.templates().len());
+        let jobs = dirman.export_jobs();
+        assert_eq!(base.join("cohortA/ppkt"), jobs[0].phenopacket_dir);
+    }
+
+    #[test]
+    fn test_refresh_cohorts() {
+        let store = create_test_store();
+        let base = store.path();
+        let mut dirman = DirectoryManager::new(base.to_string_lossy()).unwrap();
+        fs::remove_dir_all(base.join("MALFORMED")).unwrap();
+        fs::create_dir_all(base.join("ACTA1/phenopackets")).unwrap();
+        let mut names = BTreeSet::new();
+        for path in [base.join("MALFORMED/notes.xlsx"), base.join("ACTA1/phenopackets"), base.join(".git/index")] {
+            if let Some(name) = DirectoryManager::cohort_name_for_path(base, &path) {
+                names.insert(name);
+            }
+        }
+        let change = dirman.refresh_cohorts(&names);
+        assert_eq!(vec!["ACTA1".to_string()], change.changed_cohorts);
+        assert_eq!(vec!["MALFORMED".to_string()], change.removed_cohorts);
+        let cohort_names: Vec<&str> = dirman.cohort_directories().iter().map(|c| c.cohort_name.as_str()).collect();
+        assert_eq!(vec!["ACTA1", "FBN1"], cohort_names);
+    }
+
+    
+}
+
+// endregion: --- Tests
\ No newline at end of file
@@ -0,0 +1,259 @@
+//! Create the release archive of Phenopacket Store
+//!
+//! A release consists of three files that are written to the chosen output directory
+//! - `phenopacket-store-{version}.zip` with all phenopackets (`{version}/{cohort}/{file}`)
+//! - `phenopacket-store-{version}-manifest.tsv` with cohort, disease, gene, file name and SHA-256 checksum
+//! - `phenopacket-store-{version}-summary.json` with the counts and the phenopackets that failed the checks
+//!
+//! Everything is created from the local files; phenopackets that fail the checks are not added to the archive.
+//! Only structural checks are done (see [`check_phenopacket_structure`]); the phenopackets are not validated
+//! against the Phenopacket schema or the HPO, and the summary says so.
+
+use std::{collections::BTreeSet, fs::{self, File}, io::Write, path::{Path, PathBuf}};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use crate::directory_manager::cohort_metadata::{phenopacket_disease_ids, phenopacket_gene_symbols};
+
+
+/// A phenopacket that was not included in the release
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvalidPhenopacket {
+    pub cohort_name: String,
+    pub file_name: String,
+    pub errors: Vec<String>,
+}
+
+/// Description of the checks done for a release, recorded in the summary
+pub const RELEASE_CHECKS: &str = "Structural checks only (id, subject id, phenotypic features, disease, metaData); \
+the phenopackets were not validated against the Phenopacket schema or the HPO";
+
+/// Summary of a release (also written as the summary JSON file)
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseSummary {
+    pub version: String,
+    pub archive_path: String,
+    pub manifest_path: String,
+    pub summary_path: String,
+    pub n_cohorts: usize,
+    pub n_phenopackets: usize,
+    pub n_diseases: usize,
+    pub n_genes: usize,
+    /// The checks that were done before a phenopacket was added to the archive
+    pub checks: String,
+    pub invalid: Vec<InvalidPhenopacket>,
+}
+
+/// One line of the manifest
+struct ManifestEntry {
+    cohort_name: String,
+    disease_ids: Vec<String>,
+    genes: Vec<String>,
+    file_name: String,
+    sha256: String,
+}
+
+
+/// Check that the elements every phenopacket of the release must have are present (no schema or HPO validation)
+pub fn check_phenopacket_structure(json: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    if json["id"].as_str().is_none_or(|id| id.trim().is_empty()) {
+        errors.push("missing id".to_string());
+    }
+    if json["subject"]["id"].as_str().is_none_or(|id| id.trim().is_empty()) {
+        errors.push("missing subject id".to_string());
+    }
+    if json["phenotypicFeatures"].as_array().is_none_or(|f| f.is_empty()) {
+        errors.push("no phenotypic features".to_string());
+    }
+    if phenopacket_disease_ids(json).is_empty() {
+        errors.push("no disease".to_string());
+    }
+    let meta_data = &json["metaData"];
+    if meta_data["createdBy"].as_str().is_none_or(|c| c.trim().is_empty()) {
+        errors.push("missing metaData.createdBy".to_string());
+    }
+    if meta_data["phenopacketSchemaVersion"].as_str().is_none() {
+        errors.push("missing metaData.phenopacketSchemaVersion".to_string());
+    }
+    if meta_data["resources"].as_array().is_none_or(|r| r.is_empty()) {
+        errors.push("missing metaData.resources".to_string());
+    }
+    errors
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+fn check_version(version: &str) -> Result<(), String> {
+    let valid = !version.trim().is_empty()
+        && version.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid release version '{}' (use letters, digits, '.', '-' and '_')", version))
+    }
+}
+
+fn write_manifest(path: &Path, entries: &[ManifestEntry]) -> Result<(), String> {
+    let mut tsv = String::from("cohort\tdisease\tgene\tfile\tsha256\n");
+    for entry in entries {
+        tsv.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\n",
+            entry.cohort_name,
+            entry.disease_ids.join(";"),
+            entry.genes.join(";"),
+            entry.file_name,
+            entry.sha256
+        ));
+    }
+    fs::write(path, tsv).map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+/// Create the release archive, manifest and summary in `out_dir`.
+///
+/// # Arguments
+/// * `phenopackets` - cohort name and path of each phenopacket (see [`DirectoryManager::phenopacket_paths`](crate::directory_manager::DirectoryManager::phenopacket_paths))
+/// * `version` - release version, e.g., `0.1.25`
+/// * `out_dir` - directory to which the three files are written
+/// * `progress_cb` - called with (current, total) after each phenopacket
+pub fn create_release<F>(
+    phenopackets: &[(String, PathBuf)],
+    version: &str,
+    out_dir: &Path,
+    mut progress_cb: F,
+) -> Result<ReleaseSummary, String>
+where
+    F: FnMut(u32, u32),
+{
+    check_version(version)?;
+    let prefix = format!("phenopacket-store-{}", version);
+    let archive_path = out_dir.join(format!("{}.zip", prefix));
+    let manifest_path = out_dir.join(format!("{}-manifest.tsv", prefix));
+    let summary_path = out_dir.join(format!("{}-summary.json", prefix));
+    let file = File::create(&archive_path)
+        .map_err(|e| format!("Could not create {}: {}", archive_path.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut summary = ReleaseSummary {
+        version: version.to_string(),
+        archive_path: archive_path.to_string_lossy().to_string(),
+        manifest_path: manifest_path.to_string_lossy().to_string(),
+        summary_path: summary_path.to_string_lossy().to_string(),
+        checks: RELEASE_CHECKS.to_string(),
+        ..Default::default()
+    };
+    let mut entries: Vec<ManifestEntry> = Vec::new();
+    let mut cohorts: BTreeSet<&str> = BTreeSet::new();
+    let mut diseases: BTreeSet<String> = BTreeSet::new();
+    let mut genes: BTreeSet<String> = BTreeSet::new();
+    let total = phenopackets.len() as u32;
+    for (i, (cohort_name, path)) in phenopackets.iter().enumerate() {
+        progress_cb(i as u32 + 1, total);
+        let file_name = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+        let invalid = |errors: Vec<String>| InvalidPhenopacket {
+            cohort_name: cohort_name.clone(),
+            file_name: file_name.clone(),
+            errors,
+        };
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                summary.invalid.push(invalid(vec![e.to_string()]));
+                continue;
+            }
+        };
+        let json: Value = match serde_json::from_slice(&bytes) {
+            Ok(json) => json,
+            Err(e) => {
+                summary.invalid.push(invalid(vec![format!("invalid JSON: {}", e)]));
+                continue;
+            }
+        };
+        let errors = check_phenopacket_structure(&json);
+        if !errors.is_empty() {
+            summary.invalid.push(invalid(errors));
+            continue;
+        }
+        zip.start_file(format!("{}/{}/{}", version, cohort_name, file_name), options)
+            .and_then(|_| zip.write_all(&bytes).map_err(Into::into))
+            .map_err(|e| format!("Could not add {} to archive: {}", file_name, e))?;
+        let entry = ManifestEntry {
+            cohort_name: cohort_name.clone(),
+            disease_ids: phenopacket_disease_ids(&json),
+            genes: phenopacket_gene_symbols(&json),
+            file_name,
+            sha256: sha256_hex(&bytes),
+        };
+        cohorts.insert(cohort_name);
+        diseases.extend(entry.disease_ids.iter().cloned());
+        genes.extend(entry.genes.iter().cloned());
+        entries.push(entry);
+    }
+    zip.finish().map_err(|e| format!("Could not write {}: {}", archive_path.display(), e))?;
+    write_manifest(&manifest_path, &entries)?;
+    summary.n_cohorts = cohorts.len();
+    summary.n_phenopackets = entries.len();
+    summary.n_diseases = diseases.len();
+    summary.n_genes = genes.len();
+    let json = serde_json::to_string_pretty(&summary)
+        .map_err(|e| format!("Could not serialize release summary: {}", e))?;
+    fs::write(&summary_path, json)
+        .map_err(|e| format!("Could not write {}: {}", summary_path.display(), e))?;
+    Ok(summary)
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn phenopacket(id: &str) -> Value {
+        serde_json::json!({
+            "id": id,
+            "subject": {"id": "individual 1"},
+            "phenotypicFeatures": [{"type": {"id": "HP:0001250", "label": "Seizure"}}],
+            "diseases": [{"term": {"id": "OMIM:154700", "label": "Marfan syndrome"}}],
+            "interpretations": [{"diagnosis": {"genomicInterpretations": [
+                {"variantInterpretation": {"variationDescriptor": {"geneContext": {"symbol": "FBN1"}}}}
+            ]}}],
+            "metaData": {"createdBy": "0000-0002-0736-9199", "phenopacketSchemaVersion": "2.0",
+                         "resources": [{"id": "hp"}]}
+        })
+    }
+
+    #[test]
+    fn test_create_release() {
+        let tmp = TempDir::new().unwrap();
+        let base = tmp.path();
+        let valid = base.join("PMID_1_individual_1.json");
+        let invalid = base.join("PMID_1_individual_2.json");
+        fs::write(&valid, phenopacket("PMID_1_individual_1").to_string()).unwrap();
+        fs::write(&invalid, r#"{"id": "PMID_1_individual_2"}"#).unwrap();
+        let phenopackets = vec![("FBN1".to_string(), valid), ("FBN1".to_string(), invalid)];
+        let summary = create_release(&phenopackets, "0.1.0", base, |_, _| {}).unwrap();
+        assert_eq!(1, summary.n_phenopackets);
+        assert_eq!(1, summary.n_genes);
+        assert_eq!(1, summary.invalid.len());
+        assert_eq!(RELEASE_CHECKS, summary.checks);
+        let manifest = fs::read_to_string(&summary.manifest_path).unwrap();
+        let line = manifest.lines().nth(1).unwrap();
+        assert!(line.starts_with("FBN1\tOMIM:154700\tFBN1\tPMID_1_individual_1.json\t"));
+        assert_eq!(64, line.rsplit('\t').next().unwrap().len());
+        let archive = zip::ZipArchive::new(File::open(&summary.archive_path).unwrap()).unwrap();
+        assert_eq!(vec!["0.1.0/FBN1/PMID_1_individual_1.json"], archive.file_names().collect::<Vec<_>>());
+        assert!(create_release(&phenopackets, "../0.1.0", base, |_, _| {}).is_err());
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,420 @@
+//! Structured search across all cohorts of Phenopacket Store
+//!
+//! We index each row of the cohort templates and each phenopacket with the information curators
+//! typically search for (HPO terms, genes, variants, diseases, PMIDs, individual IDs and curators),
+//! so that questions such as "which cohorts contain HP:0001250 as excluded" can be answered without
+//! grepping JSON files. The records are created while scanning a cohort directory and are therefore
+//! kept current by the repository watcher.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::util::{json_fields::str_field, pubmed_retrieval::PubmedRetriever};
+
+
+/// Where an indexed record comes from
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RecordSource {
+    /// A row of a cohort template (`*_individuals.json`)
+    TemplateRow,
+    /// A GA4GH phenopacket in the `phenopackets` directory
+    Phenopacket,
+}
+
+/// The searchable data of one individual (template row or phenopacket)
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedRecord {
+    pub cohort_name: String,
+    pub file_name: String,
+    pub source: Option<RecordSource>,
+    /// Index of the row in the template (template rows only)
+    pub row_index: Option<usize>,
+    pub individual_id: String,
+    pub pmids: Vec<String>,
+    pub disease_ids: Vec<String>,
+    pub genes: Vec<String>,
+    /// All representations of the variants (variant key, transcript:c.HGVS, g.HGVS, p.HGVS, SV label)
+    pub variants: Vec<String>,
+    pub observed_terms: Vec<String>,
+    pub excluded_terms: Vec<String>,
+    pub curator_orcids: Vec<String>,
+}
+
+/// Whether to match terms that were observed, excluded, or either
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TermStatusFilter {
+    #[default]
+    Any,
+    Observed,
+    Excluded,
+}
+
+/// A structured query. All criteria that are set must match (logical AND).
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchQuery {
+    /// HPO term id, e.g., HP:0001250
+    pub hpo_id: Option<String>,
+    /// If true, annotations to descendants of `hpo_id` also match
+    pub include_descendants: bool,
+    pub term_status: TermStatusFilter,
+    /// Gene symbol, e.g., FBN1
+    pub gene: Option<String>,
+    /// Variant, e.g., NM_000138.5:c.1234G>A, c.1234G>A, or a variant key. Partial strings match.
+    pub variant: Option<String>,
+    pub disease_id: Option<String>,
+    pub pmid: Option<String>,
+    pub individual_id: Option<String>,
+    pub curator_orcid: Option<String>,
+    /// If false, only template rows are searched
+    pub include_phenopackets: bool,
+}
+
+/// One record that matched the query
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub cohort_name: String,
+    pub file_name: String,
+    pub source: Option<RecordSource>,
+    pub row_index: Option<usize>,
+    pub individual_id: String,
+    pub pmids: Vec<String>,
+    /// The HPO terms of the record that matched the term query (if any)
+    pub matched_terms: Vec<String>,
+}
+
+/// Result of a repository search
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    /// Names of the cohorts with at least one hit
+    pub cohorts: Vec<String>,
+    pub hits: Vec<SearchHit>,
+}
+
+
+fn push_unique(list: &mut Vec<String>, value: &str) {
+    let value = value.trim();
+    if !value.is_empty() && !list.iter().any(|v| v == value) {
+        list.push(value.to_string());
+    }
+}
+
+/// Normalize a PMID to the form `PMID:12345`
+fn normalize_pmid(pmid: &str) -> Option<String> {
+    PubmedRetriever::extract_pmid(pmid).map(|n| format!("PMID:{}", n))
+}
+
+/// Normalize an ORCID to the bare form `0000-0002-0736-9199` (removing `ORCID:` and URL prefixes)
+fn normalize_orcid(orcid: &str) -> String {
+    let orcid = orcid.trim();
+    let orcid = orcid.rsplit('/').next().unwrap_or(orcid);
+    let orcid = orcid.strip_prefix("ORCID:").or_else(|| orcid.strip_prefix("orcid:")).unwrap_or(orcid);
+    orcid.to_uppercase()
+}
+
+
+impl IndexedRecord {
+    /// Create one record per row of a cohort template
+    pub fn from_template(cohort_name: &str, file_name: &str, json: &Value) -> Vec<IndexedRecord> {
+        let headers: Vec<String> = json["hpoHeaders"]
+            .as_array()
+            .map(|h| h.iter().map(|d| str_field(d, "hpoId")).collect())
+            .unwrap_or_default();
+        let mut curator_orcids: Vec<String> = Vec::new();
+        for event in json["curationHistory"].as_array().into_iter().flatten() {
+            push_unique(&mut curator_orcids, &normalize_orcid(&str_field(event, "orcid")));
+        }
+        let mut cohort_genes: Vec<String> = Vec::new();
+        for disease in json["diseaseList"].as_array().into_iter().flatten() {
+            for gene in disease["geneTranscriptList"].as_array().into_iter().flatten() {
+                push_unique(&mut cohort_genes, &str_field(gene, "geneSymbol"));
+            }
+        }
+        let mut records = Vec::new();
+        for (row_index, row) in json["rows"].as_array().into_iter().flatten().enumerate() {
+            let individual = &row["individualData"];
+            let mut record = IndexedRecord {
+                cohort_name: cohort_name.to_string(),
+                file_name: file_name.to_string(),
+                source: Some(RecordSource::TemplateRow),
+                row_index: Some(row_index),
+                individual_id: str_field(individual, "individualId"),
+                curator_orcids: curator_orcids.clone(),
+                ..Default::default()
+            };
+            if let Some(pmid) = normalize_pmid(&str_field(individual, "pmid")) {
+                record.pmids.push(pmid);
+            }
+            for disease_id in row["diseaseIdList"].as_array().into_iter().flatten() {
+                push_unique(&mut record.disease_ids, disease_id.as_str().unwrap_or_default());
+            }
+            for (i, cell) in row["hpoData"].as_array().into_iter().flatten().enumerate() {
+                let hpo_id = match headers.get(i) {
+                    Some(id) => id,
+                    None => continue,
+                };
+                match cell["type"].as_str() {
+                    Some("Observed") | Some("OnsetAge") => record.observed_terms.push(hpo_id.clone()),
+                    Some("Excluded") => record.excluded_terms.push(hpo_id.clone()),
+                    _ => {}
+                }
+            }
+            if let Some(allele_map) = row["alleleCountMap"].as_object() {
+                for variant_key in allele_map.keys() {
+                    Self::add_variant(&mut record, json, variant_key);
+                }
+            }
+            if record.genes.is_empty() {
+                record.genes = cohort_genes.clone();
+            }
+            records.push(record);
+        }
+        records
+    }
+
+    /// Add the representations of the variant with the indicated key (from the variant maps of the cohort)
+    fn add_variant(record: &mut IndexedRecord, cohort: &Value, variant_key: &str) {
+        push_unique(&mut record.variants, variant_key);
+        let hgvs = &cohort["hgvsVariants"][variant_key];
+        if hgvs.is_object() {
+            let transcript = str_field(hgvs, "transcript");
+            let c_hgvs = str_field(hgvs, "hgvs");
+            push_unique(&mut record.variants, &format!("{}:{}", transcript, c_hgvs));
+            push_unique(&mut record.variants, &str_field(hgvs, "gHgvs"));
+            push_unique(&mut record.variants, &str_field(hgvs, "pHgvs"));
+            push_unique(&mut record.genes, &str_field(hgvs, "symbol"));
+        }
+        let sv = &cohort["structuralVariants"][variant_key];
+        if sv.is_object() {
+            push_unique(&mut record.variants, &str_field(sv, "label"));
+            push_unique(&mut record.genes, &str_field(sv, "geneSymbol"));
+        }
+        let intergenic = &cohort["intergenicVariants"][variant_key];
+        if intergenic.is_object() {
+            push_unique(&mut record.variants, &str_field(intergenic, "gHgvs"));
+            push_unique(&mut record.variants, &str_field(intergenic, "geneHgvs"));
+            push_unique(&mut record.genes, &str_field(intergenic, "symbol"));
+        }
+    }
+
+    /// Create a record from a GA4GH phenopacket (v2 JSON)
+    pub fn from_phenopacket(cohort_name: &str, file_name: &str, json: &Value) -> IndexedRecord {
+        let mut record = IndexedRecord {
+            cohort_name: cohort_name.to_string(),
+            file_name: file_name.to_string(),
+            source: Some(RecordSource::Phenopacket),
+            individual_id: str_field(&json["subject"], "id"),
+            ..Default::default()
+        };
+        for feature in json["phenotypicFeatures"].as_array().into_iter().flatten() {
+            let hpo_id = str_field(&feature["type"], "id");
+            if feature["excluded"].as_bool().unwrap_or(false) {
+                record.excluded_terms.push(hpo_id);
+            } else {
+                record.observed_terms.push(hpo_id);
+            }
+        }
+        for disease in json["diseases"].as_array().into_iter().flatten() {
+            push_unique(&mut record.disease_ids, &str_field(&disease["term"], "id"));
+        }
+        for interpretation in json["interpretations"].as_array().into_iter().flatten() {
+            let diagnosis = &interpretation["diagnosis"];
+            push_unique(&mut record.disease_ids, &str_field(&diagnosis["disease"], "id"));
+            for gi in diagnosis["genomicInterpretations"].as_array().into_iter().flatten() {
+                push_unique(&mut record.genes, &str_field(&gi["gene"], "symbol"));
+                let descriptor = &gi["variantInterpretation"]["variationDescriptor"];
+                push_unique(&mut record.genes, &str_field(&descriptor["geneContext"], "symbol"));
+                push_unique(&mut record.variants, &str_field(descriptor, "id"));
+                push_unique(&mut record.variants, &str_field(descriptor, "label"));
+                for expression in descriptor["expressions"].as_array().into_iter().flatten() {
+                    push_unique(&mut record.variants, &str_field(expression, "value"));
+                }
+            }
+        }
+        let meta_data = &json["metaData"];
+        for reference in meta_data["externalReferences"].as_array().into_iter().flatten() {
+            if let Some(pmid) = normalize_pmid(&str_field(reference, "id")) {
+                push_unique(&mut record.pmids, &pmid);
+            }
+        }
+        push_unique(&mut record.curator_orcids, &normalize_orcid(&str_field(meta_data, "createdBy")));
+        record
+    }
+
+    /// Check the record against the query.
+    ///
+    /// `term_matches(annotated_term, query_term)` decides whether an annotated HPO term matches the
+    /// queried term (e.g., equality, or being a descendant of it).
+    /// Returns the matching HPO terms (empty if no term was queried), or None if the record does not match.
+    pub fn matches<F>(&self, query: &SearchQuery, term_matches: &F) -> Option<Vec<String>>
+    where
+        F: Fn(&str, &str) -> bool,
+    {
+        let eq = |a: &str, b: &str| a.trim().eq_ignore_ascii_case(b.trim());
+        if !query.include_phenopackets && self.source == Some(RecordSource::Phenopacket) {
+            return None;
+        }
+        if let Some(gene) = &query.gene {
+            if !self.genes.iter().any(|g| eq(g, gene)) {
+                return None;
+            }
+        }
+        if let Some(variant) = &query.variant {
+            let variant = variant.trim().to_lowercase();
+            if !self.variants.iter().any(|v| v.to_lowercase().contains(&variant)) {
+                return None;
+            }
+        }
+        if let Some(disease_id) = &query.disease_id {
+            if !self.disease_ids.iter().any(|d| eq(d, disease_id)) {
+                return None;
+            }
+        }
+        if let Some(pmid) = &query.pmid {
+            let pmid = normalize_pmid(pmid)?;
+            if !self.pmids.contains(&pmid) {
+                return None;
+            }
+        }
+        if let Some(individual_id) = &query.individual_id {
+            if !eq(&self.individual_id, individual_id) {
+                return None;
+            }
+        }
+        if let Some(orcid) = &query.curator_orcid {
+            if !self.curator_orcids.contains(&normalize_orcid(orcid)) {
+                return None;
+            }
+        }
+        let mut matched_terms: Vec<String> = Vec::new();
+        if let Some(hpo_id) = &query.hpo_id {
+            let candidates: Vec<&String> = match query.term_status {
+                TermStatusFilter::Any => self.observed_terms.iter().chain(self.excluded_terms.iter()).collect(),
+                TermStatusFilter::Observed => self.observed_terms.iter().collect(),
+                TermStatusFilter::Excluded => self.excluded_terms.iter().collect(),
+            };
+            matched_terms = candidates
+                .into_iter()
+                .filter(|t| term_matches(t, hpo_id.trim()))
+                .cloned()
+                .collect();
+            if matched_terms.is_empty() {
+                return None;
+            }
+        }
+        Some(matched_terms)
+    }
+}
+
+/// Search the records and collect the hits
+pub fn search<'a, I, F>(records: I, query: &SearchQuery, term_matches: &F) -> SearchResult
+where
+    I: IntoIterator<Item = &'a IndexedRecord>,
+    F: Fn(&str, &str) -> bool,
+{
+    let mut result = SearchResult::default();
+    for record in records {
+        if let Some(matched_terms) = record.matches(query, term_matches) {
+            push_unique(&mut result.cohorts, &record.cohort_name);
+            result.hits.push(SearchHit {
+                cohort_name: record.cohort_name.clone(),
+                file_name: record.file_name.clone(),
+                source: record.source.clone(),
+                row_index: record.row_index,
+                individual_id: record.individual_id.clone(),
+                pmids: record.pmids.clone(),
+                matched_terms,
+            });
+        }
+    }
+    result
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template() -> Value {
+        serde_json::json!({
+            "diseaseList": [{"diseaseId": "OMIM:154700", "geneTranscriptList": [{"geneSymbol": "FBN1"}]}],
+            "hpoHeaders": [{"hpoId": "HP:0001250", "hpoLabel": "Seizure"}, {"hpoId": "HP:0001166", "hpoLabel": "Arachnodactyly"}],
+            "rows": [
+                {
+                    "individualData": {"pmid": "PMID:123", "individualId": "P1"},
+                    "diseaseIdList": ["OMIM:154700"],
+                    "alleleCountMap": {"c1234GtoA_FBN1_NM_000138v5": 1},
+                    "hpoData": [{"type": "Excluded"}, {"type": "OnsetAge", "data": "P2Y"}]
+                },
+                {
+                    "individualData": {"pmid": "PMID:456", "individualId": "P2"},
+                    "diseaseIdList": ["OMIM:154700"],
+                    "alleleCountMap": {},
+                    "hpoData": [{"type": "Observed"}, {"type": "Na"}]
+                }
+            ],
+            "hgvsVariants": {"c1234GtoA_FBN1_NM_000138v5": {"transcript": "NM_000138.5", "hgvs": "c.1234G>A", "symbol": "FBN1"}},
+            "curationHistory": [{"orcid": "0000-0002-0736-9199"}]
+        })
+    }
+
+    fn exact(a: &str, b: &str) -> bool {
+        a == b
+    }
+
+    #[test]
+    fn test_excluded_term_query() {
+        let records = IndexedRecord::from_template("FBN1", "FBN1_individuals.json", &template());
+        let query = SearchQuery {
+            hpo_id: Some("HP:0001250".to_string()),
+            term_status: TermStatusFilter::Excluded,
+            ..Default::default()
+        };
+        let result = search(&records, &query, &exact);
+        assert_eq!(1, result.hits.len());
+        assert_eq!("P1", result.hits[0].individual_id);
+        assert_eq!(vec!["FBN1"], result.cohorts);
+    }
+
+    #[test]
+    fn test_variant_and_curator_query() {
+        let records = IndexedRecord::from_template("FBN1", "FBN1_individuals.json", &template());
+        let query = SearchQuery {
+            variant: Some("NM_000138.5:c.1234G>A".to_string()),
+            curator_orcid: Some("https://orcid.org/0000-0002-0736-9199".to_string()),
+            ..Default::default()
+        };
+        let result = search(&records, &query, &exact);
+        assert_eq!(1, result.hits.len());
+        assert_eq!(Some(0), result.hits[0].row_index);
+    }
+
+    #[test]
+    fn test_phenopacket_record() {
+        let ppkt = serde_json::json!({
+            "subject": {"id": "P1"},
+            "phenotypicFeatures": [{"type": {"id": "HP:0001166"}}, {"type": {"id": "HP:0001250"}, "excluded": true}],
+            "interpretations": [{"diagnosis": {"disease": {"id": "OMIM:154700"}, "genomicInterpretations": [
+                {"variantInterpretation": {"variationDescriptor": {"geneContext": {"symbol": "FBN1"},
+                 "expressions": [{"syntax": "hgvs.c", "value": "NM_000138.5:c.1234G>A"}]}}}]}}],
+            "metaData": {"createdBy": "ORCID:0000-0002-0736-9199", "externalReferences": [{"id": "PMID:123"}]}
+        });
+        let record = IndexedRecord::from_phenopacket("FBN1", "PMID_123_P1.json", &ppkt);
+        let query = SearchQuery { pmid: Some("123".to_string()), gene: Some("fbn1".to_string()), ..Default::default() };
+        assert!(record.matches(&query, &exact).is_none(), "phenopackets are excluded by default");
+        let query = SearchQuery { include_phenopackets: true, ..query };
+        assert!(record.matches(&query, &exact).is_some());
+        assert_eq!(vec!["0000-0002-0736-9199"], record.curator_orcids);
+        assert_eq!(vec!["HP:0001250"], record.excluded_terms);
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,362 @@
+//! Summary statistics of Phenopacket Store
+//!
+//! For annual reports and grant applications we need counts of cohorts, individuals, diseases, genes,
+//! publications and HPO terms as well as a few distributions (terms per individual, variant types, sex,
+//! age of onset) and the growth of the repository over time. The statistics are computed from the cohort
+//! templates, which are the source of the phenopackets. The year in which a cohort was created is taken from
+//! the earliest entry of its curation history, or from the modification time of the template if there is none.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{hpo::onset::onset_category, util::{json_fields::str_field, pubmed_retrieval::PubmedRetriever}};
+
+
+/// The data of one template row that we need for the statistics
+#[derive(Clone, Debug, Default)]
+pub struct IndividualStatistics {
+    pub pmid: Option<String>,
+    pub sex: String,
+    pub age_of_onset: String,
+    pub disease_ids: Vec<String>,
+    pub observed_terms: Vec<String>,
+    pub excluded_terms: Vec<String>,
+}
+
+/// The data of one cohort template that we need for the statistics
+#[derive(Clone, Debug, Default)]
+pub struct TemplateStatistics {
+    pub cohort_name: String,
+    pub created_year: Option<i32>,
+    pub genes: Vec<String>,
+    pub hgvs_variants: Vec<String>,
+    pub structural_variants: Vec<String>,
+    pub intergenic_variants: Vec<String>,
+    pub individuals: Vec<IndividualStatistics>,
+}
+
+fn map_keys(json: &Value) -> Vec<String> {
+    json.as_object().map(|m| m.keys().cloned().collect()).unwrap_or_default()
+}
+
+impl TemplateStatistics {
+    /// Extract the statistics of a cohort template.
+    ///
+    /// `last_modified` (seconds since the Unix epoch) is used for the creation year if the template
+    /// has no curation history.
+    pub fn from_json(cohort_name: &str, json: &Value, last_modified: Option<u64>) -> Self {
+        let created_year = json["curationHistory"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|event| str_field(event, "date").get(0..4).and_then(|y| y.parse::<i32>().ok()))
+            .min()
+            .or_else(|| last_modified.map(year_from_unix_seconds));
+        let headers: Vec<String> = json["hpoHeaders"]
+            .as_array()
+            .map(|h| h.iter().map(|d| str_field(d, "hpoId")).collect())
+            .unwrap_or_default();
+        let mut genes: BTreeSet<String> = BTreeSet::new();
+        for disease in json["diseaseList"].as_array().into_iter().flatten() {
+            for gene in disease["geneTranscriptList"].as_array().into_iter().flatten() {
+                let symbol = str_field(gene, "geneSymbol");
+                if !symbol.is_empty() {
+                    genes.insert(symbol);
+                }
+            }
+        }
+        let mut individuals: Vec<IndividualStatistics> = Vec::new();
+        for row in json["rows"].as_array().into_iter().flatten() {
+            let individual = &row["individualData"];
+            let mut stats = IndividualStatistics {
+                pmid: PubmedRetriever::extract_pmid(&str_field(individual, "pmid")).map(|n| format!("PMID:{}", n)),
+                sex: str_field(individual, "sex"),
+                age_of_onset: str_field(individual, "ageOfOnset"),
+                disease_ids: row["diseaseIdList"]
+                    .as_array()
+                    .map(|ids| ids.iter().filter_map(|id| id.as_str().map(String::from)).collect())
+                    .unwrap_or_default(),
+                ..Default::default()
+            };
+            for (i, cell) in row["hpoData"].as_array().into_iter().flatten().enumerate() {
+                let hpo_id = match headers.get(i) {
+                    Some(id) => id.clone(),
+                    None => continue,
+                };
+                match cell["type"].as_str() {
+                    Some("Observed") | Some("OnsetAge") => stats.observed_terms.push(hpo_id),
+                    Some("Excluded") => stats.excluded_terms.push(hpo_id),
+                    _ => {}
+                }
+            }
+            individuals.push(stats);
+        }
+        Self {
+            cohort_name: cohort_name.to_string(),
+            created_year,
+            genes: genes.into_iter().collect(),
+            hgvs_variants: map_keys(&json["hgvsVariants"]),
+            structural_variants: map_keys(&json["structuralVariants"]),
+            intergenic_variants: map_keys(&json["intergenicVariants"]),
+            individuals,
+        }
+    }
+}
+
+/// Convert seconds since the Unix epoch to the (UTC) calendar year
+fn year_from_unix_seconds(seconds: u64) -> i32 {
+    date_from_unix_seconds(seconds).0
+}
+
+/// Convert seconds since the Unix epoch to the (UTC) calendar date (year, month, day)
+pub fn date_from_unix_seconds(seconds: u64) -> (i32, u32, u32) {
+    // civil_from_days (H. Hinnant)
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month_index >= 10 { 1 } else { 0 };
+    (year as i32, month as u32, day as u32)
+}
+
+/// Distribution of the number of observed HPO terms per individual
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TermsPerIndividual {
+    pub mean: f64,
+    pub median: f64,
+    pub max: usize,
+    /// Number of observed terms → number of individuals
+    pub histogram: BTreeMap<usize, usize>,
+}
+
+/// Number of distinct variants of each type
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariantTypeCounts {
+    pub hgvs: usize,
+    pub structural: usize,
+    pub intergenic: usize,
+}
+
+/// Size of the repository at the end of a year
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrowthPoint {
+    pub year: i32,
+    pub n_cohorts: usize,
+    pub n_individuals: usize,
+}
+
+/// Aggregated statistics of the entire repository
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepositoryStatistics {
+    pub n_cohorts: usize,
+    pub n_individuals: usize,
+    pub n_diseases: usize,
+    pub n_genes: usize,
+    pub n_pmids: usize,
+    /// Number of distinct HPO terms used as observed or excluded annotation
+    pub n_hpo_terms: usize,
+    pub terms_per_individual: TermsPerIndividual,
+    pub variant_types: VariantTypeCounts,
+    pub sex_distribution: BTreeMap<String, usize>,
+    pub onset_distribution: BTreeMap<String, usize>,
+    /// Cumulative number of cohorts and individuals per year (cohorts without a date are not included)
+    pub growth: Vec<GrowthPoint>,
+}
+
+impl RepositoryStatistics {
+    pub fn from_templates<'a>(templates: impl IntoIterator<Item = &'a TemplateStatistics>) -> Self {
+        let mut stats = RepositoryStatistics::default();
+        let mut cohorts: BTreeSet<&str> = BTreeSet::new();
+        let mut diseases: BTreeSet<&str> = BTreeSet::new();
+        let mut genes: BTreeSet<&str> = BTreeSet::new();
+        let mut pmids: BTreeSet<&str> = BTreeSet::new();
+        let mut terms: BTreeSet<&str> = BTreeSet::new();
+        let mut hgvs: BTreeSet<&str> = BTreeSet::new();
+        let mut structural: BTreeSet<&str> = BTreeSet::new();
+        let mut intergenic: BTreeSet<&str> = BTreeSet::new();
+        let mut term_counts: Vec<usize> = Vec::new();
+        // year → (cohorts, individuals) added in that year
+        let mut added_per_year: BTreeMap<i32, (BTreeSet<&str>, usize)> = BTreeMap::new();
+        for template in templates {
+            cohorts.insert(&template.cohort_name);
+            genes.extend(template.genes.iter().map(String::as_str));
+            hgvs.extend(template.hgvs_variants.iter().map(String::as_str));
+            structural.extend(template.structural_variants.iter().map(String::as_str));
+            intergenic.extend(template.intergenic_variants.iter().map(String::as_str));
+            if let Some(year) = template.created_year {
+                let entry = added_per_year.entry(year).or_default();
+                entry.0.insert(&template.cohort_name);
+                entry.1 += template.individuals.len();
+            }
+            for individual in &template.individuals {
+                stats.n_individuals += 1;
+                diseases.extend(individual.disease_ids.iter().map(String::as_str));
+                if let Some(pmid) = &individual.pmid {
+                    pmids.insert(pmid);
+                }
+                terms.extend(individual.observed_terms.iter().map(String::as_str));
+                terms.extend(individual.excluded_terms.iter().map(String::as_str));
+                term_counts.push(individual.observed_terms.len());
+                let sex = if individual.sex.is_empty() { "not available".to_string() } else { individual.sex.to_uppercase() };
+                *stats.sex_distribution.entry(sex).or_default() += 1;
+                *stats.onset_distribution.entry(onset_category(&individual.age_of_onset)).or_default() += 1;
+            }
+        }
+        stats.n_cohorts = cohorts.len();
+        stats.n_diseases = diseases.len();
+        stats.n_genes = genes.len();
+        stats.n_pmids = pmids.len();
+        stats.n_hpo_terms = terms.len();
+        stats.variant_types = VariantTypeCounts {
+            hgvs: hgvs.len(),
+            structural: structural.len(),
+            intergenic: intergenic.len(),
+        };
+        stats.terms_per_individual = Self::terms_per_individual(term_counts);
+        let mut seen_cohorts: BTreeSet<&str> = BTreeSet::new();
+        let mut n_individuals = 0;
+        for (year, (year_cohorts, year_individuals)) in added_per_year {
+            seen_cohorts.extend(year_cohorts);
+            n_individuals += year_individuals;
+            stats.growth.push(GrowthPoint { year, n_cohorts: seen_cohorts.len(), n_individuals });
+        }
+        stats
+    }
+
+    fn terms_per_individual(mut counts: Vec<usize>) -> TermsPerIndividual {
+        let mut distribution = TermsPerIndividual::default();
+        if counts.is_empty() {
+            return distribution;
+        }
+        counts.sort_unstable();
+        let n = counts.len();
+        distribution.mean = counts.iter().sum::<usize>() as f64 / n as f64;
+        distribution.median = if n.is_multiple_of(2) {
+            (counts[n / 2 - 1] + counts[n / 2]) as f64 / 2.0
+        } else {
+            counts[n / 2] as f64
+        };
+        distribution.max = counts[n - 1];
+        for count in counts {
+            *distribution.histogram.entry(count).or_default() += 1;
+        }
+        distribution
+    }
+
+    /// Write the statistics as CSV in long format with the columns `category,key,value`
+    pub fn to_csv(&self) -> String {
+        let mut rows: Vec<(String, String, String)> = vec![
+            ("summary".into(), "cohorts".into(), self.n_cohorts.to_string()),
+            ("summary".into(), "individuals".into(), self.n_individuals.to_string()),
+            ("summary".into(), "diseases".into(), self.n_diseases.to_string()),
+            ("summary".into(), "genes".into(), self.n_genes.to_string()),
+            ("summary".into(), "pmids".into(), self.n_pmids.to_string()),
+            ("summary".into(), "hpoTerms".into(), self.n_hpo_terms.to_string()),
+            ("termsPerIndividual".into(), "mean".into(), format!("{:.2}", self.terms_per_individual.mean)),
+            ("termsPerIndividual".into(), "median".into(), self.terms_per_individual.median.to_string()),
+            ("termsPerIndividual".into(), "max".into(), self.terms_per_individual.max.to_string()),
+            ("variantType".into(), "hgvs".into(), self.variant_types.hgvs.to_string()),
+            ("variantType".into(), "structural".into(), self.variant_types.structural.to_string()),
+            ("variantType".into(), "intergenic".into(), self.variant_types.intergenic.to_string()),
+        ];
+        for (n_terms, n_individuals) in &self.terms_per_individual.histogram {
+            rows.push(("termsPerIndividualHistogram".into(), n_terms.to_string(), n_individuals.to_string()));
+        }
+        for (sex, count) in &self.sex_distribution {
+            rows.push(("sex".into(), sex.clone(), count.to_string()));
+        }
+        for (onset, count) in &self.onset_distribution {
+            rows.push(("ageOfOnset".into(), onset.clone(), count.to_string()));
+        }
+        for point in &self.growth {
+            rows.push(("growthCohorts".into(), point.year.to_string(), point.n_cohorts.to_string()));
+            rows.push(("growthIndividuals".into(), point.year.to_string(), point.n_individuals.to_string()));
+        }
+        let mut csv = String::from("category,key,value\n");
+        for (category, key, value) in rows {
+            csv.push_str(&format!("{},{},{}\n", category, csv_field(&key), value));
+        }
+        csv
+    }
+}
+
+/// Quote a CSV field if needed
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(cohort: &str, date: &str, pmid: &str) -> Value {
+        serde_json::json!({
+            "hpoHeaders": [{"hpoId": "HP:0001250", "hpoLabel": "Seizure"}, {"hpoId": "HP:0001263", "hpoLabel": "Global developmental delay"}],
+            "rows": [
+                {"individualData": {"pmid": pmid, "individualId": "A", "sex": "F", "ageOfOnset": "P2Y"},
+                 "diseaseIdList": ["OMIM:100000"],
+                 "hpoData": [{"type": "Observed"}, {"type": "Excluded"}],
+                 "alleleCountMap": {}},
+                {"individualData": {"pmid": pmid, "individualId": "B", "sex": "M", "ageOfOnset": "Congenital onset"},
+                 "diseaseIdList": ["OMIM:100000"],
+                 "hpoData": [{"type": "Observed"}, {"type": "Observed"}],
+                 "alleleCountMap": {}}
+            ],
+            "diseaseList": [{"diseaseId": "OMIM:100000", "geneTranscriptList": [{"geneSymbol": cohort}]}],
+            "hgvsVariants": {"c1A>G_NM_1": {}},
+            "structuralVariants": {},
+            "intergenicVariants": {},
+            "curationHistory": [{"orcid": "0000-0002-0736-9199", "date": date}]
+        })
+    }
+
+    #[test]
+    fn test_repository_statistics() {
+        let templates = vec![
+            TemplateStatistics::from_json("GENE1", &template("GENE1", "2023-03-01", "PMID:1"), None),
+            TemplateStatistics::from_json("GENE2", &template("GENE2", "2024-05-01", "PMID:2"), None),
+        ];
+        let stats = RepositoryStatistics::from_templates(&templates);
+        assert_eq!(2, stats.n_cohorts);
+        assert_eq!(4, stats.n_individuals);
+        assert_eq!(1, stats.n_diseases);
+        assert_eq!(2, stats.n_genes);
+        assert_eq!(2, stats.n_pmids);
+        assert_eq!(2, stats.n_hpo_terms);
+        assert_eq!(1, stats.variant_types.hgvs);
+        assert_eq!(1.5, stats.terms_per_individual.mean);
+        assert_eq!(Some(&2), stats.onset_distribution.get("Childhood onset"));
+        assert_eq!(Some(&2), stats.sex_distribution.get("F"));
+        assert_eq!(2, stats.growth.len());
+        assert_eq!((2024, 2, 4), (stats.growth[1].year, stats.growth[1].n_cohorts, stats.growth[1].n_individuals));
+        assert!(stats.to_csv().contains("growthIndividuals,2023,2\n"));
+    }
+
+    #[test]
+    fn test_year_and_date() {
+        assert_eq!(1970, year_from_unix_seconds(0));
+        assert_eq!(2024, year_from_unix_seconds(1_717_200_000));
+        assert_eq!((2024, 6, 1), date_from_unix_seconds(1_717_200_000));
+        assert_eq!((1970, 1, 1), date_from_unix_seconds(0));
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,122 @@
+//! Keep the repository index current by watching the file system
+//!
+//! Walking the entire phenopacket-store tree takes several seconds, so we build the
+//! [`DirectoryManager`] once and then update it incrementally. The watcher receives file system
+//! notifications (inotify on Linux, FSEvents on macOS, ReadDirectoryChangesW on Windows),
+//! determines which cohort directories were affected, rescans only those, and reports the
+//! changes via a callback (which we use to emit a Tauri event to the frontend).
+
+use std::{collections::BTreeSet, path::{Path, PathBuf}, sync::{mpsc, Arc, Mutex}, thread, time::Duration};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+
+use crate::directory_manager::DirectoryManager;
+
+/// Events that arrive within this interval are processed together (e.g., a git pull that touches many files)
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(500);
+
+
+/// Payload of the event we emit when cohort directories change on disk
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepositoryChangeEvent {
+    /// Cohorts that were added or whose contents changed
+    pub changed_cohorts: Vec<String>,
+    /// Cohorts whose directory was removed
+    pub removed_cohorts: Vec<String>,
+    /// Problems with the file system notifications or with rescanning a cohort
+    pub warnings: Vec<String>,
+}
+
+impl RepositoryChangeEvent {
+    pub fn is_empty(&self) -> bool {
+        self.changed_cohorts.is_empty() && self.removed_cohorts.is_empty() && self.warnings.is_empty()
+    }
+}
+
+
+/// Watches the base directory of a [`DirectoryManager`] and updates it when files change.
+///
+/// Watching stops when this object is dropped.
+pub struct RepositoryWatcher {
+    base_dir: PathBuf,
+    /// Held to keep the watcher alive; the worker thread ends when it is dropped
+    _watcher: RecommendedWatcher,
+}
+
+impl RepositoryWatcher {
+    /// Start watching the directory of `index`.
+    ///
+    /// # Arguments
+    /// * `index` - the shared repository index that will be updated
+    /// * `on_change` - called (from a background thread) after the index was updated
+    pub fn start<F>(index: Arc<Mutex<DirectoryManager>>, on_change: F) -> Result<Self, String>
+    where
+        F: Fn(RepositoryChangeEvent) + Send + 'static,
+    {
+        let base_dir = index.lock()
+            .map_err(|_| "Failed to acquire lock on repository index".to_string())?
+            .base_dir()
+            .to_path_buf();
+        let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+        let mut watcher = notify::recommended_watcher(tx)
+            .map_err(|e| format!("Could not create file watcher: {}", e))?;
+        watcher
+            .watch(&base_dir, RecursiveMode::Recursive)
+            .map_err(|e| format!("Could not watch {}: {}", base_dir.display(), e))?;
+        let worker_base_dir = base_dir.clone();
+        thread::spawn(move || {
+            // recv fails once the watcher (and with it the sender) has been dropped
+            while let Ok(first) = rx.recv() {
+                let mut cohorts: BTreeSet<String> = BTreeSet::new();
+                let mut warnings: Vec<String> = Vec::new();
+                Self::collect_cohorts(&worker_base_dir, first, &mut cohorts, &mut warnings);
+                while let Ok(next) = rx.recv_timeout(DEBOUNCE_INTERVAL) {
+                    Self::collect_cohorts(&worker_base_dir, next, &mut cohorts, &mut warnings);
+                }
+                if cohorts.is_empty() && warnings.is_empty() {
+                    continue;
+                }
+                let mut change = match index.lock() {
+                    Ok(mut dirman) => dirman.refresh_cohorts(&cohorts),
+                    Err(_) => {
+                        on_change(RepositoryChangeEvent {
+                            warnings: vec!["Repository index lock poisoned; stopped watching for changes".to_string()],
+                            ..Default::default()
+                        });
+                        break;
+                    }
+                };
+                change.warnings.extend(warnings);
+                if !change.is_empty() {
+                    on_change(change);
+                }
+            }
+        });
+        Ok(Self { base_dir, _watcher: watcher })
+    }
+
+    pub fn base_dir(&self) -> &Path {
+        &self.base_dir
+    }
+
+    /// Add the names of the cohorts affected by the event to `cohorts` (or the error to `warnings`)
+    fn collect_cohorts(base_dir: &Path, event: notify::Result<Event>, cohorts: &mut BTreeSet<String>, warnings: &mut Vec<String>) {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                warnings.push(format!("File watcher error: {}", e));
+                return;
+            }
+        };
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+        for path in &event.paths {
+            if let Some(cohort) = DirectoryManager::cohort_name_for_path(base_dir, path) {
+                cohorts.insert(cohort);
+            }
+        }
+    }
+}
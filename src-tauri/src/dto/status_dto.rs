@@ -1,5 +1,7 @@
 use serde::Serialize;
 
+use crate::settings::project_settings::ProjectSettings;
+
 #[derive(Clone, Debug,Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StatusDto {
@@ -9,9 +11,17 @@ pub struct StatusDto {
     pub pt_template_path: String,
     pub pt_template_loaded: bool,
     pub biocurator_orcid: String,
+    /// Name of the active curator profile
+    pub curator_name: String,
     pub hpo_json_path: String,
     pub has_error: bool,
     pub error_message: String,
+    /// Problems found while loading the settings file
+    pub settings_warnings: Vec<String>,
+    /// Problems of background tasks, e.g., the HPO cache could not be written
+    pub background_warnings: Vec<String>,
+    /// Settings of the repository (`.phenoboard.toml`) that override the user settings
+    pub project_settings: Option<ProjectSettings>,
 }
 
 impl Default for StatusDto {
@@ -23,9 +33,13 @@ impl Default for StatusDto {
             pt_template_path: Default::default(), 
             pt_template_loaded: false,
             biocurator_orcid: "Not Initialized".to_string(),
+            curator_name: String::default(),
             hpo_json_path: "Not Initialized".to_string(),
             has_error: false,
             error_message: String::default(),
+            settings_warnings: vec![],
+            background_warnings: vec![],
+            project_settings: None,
         }
     }
 }
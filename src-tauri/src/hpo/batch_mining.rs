@@ -0,0 +1,399 @@
+//! Batch mining of a directory of case reports
+//!
+//! For literature reviews, we often collect case descriptions as plain text or Markdown files
+//! (one individual per file). This module mines each file and creates a draft cohort with one row
+//! per file. The individual ID is taken from the file name and other metadata (e.g., the PMID) from
+//! an optional front matter block at the beginning of the file:
+//!
+//! ```text
+//! ---
+//! pmid: PMID:29198722
+//! title: Novel variants in ...
+//! sex: F
+//! ---
+//! The proband presented with ...
+//! ```
+
+use std::{collections::HashMap, fs, path::{Path, PathBuf}, sync::Arc};
+
+use fenominal::FenominalSentence;
+use ga4ghphetools::dto::{cohort_dto::{CohortData, CohortType, DiseaseData, IndividualData, RowData}, hpo_term_dto::CellValueInner};
+use ontolius::ontology::csr::FullCsrOntology;
+use serde::{Deserialize, Serialize};
+
+use crate::{hpo::{mined_row::{self, StatusConflict}, text_annotation::{self, HitSpan}, ClinicalStatus, MappedTerm}, util::pubmed_retrieval::PubmedRetriever};
+
+/// File extensions of case reports we will mine
+const CASE_REPORT_EXTENSIONS: [&str; 3] = ["txt", "md", "markdown"];
+/// Hits that cover fewer characters than this are flagged for review
+const MIN_CONFIDENT_MATCH_LENGTH: usize = 4;
+
+
+/// Reason why a hit should be reviewed by the curator
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ReviewReason {
+    /// The matched text is very short (e.g., an abbreviation)
+    ShortMatch,
+    /// The matched text is much shorter than the label of the term
+    PartialLabelMatch,
+    /// The term was negated; negation detection is less reliable than concept recognition
+    Negated,
+    /// The words of the hit could not be found in the case report, so the term was not added to the row
+    NotLocated,
+}
+
+/// A hit that the curator should check before accepting the draft cohort
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LowConfidenceHit {
+    pub file_name: String,
+    pub individual_id: String,
+    pub term_id: String,
+    pub label: String,
+    pub matched_text: String,
+    pub reason: ReviewReason,
+}
+
+/// Summary of the mining results for one case report
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaseReportSummary {
+    pub file_name: String,
+    pub individual_id: String,
+    pub pmid: String,
+    /// Number of observed and excluded terms in the row that was added to the cohort
+    pub n_observed: usize,
+    pub n_excluded: usize,
+    pub n_redundant: usize,
+    /// Terms that were mined with conflicting statuses (the first status was used for the row)
+    pub status_conflicts: Vec<StatusConflict>,
+    /// Problems the curator should fix in the draft cohort, e.g., a missing PMID
+    pub warnings: Vec<String>,
+    /// Set if we could not create a row from this file
+    pub error: Option<String>,
+}
+
+/// The draft cohort together with a review report
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchMiningReport {
+    pub cohort_data: CohortData,
+    pub case_reports: Vec<CaseReportSummary>,
+    pub low_confidence_hits: Vec<LowConfidenceHit>,
+}
+
+
+/// Mine all case reports in `directory` and create a new cohort with one row per file.
+///
+/// # Arguments
+/// * `hpo` - reference to the HPO
+/// * `directory` - directory with the case reports (`*.txt`, `*.md`)
+/// * `disease_data`, `cohort_type`, `acronym` - used to create the new cohort (see `create_new_cohort_data`)
+/// * `mine` - function that performs fenominal text mining
+/// * `progress_cb` - called with (current, total) after each file
+///
+/// # Returns
+/// A report with the draft cohort. Files that could not be processed are listed with an error
+/// message in the report rather than aborting the entire batch. A file whose individual (PMID and
+/// individual ID) was already created from an earlier file is reported as an error, and a missing PMID
+/// as a warning of the file.
+pub fn mine_case_report_directory<M, F>(
+    hpo: Arc<FullCsrOntology>,
+    directory: &Path,
+    disease_data: DiseaseData,
+    cohort_type: CohortType,
+    acronym: String,
+    mine: M,
+    mut progress_cb: F,
+) -> Result<BatchMiningReport, String>
+where
+    M: Fn(&str) -> Result<Vec<FenominalSentence>, String>,
+    F: FnMut(u32, u32),
+{
+    let files = get_case_report_files(directory)?;
+    if files.is_empty() {
+        return Err(format!("No case reports (*.txt, *.md) found in {}", directory.display()));
+    }
+    let mut cohort = ga4ghphetools::factory::create_new_cohort_data(cohort_type, disease_data, acronym, hpo.clone())?;
+    let mut case_reports: Vec<CaseReportSummary> = Vec::new();
+    let mut low_confidence_hits: Vec<LowConfidenceHit> = Vec::new();
+    // file that created the row of each individual (PMID, individual ID)
+    let mut row_files: HashMap<(String, String), String> = HashMap::new();
+    let total = files.len() as u32;
+    for (i, path) in files.iter().enumerate() {
+        let file_name = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+        let contents = match fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(e) => {
+                case_reports.push(CaseReportSummary::error(&file_name, "", "", format!("Could not read file: {}", e)));
+                progress_cb(i as u32 + 1, total);
+                continue;
+            }
+        };
+        let (front_matter, body) = parse_front_matter(&contents);
+        let individual_id = front_matter
+            .get("individual_id")
+            .cloned()
+            .unwrap_or_else(|| individual_id_from_path(path));
+        let mut warnings = Vec::new();
+        let pmid = pmid_from_front_matter(&front_matter).unwrap_or_else(|w| {
+            warnings.push(w);
+            String::new()
+        });
+        if let Some(other_file) = row_files.get(&(pmid.clone(), individual_id.clone())) {
+            let error = format!("Individual '{}' ({}) was already created from {}", individual_id, pmid, other_file);
+            case_reports.push(CaseReportSummary { warnings, ..CaseReportSummary::error(&file_name, &individual_id, &pmid, error) });
+            progress_cb(i as u32 + 1, total);
+            continue;
+        }
+        let (hits, unlocated_hits) = match mine(body) {
+            Ok(sentences) => text_annotation::extract_hit_spans(body, &sentences),
+            Err(e) => {
+                case_reports.push(CaseReportSummary { warnings, ..CaseReportSummary::error(&file_name, &individual_id, &pmid, e) });
+                progress_cb(i as u32 + 1, total);
+                continue;
+            }
+        };
+        low_confidence_hits.extend(
+            hits.iter()
+                .filter_map(|hit| review_reason(hit).map(|reason| (hit, reason)))
+                .map(|(hit, reason)| LowConfidenceHit {
+                    file_name: file_name.clone(),
+                    individual_id: individual_id.clone(),
+                    term_id: hit.term_id.clone(),
+                    label: hit.label.clone(),
+                    matched_text: hit.matched_text.clone(),
+                    reason,
+                })
+        );
+        low_confidence_hits.extend(unlocated_hits.into_iter().map(|hit| LowConfidenceHit {
+            file_name: file_name.clone(),
+            individual_id: individual_id.clone(),
+            term_id: hit.term_id,
+            label: hit.label,
+            matched_text: hit.matched_text,
+            reason: ReviewReason::NotLocated,
+        }));
+        let mined_terms: Vec<MappedTerm> = hits.iter().map(to_mapped_term).collect();
+        let individual = individual_data(&individual_id, &pmid, &front_matter);
+        match mined_row::add_mined_row_to_cohort(hpo.clone(), individual, mined_terms, vec![], cohort.clone()) {
+            Ok(mined_row) => {
+                cohort = mined_row.cohort_data;
+                let (n_observed, n_excluded) = cohort
+                    .rows
+                    .iter()
+                    .find(|row| row.individual_data.pmid == pmid && row.individual_data.individual_id == individual_id)
+                    .map(count_statuses)
+                    .unwrap_or_default();
+                row_files.insert((pmid.clone(), individual_id.clone()), file_name.clone());
+                case_reports.push(CaseReportSummary {
+                    file_name,
+                    individual_id,
+                    pmid,
+                    n_observed,
+                    n_excluded,
+                    n_redundant: mined_row.redundant_terms.len(),
+                    status_conflicts: mined_row.status_conflicts,
+                    warnings,
+                    error: None,
+                });
+            }
+            Err(e) => {
+                case_reports.push(CaseReportSummary { warnings, ..CaseReportSummary::error(&file_name, &individual_id, &pmid, e) });
+            }
+        }
+        progress_cb(i as u32 + 1, total);
+    }
+    Ok(BatchMiningReport {
+        cohort_data: cohort,
+        case_reports,
+        low_confidence_hits,
+    })
+}
+
+impl CaseReportSummary {
+    fn error(file_name: &str, individual_id: &str, pmid: &str, error: String) -> Self {
+        Self {
+            file_name: file_name.to_string(),
+            individual_id: individual_id.to_string(),
+            pmid: pmid.to_string(),
+            n_observed: 0,
+            n_excluded: 0,
+            n_redundant: 0,
+            status_conflicts: Vec::new(),
+            warnings: Vec::new(),
+            error: Some(error),
+        }
+    }
+}
+
+/// Get all non-hidden case report files in the directory, sorted by file name
+fn get_case_report_files(directory: &Path) -> Result<Vec<PathBuf>, String> {
+    if !directory.is_dir() {
+        return Err(format!("Could not find directory '{}'", directory.display()));
+    }
+    let mut files: Vec<PathBuf> = fs::read_dir(directory)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|p| p.is_file())
+        .filter(|p| p.file_name().is_some_and(|f| !f.to_string_lossy().starts_with('.')))
+        .filter(|p| p.extension().is_some_and(|ext| {
+            CASE_REPORT_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str())
+        }))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// The individual ID is the file name without extension, e.g., `Proband_1.md` -> `Proband 1`
+fn individual_id_from_path(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().replace('_', " ").trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Split off an optional front matter block delimited by `---` lines and parse its `key: value` pairs.
+///
+/// Keys are lower-cased and spaces/hyphens are replaced by underscores, so that `Individual ID`
+/// and `individual-id` both become `individual_id`. Returns the key/value map and the remaining text.
+pub fn parse_front_matter(contents: &str) -> (HashMap<String, String>, &str) {
+    let mut map: HashMap<String, String> = HashMap::new();
+    let trimmed = contents.trim_start_matches('\u{feff}');
+    let mut lines = trimmed.split_inclusive('\n');
+    match lines.next() {
+        Some(first) if first.trim_end() == "---" => {},
+        _ => return (map, trimmed),
+    }
+    let mut offset = trimmed.find('\n').map_or(trimmed.len(), |i| i + 1);
+    for line in lines {
+        offset += line.len();
+        let line_content = line.trim();
+        if line_content == "---" {
+            return (map, &trimmed[offset..]);
+        }
+        if let Some((key, value)) = line_content.split_once(':') {
+            let key = key.trim().to_lowercase().replace([' ', '-'], "_");
+            let value = value.trim().trim_matches('"').trim();
+            if !key.is_empty() && !value.is_empty() {
+                map.insert(key, value.to_string());
+            }
+        }
+    }
+    // No closing delimiter, so this was not a front matter block
+    (HashMap::new(), trimmed)
+}
+
+/// The PMID of the front matter (e.g., `PMID:29198722`); an error message if it is missing or cannot be parsed
+fn pmid_from_front_matter(front_matter: &HashMap<String, String>) -> Result<String, String> {
+    let pmid = front_matter
+        .get("pmid")
+        .ok_or_else(|| "No PMID in the front matter; set it in the draft cohort".to_string())?;
+    PubmedRetriever::extract_pmid(pmid)
+        .map(|n| format!("PMID:{}", n))
+        .ok_or_else(|| format!("Could not parse the PMID '{}' of the front matter; set it in the draft cohort", pmid))
+}
+
+/// Determine whether a hit should be reviewed by the curator
+fn review_reason(hit: &HitSpan) -> Option<ReviewReason> {
+    let n_chars = hit.matched_text.chars().count();
+    if n_chars < MIN_CONFIDENT_MATCH_LENGTH {
+        Some(ReviewReason::ShortMatch)
+    } else if hit.label.chars().count() > 2 * n_chars {
+        Some(ReviewReason::PartialLabelMatch)
+    } else if hit.negated {
+        Some(ReviewReason::Negated)
+    } else {
+        None
+    }
+}
+
+fn to_mapped_term(hit: &HitSpan) -> MappedTerm {
+    let mut mt = MappedTerm::new(&hit.term_id, &hit.label);
+    if hit.negated {
+        mt.status = ClinicalStatus::Excluded;
+    }
+    mt
+}
+
+/// Number of observed (including onset ages) and excluded terms of a row
+fn count_statuses(row: &RowData) -> (usize, usize) {
+    row.hpo_data.iter().fold((0, 0), |(observed, excluded), cell| match cell.entry {
+        CellValueInner::Observed | CellValueInner::OnsetAge(_) => (observed + 1, excluded),
+        CellValueInner::Excluded => (observed, excluded + 1),
+        _ => (observed, excluded),
+    })
+}
+
+/// Create the demographic data for a row. Fields not provided in the front matter are set to `na`
+/// (and the sex to `U`, unknown) and can be edited by the curator in the draft cohort.
+fn individual_data(
+    individual_id: &str,
+    pmid: &str,
+    front_matter: &HashMap<String, String>
+) -> IndividualData {
+    let field = |key: &str, default: &str| -> String {
+        front_matter.get(key).cloned().unwrap_or_else(|| default.to_string())
+    };
+    IndividualData {
+        pmid: pmid.to_string(),
+        title: field("title", "na"),
+        individual_id: individual_id.to_string(),
+        comment: field("comment", "na"),
+        age_of_onset: field("age_of_onset", "na"),
+        age_at_last_encounter: field("age_at_last_encounter", "na"),
+        deceased: field("deceased", "na"),
+        sex: field("sex", "U"),
+    }
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_front_matter() {
+        let contents = "---\npmid: PMID:29198722\nIndividual ID: \"P1\"\n---\nThe proband had seizures.";
+        let (map, body) = parse_front_matter(contents);
+        assert_eq!(Some(&"PMID:29198722".to_string()), map.get("pmid"));
+        assert_eq!(Some(&"P1".to_string()), map.get("individual_id"));
+        assert_eq!("The proband had seizures.", body);
+    }
+
+    #[test]
+    fn test_no_front_matter() {
+        let contents = "The proband had seizures.\n---\n";
+        let (map, body) = parse_front_matter(contents);
+        assert!(map.is_empty());
+        assert_eq!(contents, body);
+    }
+
+    #[test]
+    fn test_unterminated_front_matter() {
+        let contents = "---\npmid: 123\nThe proband had seizures.";
+        let (map, body) = parse_front_matter(contents);
+        assert!(map.is_empty());
+        assert_eq!(contents, body);
+    }
+
+    #[test]
+    fn test_pmid_from_front_matter() {
+        let (map, _) = parse_front_matter("---\npmid: 29198722\n---\n");
+        assert_eq!(Ok("PMID:29198722".to_string()), pmid_from_front_matter(&map));
+        let (map, _) = parse_front_matter("---\npmid: unknown\n---\n");
+        assert!(pmid_from_front_matter(&map).unwrap_err().contains("'unknown'"));
+        assert!(pmid_from_front_matter(&HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_individual_id_from_path() {
+        assert_eq!("Proband 1", individual_id_from_path(Path::new("/tmp/cases/Proband_1.md")));
+    }
+}
+
+// endregion: --- Tests
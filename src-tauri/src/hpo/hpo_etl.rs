@@ -1,8 +1,64 @@
 use std::collections::{HashMap, HashSet};
-use crate::hpo::{MappedTerm, MinedCell, MiningConcept};
+use serde::{Deserialize, Serialize};
+use crate::hpo::{ClinicalStatus, MappedTerm, MinedCell, MiningConcept};
 
 
 
+/// An HPO term that was mapped with conflicting clinical status (observed and excluded)
+/// from different concepts of the same cell.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CellStatusConflict {
+    pub cell_text: String,
+    pub row_index_list: Vec<usize>,
+    pub hpo_id: String,
+    pub hpo_label: String,
+    /// The original texts of the concepts that mapped to the term as observed
+    pub observed_in: Vec<String>,
+    /// The original texts of the concepts that mapped to the term as excluded
+    pub excluded_in: Vec<String>,
+    /// The status that we assigned to the term in the cell
+    pub resolved_status: ClinicalStatus,
+}
+
+/// Cell mappings together with the conflicts that were resolved while creating them
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CellMappingResult {
+    pub mined_cells: Vec<MinedCell>,
+    pub conflicts: Vec<CellStatusConflict>,
+}
+
+
+/// Merge the concepts of the first phase of text mining into a dictionary with one
+/// concept per unique original text.
+///
+/// The row indices of identical concepts are merged, sorted and deduplicated. The concepts
+/// are returned in the order in which they first occur in the input (i.e., by first row index
+/// if the input was created row by row), so that repeated runs produce the same order.
+pub fn create_canonical_dictionary(mining_results: Vec<MiningConcept>) -> Vec<MiningConcept> {
+    let mut canonical: Vec<MiningConcept> = Vec::new();
+    let mut index_by_text: HashMap<String, usize> = HashMap::new();
+    for mut concept in mining_results {
+        match index_by_text.get(&concept.original_text) {
+            Some(&i) => {
+                canonical[i].row_index_list.append(&mut concept.row_index_list);
+            },
+            None => {
+                // First time we see this concept → becomes canonical
+                index_by_text.insert(concept.original_text.clone(), canonical.len());
+                canonical.push(concept);
+            }
+        }
+    }
+    for concept in canonical.iter_mut() {
+        concept.row_index_list.sort_unstable();
+        concept.row_index_list.dedup();
+    }
+    canonical
+}
+
+
 /// Creates row-aware HPO mappings by grouping identical cell texts and
 /// attaching all matching HPO concepts to each group.
 ///
@@ -17,8 +73,8 @@ use crate::hpo::{MappedTerm, MinedCell, MiningConcept};
 ///
 /// - Each `MinedCell` corresponds to **one unique cell text**
 /// - Its `row_index_list` contains **all rows where that text occurs**
-/// - Its `hpo_duplet_list` contains **all HPO terms from concepts that apply
-///   to any of those rows**
+/// - Its `mapped_term_list` contains **each HPO term from concepts that apply
+///   to any of those rows exactly once**
 ///
 /// This allows identical spreadsheet entries to be processed once while
 /// preserving row-level provenance.
@@ -28,11 +84,15 @@ use crate::hpo::{MappedTerm, MinedCell, MiningConcept};
 /// 1. Cell values are grouped by identical text.
 /// 2. For each group of rows:
 ///    - All `MiningConcept`s whose `row_index_list` intersects the group
-///      are selected.
-///    - Their suggested HPO terms are collected into HPO duplets.
+///      are selected (in the order of `mining_results`).
+///    - Their suggested HPO terms are collected; if two concepts map to the same
+///      HPO term, the term is added only once.
+///    - If the same term is observed according to one concept and excluded according
+///      to another, the term is recorded as observed and the conflict is reported.
 /// 3. A single [`MinedCell`] is created per unique cell text.
 ///
-/// The resulting vector has no guaranteed ordering.
+/// The resulting cells are ordered by their first row index, so the output is
+/// identical for identical input.
 ///
 /// # Assumptions
 ///
@@ -45,32 +105,69 @@ use crate::hpo::{MappedTerm, MinedCell, MiningConcept};
 pub fn create_cell_mappings(
     cell_values: Vec<String>,
     mining_results: Vec<MiningConcept>,
-) -> Result<Vec<MinedCell>, String> {
+) -> Result<CellMappingResult, String> {
     let mut mined_cells: Vec<MinedCell> = Vec::new();
-    // collect identical entries of original cell values
-    let mut rows_by_text: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut conflicts: Vec<CellStatusConflict> = Vec::new();
+    // collect identical entries of original cell values, in order of first occurrence
+    let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+    let mut group_by_text: HashMap<String, usize> = HashMap::new();
     for (row_index, text) in cell_values.into_iter().enumerate() {
-        rows_by_text
-            .entry(text)
-            .or_default()
-            .push(row_index);
+        match group_by_text.get(&text) {
+            Some(&i) => groups[i].1.push(row_index),
+            None => {
+                group_by_text.insert(text.clone(), groups.len());
+                groups.push((text, vec![row_index]));
+            }
+        }
     }
-    for (cell_text, row_indices) in rows_by_text {
+    for (cell_text, row_indices) in groups {
         let row_set: HashSet<usize> = row_indices.iter().copied().collect();
         let mut mapped_terms: Vec<MappedTerm> = Vec::new();
-        // 3. Find concepts that apply to any of these rows
+        // concept texts by HPO id and status, to detect conflicts
+        let mut observed_in: HashMap<String, Vec<String>> = HashMap::new();
+        let mut excluded_in: HashMap<String, Vec<String>> = HashMap::new();
+        // Find concepts that apply to any of these rows
         for concept in &mining_results {
-            if concept
+            if !concept
                 .row_index_list
                 .iter()
                 .any(|idx| row_set.contains(idx))
             {
-                for term in &concept.suggested_terms {
-                    mapped_terms.push(MappedTerm::new(&term.id, &term.label));
+                continue;
+            }
+            for term in &concept.suggested_terms {
+                let sources = match concept.clinical_status {
+                    ClinicalStatus::Excluded => &mut excluded_in,
+                    _ => &mut observed_in,
+                };
+                sources.entry(term.id.clone()).or_default().push(concept.original_text.clone());
+                match mapped_terms.iter_mut().find(|mt| mt.hpo_id == term.id) {
+                    Some(existing) => {
+                        if concept.clinical_status == ClinicalStatus::Observed {
+                            existing.status = ClinicalStatus::Observed;
+                        }
+                    },
+                    None => {
+                        let mut mt = MappedTerm::new(&term.id, &term.label);
+                        mt.status = concept.clinical_status;
+                        mapped_terms.push(mt);
+                    }
                 }
             }
         }
-
+        for mt in &mapped_terms {
+            if let (Some(observed), Some(excluded)) = (observed_in.get(&mt.hpo_id), excluded_in.get(&mt.hpo_id)) {
+                conflicts.push(CellStatusConflict {
+                    cell_text: cell_text.clone(),
+                    row_index_list: row_indices.clone(),
+                    hpo_id: mt.hpo_id.clone(),
+                    hpo_label: mt.hpo_label.clone(),
+                    observed_in: observed.clone(),
+                    excluded_in: excluded.clone(),
+                    resolved_status: mt.status,
+                });
+            }
+        }
         mined_cells.push(MinedCell {
             cell_text,
             row_index_list: row_indices,
@@ -78,40 +175,75 @@ pub fn create_cell_mappings(
         });
     }
 
-    Ok(mined_cells)
+    Ok(CellMappingResult { mined_cells, conflicts })
 }
 
 
 
-fn mined_cell_to_string(cell: &MinedCell) -> String {
-    let mut items: Vec<String> = Vec::new();
-    for mt in &cell.mapped_term_list {
-        let onset = mt.onset.to_string();
-        let status = mt.status.to_string();
-        items.push(format!("{}-{}-{}", mt.hpo_id, status, onset));
-    }
-    items.join(";")
-}
+// region:    --- Tests
 
-pub fn get_multi_hpo_strings(mined_cells: Vec<MinedCell>) -> Result<Vec<String>, String> {
-    let mut index_map: HashMap<usize, String> = HashMap::new();
-    let mut max_index: usize = 0;
-    for cell in mined_cells {
-        let content = mined_cell_to_string(&cell);
-        for i in cell.row_index_list {
-            if index_map.insert(i, content.clone()).is_some() {
-                return Err(format!("Double index for mutli HPO mappings: {}", i));
-            }
-            max_index = max_index.max(i);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hpo::MiningStatus;
+    use fenominal::OntologyMatch;
+
+    fn concept(text: &str, rows: Vec<usize>, hpo_id: &str, status: ClinicalStatus) -> MiningConcept {
+        let term: OntologyMatch = serde_json::from_value(serde_json::json!({
+            "id": hpo_id, "label": "label", "matchedText": text
+        })).unwrap();
+        MiningConcept {
+            original_text: text.to_string(),
+            row_index_list: rows,
+            suggested_terms: vec![term],
+            mining_status: MiningStatus::Confirmed,
+            clinical_status: status,
         }
     }
-   let hpo_strings = (0..=max_index)
-        .map(|i| {
-            index_map.remove(&i).ok_or_else(|| {
-                format!("Missing index in sequence: {}", i)
-            })
-        })
-        .collect::<Result<Vec<String>, String>>()?;
-
-    Ok(hpo_strings)
-}
\ No newline at end of file
+
+    #[test]
+    fn test_canonical_dictionary_preserves_order() {
+        let concepts = vec![
+            concept("seizures", vec![0], "HP:0001250", ClinicalStatus::Observed),
+            concept("ataxia", vec![1], "HP:0001251", ClinicalStatus::Observed),
+            concept("seizures", vec![2], "HP:0001250", ClinicalStatus::Observed),
+        ];
+        let dict = create_canonical_dictionary(concepts);
+        assert_eq!(2, dict.len());
+        assert_eq!("seizures", dict[0].original_text);
+        assert_eq!(vec![0, 2], dict[0].row_index_list);
+        assert_eq!("ataxia", dict[1].original_text);
+    }
+
+    #[test]
+    fn test_cell_mappings_are_ordered_by_first_row() {
+        let cells: Vec<String> = vec!["b", "a", "b", "c"].into_iter().map(String::from).collect();
+        let concepts = vec![
+            concept("b", vec![0, 2], "HP:0001250", ClinicalStatus::Observed),
+            concept("a", vec![1], "HP:0001251", ClinicalStatus::Observed),
+        ];
+        let result = create_cell_mappings(cells, concepts).unwrap();
+        let texts: Vec<&str> = result.mined_cells.iter().map(|c| c.cell_text.as_str()).collect();
+        assert_eq!(vec!["b", "a", "c"], texts);
+        assert_eq!(vec![0, 2], result.mined_cells[0].row_index_list);
+        assert!(result.mined_cells[2].mapped_term_list.is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_terms_and_conflicts() {
+        let cells: Vec<String> = vec!["seizures; no seizures; fits".to_string()];
+        let concepts = vec![
+            concept("seizures", vec![0], "HP:0001250", ClinicalStatus::Observed),
+            concept("no seizures", vec![0], "HP:0001250", ClinicalStatus::Excluded),
+            concept("fits", vec![0], "HP:0001250", ClinicalStatus::Observed),
+        ];
+        let result = create_cell_mappings(cells, concepts).unwrap();
+        assert_eq!(1, result.mined_cells[0].mapped_term_list.len());
+        assert_eq!(ClinicalStatus::Observed, result.mined_cells[0].mapped_term_list[0].status);
+        assert_eq!(1, result.conflicts.len());
+        assert_eq!(vec!["seizures", "fits"], result.conflicts[0].observed_in);
+        assert_eq!(vec!["no seizures"], result.conflicts[0].excluded_in);
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,405 @@
+//! Download manager for releases of the Human Phenotype Ontology (hp.json)
+//!
+//! Releases are stored in `~/.phenoboard/hpo/<version>/hp.json`. The releases can be obtained from
+//! - the GitHub releases of the HPO (default); the API URL can be changed, e.g., to a local file server for testing
+//! - the OBO PURLs (`<base_url>/hp.json` for the latest release and `<base_url>/releases/<version>/hp.json`)
+//! - a local mirror directory with the same layout as our release directory (`<version>/hp.json`)
+//!
+//! Each download is written to a temporary file and checked (size, SHA-256 checksum if the source provides
+//! one, and the version recorded in the file) before it replaces an installed release.
+
+use std::{fs::{self, File}, io::{Read, Write}, path::{Path, PathBuf}, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub const HPO_JSON_FILE: &str = "hp.json";
+const CHECKSUM_FILE: &str = "hp.json.sha256";
+const GITHUB_RELEASES_API: &str = "https://api.github.com/repos/obophenotype/human-phenotype-ontology/releases";
+const HPO_PURL: &str = "https://purl.obolibrary.org/obo/hp";
+/// Version used for the latest release if the source cannot tell the version before the download
+pub const LATEST: &str = "latest";
+
+
+/// Where HPO releases are downloaded from
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum HpoReleaseSource {
+    /// GitHub releases API (or a server that provides the same JSON)
+    Github { api_url: String },
+    /// OBO PURL (only the latest release can be listed)
+    Purl { base_url: String },
+    /// Local directory with one subdirectory per release (`<version>/hp.json`)
+    LocalMirror { directory: String },
+}
+
+impl Default for HpoReleaseSource {
+    fn default() -> Self {
+        HpoReleaseSource::Github { api_url: GITHUB_RELEASES_API.to_string() }
+    }
+}
+
+impl HpoReleaseSource {
+    pub fn purl() -> Self {
+        HpoReleaseSource::Purl { base_url: HPO_PURL.to_string() }
+    }
+}
+
+/// A release that is available from the source
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HpoRelease {
+    /// e.g., `2025-05-06` ([`LATEST`] if the version is only known after the download)
+    pub version: String,
+    /// URL or path of the hp.json file
+    pub download_url: String,
+    /// Size in bytes (if provided by the source)
+    pub size: Option<u64>,
+    /// SHA-256 checksum as hexadecimal string (if provided by the source)
+    pub sha256: Option<String>,
+    pub published: Option<String>,
+    /// Is this release already in the local release directory?
+    pub installed: bool,
+}
+
+/// A release in the local release directory
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstalledHpoRelease {
+    pub version: String,
+    pub path: String,
+    pub size: u64,
+}
+
+
+/// Releases are stored in subdirectories named after the version, so only simple names are allowed
+fn check_version(version: &str) -> Result<(), String> {
+    let valid = !version.is_empty()
+        && version != "."
+        && version != ".."
+        && version.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid HPO version '{}'", version))
+    }
+}
+
+fn http_client() -> Result<reqwest::blocking::Client, String> {
+    reqwest::blocking::Client::builder()
+        .user_agent(concat!("phenoboard/", env!("CARGO_PKG_VERSION")))
+        .connect_timeout(Duration::from_secs(30))
+        .timeout(None)
+        .build()
+        .map_err(|e| format!("Could not create HTTP client: {}", e))
+}
+
+/// Releases in the local release directory (or in a local mirror), most recent first
+pub fn installed_releases(release_dir: &Path) -> Vec<InstalledHpoRelease> {
+    let Ok(entries) = fs::read_dir(release_dir) else {
+        return vec![];
+    };
+    let mut releases: Vec<InstalledHpoRelease> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let version = entry.file_name().to_string_lossy().to_string();
+            let path = entry.path().join(HPO_JSON_FILE);
+            let size = fs::metadata(&path).ok()?.len();
+            check_version(&version).ok()?;
+            Some(InstalledHpoRelease { version, path: path.to_string_lossy().to_string(), size })
+        })
+        .collect();
+    // versions are dates (YYYY-MM-DD), so the reverse lexical order is most recent first
+    releases.sort_by(|a, b| b.version.cmp(&a.version));
+    releases
+}
+
+/// Get the releases that are available from the source, most recent first
+pub fn list_releases(source: &HpoReleaseSource, release_dir: &Path) -> Result<Vec<HpoRelease>, String> {
+    let mut releases = match source {
+        HpoReleaseSource::Github { api_url } => {
+            let body = http_client()?
+                .get(api_url)
+                .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+                .send()
+                .and_then(|r| r.error_for_status())
+                .and_then(|r| r.text())
+                .map_err(|e| format!("Could not retrieve HPO releases from {}: {}", api_url, e))?;
+            parse_github_releases(&body)?
+        },
+        HpoReleaseSource::Purl { base_url } => vec![HpoRelease {
+            version: LATEST.to_string(),
+            download_url: format!("{}/{}", base_url.trim_end_matches('/'), HPO_JSON_FILE),
+            size: None,
+            sha256: None,
+            published: None,
+            installed: false,
+        }],
+        HpoReleaseSource::LocalMirror { directory } => {
+            if !Path::new(directory).is_dir() {
+                return Err(format!("HPO mirror directory not found: {}", directory));
+            }
+            installed_releases(Path::new(directory))
+                .into_iter()
+                .map(|r| {
+                    let sha256 = fs::read_to_string(Path::new(&r.path).with_file_name(CHECKSUM_FILE))
+                        .ok()
+                        .and_then(|s| s.split_whitespace().next().map(|c| c.to_lowercase()));
+                    HpoRelease {
+                        version: r.version,
+                        download_url: r.path,
+                        size: Some(r.size),
+                        sha256,
+                        published: None,
+                        installed: false,
+                    }
+                })
+                .collect()
+        },
+    };
+    let installed: Vec<String> = installed_releases(release_dir).into_iter().map(|r| r.version).collect();
+    for release in &mut releases {
+        release.installed = installed.contains(&release.version);
+    }
+    Ok(releases)
+}
+
+/// Extract the releases with an hp.json asset from the response of the GitHub releases API
+fn parse_github_releases(body: &str) -> Result<Vec<HpoRelease>, String> {
+    let json: serde_json::Value = serde_json::from_str(body)
+        .map_err(|e| format!("Could not parse list of HPO releases: {}", e))?;
+    let releases = json.as_array().ok_or("Unexpected response for the list of HPO releases")?;
+    Ok(releases
+        .iter()
+        .filter(|release| !release["draft"].as_bool().unwrap_or(false))
+        .filter_map(|release| {
+            let tag = release["tag_name"].as_str()?;
+            let asset = release["assets"]
+                .as_array()?
+                .iter()
+                .find(|asset| asset["name"].as_str() == Some(HPO_JSON_FILE))?;
+            Some(HpoRelease {
+                version: tag.trim_start_matches('v').to_string(),
+                download_url: asset["browser_download_url"].as_str()?.to_string(),
+                size: asset["size"].as_u64(),
+                sha256: asset["digest"]
+                    .as_str()
+                    .and_then(|d| d.strip_prefix("sha256:"))
+                    .map(|d| d.to_lowercase()),
+                published: release["published_at"].as_str().map(|p| p.to_string()),
+                installed: false,
+            })
+        })
+        .collect())
+}
+
+/// Read the release version from the metadata of an hp.json file
+/// (e.g., `http://purl.obolibrary.org/obo/hp/releases/2025-05-06/hp.json` gives `2025-05-06`)
+pub fn read_hpo_version(hp_json: &Path) -> Result<String, String> {
+    #[derive(Deserialize)]
+    struct Meta { version: Option<String> }
+    #[derive(Deserialize)]
+    struct Graph { meta: Option<Meta> }
+    #[derive(Deserialize)]
+    struct Graphs { graphs: Vec<Graph> }
+
+    let file = File::open(hp_json).map_err(|e| format!("Could not open {}: {}", hp_json.display(), e))?;
+    let graphs: Graphs = serde_json::from_reader(std::io::BufReader::new(file))
+        .map_err(|e| format!("{} is not a valid hp.json file: {}", hp_json.display(), e))?;
+    let version_iri = graphs.graphs
+        .into_iter()
+        .find_map(|g| g.meta.and_then(|m| m.version))
+        .ok_or_else(|| format!("No version found in {}", hp_json.display()))?;
+    let segments: Vec<&str> = version_iri.split('/').collect();
+    match segments.iter().position(|s| *s == "releases") {
+        Some(i) if i + 1 < segments.len() => Ok(segments[i + 1].to_string()),
+        _ => Ok(version_iri),
+    }
+}
+
+/// Copy `reader` to `out_path`, returning the number of bytes and the SHA-256 checksum
+fn copy_with_checksum<R, F>(mut reader: R, out_path: &Path, total: Option<u64>, progress_cb: &mut F) -> Result<(u64, String), String>
+where
+    R: Read,
+    F: FnMut(u64, u64),
+{
+    let mut out = File::create(out_path).map_err(|e| format!("Could not create {}: {}", out_path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut n_bytes: u64 = 0;
+    loop {
+        let n = reader.read(&mut buffer).map_err(|e| format!("Download failed: {}", e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+        out.write_all(&buffer[..n]).map_err(|e| format!("Could not write {}: {}", out_path.display(), e))?;
+        n_bytes += n as u64;
+        progress_cb(n_bytes, total.unwrap_or(0));
+    }
+    out.flush().map_err(|e| format!("Could not write {}: {}", out_path.display(), e))?;
+    Ok((n_bytes, format!("{:x}", hasher.finalize())))
+}
+
+/// Download (or copy from a mirror) a release into `release_dir/<version>/hp.json` and return its path.
+///
+/// The file is checked against the size and checksum given by the source and against the requested version
+/// before it replaces an existing copy. `progress_cb` is called with (bytes so far, total bytes or 0).
+pub fn download_release<F>(release: &HpoRelease, release_dir: &Path, mut progress_cb: F) -> Result<PathBuf, String>
+where
+    F: FnMut(u64, u64),
+{
+    if release.version != LATEST {
+        check_version(&release.version)?;
+    }
+    fs::create_dir_all(release_dir)
+        .map_err(|e| format!("Could not create {}: {}", release_dir.display(), e))?;
+    let tmp_path = release_dir.join(format!("{}.download", HPO_JSON_FILE));
+    let is_remote = release.download_url.starts_with("http://") || release.download_url.starts_with("https://");
+    let result = if is_remote {
+        http_client()?
+            .get(&release.download_url)
+            .send()
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Could not download {}: {}", release.download_url, e))
+            .and_then(|response| {
+                let total = release.size.or(response.content_length());
+                let content_length = response.content_length();
+                let (n_bytes, sha256) = copy_with_checksum(response, &tmp_path, total, &mut progress_cb)?;
+                if let Some(expected) = content_length {
+                    if expected != n_bytes {
+                        return Err(format!("Incomplete download: received {} of {} bytes", n_bytes, expected));
+                    }
+                }
+                Ok((n_bytes, sha256))
+            })
+    } else {
+        File::open(&release.download_url)
+            .map_err(|e| format!("Could not open {}: {}", release.download_url, e))
+            .and_then(|file| copy_with_checksum(file, &tmp_path, release.size, &mut progress_cb))
+    };
+    let verified = result.and_then(|(n_bytes, sha256)| {
+        verify_download(release, n_bytes, &sha256)?;
+        let version = read_hpo_version(&tmp_path)?;
+        if release.version != LATEST && version != release.version {
+            return Err(format!("Expected HPO version {} but the file has version {}", release.version, version));
+        }
+        check_version(&version)?;
+        Ok((version, sha256))
+    });
+    let (version, sha256) = match verified {
+        Ok(verified) => verified,
+        Err(e) => {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+    };
+    let version_dir = release_dir.join(&version);
+    fs::create_dir_all(&version_dir)
+        .map_err(|e| format!("Could not create {}: {}", version_dir.display(), e))?;
+    let hp_json = version_dir.join(HPO_JSON_FILE);
+    fs::rename(&tmp_path, &hp_json)
+        .map_err(|e| format!("Could not move download to {}: {}", hp_json.display(), e))?;
+    fs::write(version_dir.join(CHECKSUM_FILE), format!("{}  {}\n", sha256, HPO_JSON_FILE))
+        .map_err(|e| format!("Could not write checksum file: {}", e))?;
+    Ok(hp_json)
+}
+
+fn verify_download(release: &HpoRelease, n_bytes: u64, sha256: &str) -> Result<(), String> {
+    if let Some(size) = release.size {
+        if size != n_bytes {
+            return Err(format!("Size mismatch for HPO {}: expected {} bytes but got {}", release.version, size, n_bytes));
+        }
+    }
+    if let Some(expected) = &release.sha256 {
+        if !expected.eq_ignore_ascii_case(sha256) {
+            return Err(format!("Checksum mismatch for HPO {}: expected {} but got {}", release.version, expected, sha256));
+        }
+    }
+    Ok(())
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{io::{BufRead, BufReader}, net::TcpListener, thread};
+    use tempfile::TempDir;
+
+    const HP_JSON: &str = r#"{"graphs": [{"id": "http://purl.obolibrary.org/obo/hp.json",
+        "meta": {"version": "http://purl.obolibrary.org/obo/hp/releases/2025-05-06/hp.json"}, "nodes": []}]}"#;
+
+    fn sha256_hex(text: &str) -> String {
+        format!("{:x}", Sha256::digest(text.as_bytes()))
+    }
+
+    /// Serve the releases list and hp.json on a local port
+    fn serve(n_requests: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let releases = serde_json::json!([{
+            "tag_name": "v2025-05-06",
+            "published_at": "2025-05-06T12:00:00Z",
+            "assets": [{"name": "hp.json", "size": HP_JSON.len(),
+                        "digest": format!("sha256:{}", sha256_hex(HP_JSON)),
+                        "browser_download_url": format!("{}/download/hp.json", base_url)}]
+        }]).to_string();
+        thread::spawn(move || {
+            for stream in listener.incoming().take(n_requests) {
+                let mut stream = stream.unwrap();
+                let mut request_line = String::new();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                reader.read_line(&mut request_line).unwrap();
+                // skip the headers
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                let body = if request_line.contains("/releases") { releases.as_str() } else { HP_JSON };
+                write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body).unwrap();
+            }
+        });
+        base_url
+    }
+
+    #[test]
+    fn test_download_from_local_server() {
+        let base_url = serve(2);
+        let tmp = TempDir::new().unwrap();
+        let release_dir = tmp.path();
+        let source = HpoReleaseSource::Github { api_url: format!("{}/releases", base_url) };
+        let releases = list_releases(&source, release_dir).unwrap();
+        assert_eq!(1, releases.len());
+        assert_eq!("2025-05-06", releases[0].version);
+        assert!(!releases[0].installed);
+        let hp_json = download_release(&releases[0], release_dir, |_, _| {}).unwrap();
+        assert_eq!(release_dir.join("2025-05-06").join("hp.json"), hp_json);
+        assert_eq!(vec!["2025-05-06"], installed_releases(release_dir).iter().map(|r| r.version.as_str()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_copy_from_local_mirror() {
+        let mirror_tmp = TempDir::new().unwrap();
+        let mirror = mirror_tmp.path();
+        let releases_tmp = TempDir::new().unwrap();
+        let release_dir = releases_tmp.path();
+        fs::create_dir_all(mirror.join("2025-05-06")).unwrap();
+        fs::write(mirror.join("2025-05-06").join(HPO_JSON_FILE), HP_JSON).unwrap();
+        fs::write(mirror.join("2025-05-06").join(CHECKSUM_FILE), format!("{}  hp.json\n", sha256_hex(HP_JSON))).unwrap();
+        let source = HpoReleaseSource::LocalMirror { directory: mirror.to_string_lossy().to_string() };
+        let mut releases = list_releases(&source, release_dir).unwrap();
+        assert_eq!(Some(sha256_hex(HP_JSON)), releases[0].sha256);
+        download_release(&releases[0], release_dir, |_, _| {}).unwrap();
+        assert!(list_releases(&source, release_dir).unwrap()[0].installed);
+        // a corrupted file is rejected and does not replace the installed release
+        releases[0].sha256 = Some("0".repeat(64));
+        assert!(download_release(&releases[0], release_dir, |_, _| {}).is_err());
+        assert_eq!(1, installed_releases(release_dir).len());
+        assert!(!release_dir.join("hp.json.download").exists());
+    }
+}
+
+// endregion: --- Tests
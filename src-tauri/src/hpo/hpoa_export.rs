@@ -0,0 +1,495 @@
+//! Export of cohorts to the HPOA "small file" format
+//!
+//! The HPO annotation project collects one tab-separated file per disease (e.g., `OMIM-154700.tab`) with the
+//! columns given in [`HPOA_COLUMNS`]. For each disease of the cohort (melded cohorts have several), we count for
+//! each publication how many individuals were observed to have a feature (n) among those in which it was
+//! assessed (m), and write the frequency as `n/m`. Features that were excluded in all assessed individuals get
+//! the negation `NOT`. We also add one row per age-of-onset category and per mode of inheritance.
+//!
+//! The rows can be previewed before writing. Each file is checked with [`validate_hpoa_tsv`] (format) and
+//! [`validate_hpoa_terms`] (all HPO terms must be current terms of the loaded ontology) before it is written.
+
+use std::{collections::{BTreeMap, BTreeSet}, fs, path::Path, sync::LazyLock};
+
+use ontolius::{ontology::{csr::FullCsrOntology, OntologyTerms}, term::MinimalTerm, Identified, TermId};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{hpo::onset::{onset_category, onset_term}, util::{json_fields::{required_str, str_field}, pubmed_retrieval::PubmedRetriever}};
+
+/// Columns of the HPOA small file format
+pub const HPOA_COLUMNS: [&str; 14] = [
+    "#diseaseID", "diseaseName", "phenotypeID", "phenotypeName", "onsetID", "onsetName", "frequency",
+    "sex", "negation", "modifier", "description", "publication", "evidence", "biocuration",
+];
+/// Evidence code for annotations from published clinical studies
+const EVIDENCE_PCS: &str = "PCS";
+
+static DISEASE_ID_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(OMIM:\d{6}|MONDO:\d{7}|ORPHA:\d+|DECIPHER:\d+)$").unwrap());
+static HPO_ID_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^HP:\d{7}$").unwrap());
+static FREQUENCY_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(\d+)/(\d+)$|^\d+(\.\d+)?%$|^HP:\d{7}$").unwrap());
+static PUBLICATION_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(PMID:\d+|OMIM:\d{6}|ORPHA:\d+|DECIPHER:\d+|ISBN[-:]?\S+|https?://\S+)$").unwrap());
+static BIOCURATION_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^ORCID:\d{4}-\d{4}-\d{4}-\d{3}[\dX]\[\d{4}-\d{2}-\d{2}\]$").unwrap());
+
+
+/// One row of an HPOA file
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HpoaRow {
+    pub disease_id: String,
+    pub disease_name: String,
+    pub phenotype_id: String,
+    pub phenotype_name: String,
+    pub onset_id: String,
+    pub onset_name: String,
+    /// e.g., `3/5`
+    pub frequency: String,
+    pub sex: String,
+    /// `NOT` or empty
+    pub negation: String,
+    /// HPO ids of modifiers, separated by semicolons
+    pub modifier: String,
+    pub description: String,
+    pub publication: String,
+    pub evidence: String,
+    /// e.g., `ORCID:0000-0002-0736-9199[2025-01-31]`
+    pub biocuration: String,
+}
+
+impl HpoaRow {
+    fn fields(&self) -> [&str; 14] {
+        [
+            &self.disease_id, &self.disease_name, &self.phenotype_id, &self.phenotype_name, &self.onset_id,
+            &self.onset_name, &self.frequency, &self.sex, &self.negation, &self.modifier, &self.description,
+            &self.publication, &self.evidence, &self.biocuration,
+        ]
+    }
+}
+
+/// The rows of the HPOA file of one disease
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HpoaFilePreview {
+    pub disease_id: String,
+    pub disease_name: String,
+    /// e.g., `OMIM-154700.tab`
+    pub file_name: String,
+    pub rows: Vec<HpoaRow>,
+    /// Problems found by [`validate_hpoa_tsv`] and [`validate_hpoa_terms`]; the file is not written if there are any
+    pub errors: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HpoaPreview {
+    pub files: Vec<HpoaFilePreview>,
+}
+
+/// Result of writing the HPOA files of a cohort
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HpoaExportSummary {
+    pub out_dir: String,
+    /// Paths of the files that were written
+    pub files: Vec<String>,
+    pub n_rows: usize,
+}
+
+
+/// Counts of one feature within the individuals of one publication
+#[derive(Default)]
+struct FeatureCounts {
+    observed: usize,
+    excluded: usize,
+    onsets: BTreeSet<String>,
+    modifiers: BTreeSet<String>,
+}
+
+fn publication_id(pmid: &str) -> String {
+    match PubmedRetriever::extract_pmid(pmid) {
+        Some(numerical) => format!("PMID:{}", numerical),
+        None => pmid.trim().to_string(),
+    }
+}
+
+/// `OMIM:154700` → `OMIM-154700.tab`
+pub fn hpoa_file_name(disease_id: &str) -> String {
+    format!("{}.tab", disease_id.replace(':', "-"))
+}
+
+/// Create the HPOA rows of each disease of a cohort.
+///
+/// # Arguments
+/// * `cohort` - the cohort (CohortData) as JSON
+/// * `is_current_term` - whether an HPO id is a current term of the ontology (see [`is_current_term`])
+/// * `orcid` - ORCID of the biocurator (normalized form, e.g., `0000-0002-0736-9199`)
+/// * `date` - date of the biocuration (`YYYY-MM-DD`)
+///
+/// Returns an error if an HPO column, disease or mode of inheritance lacks its identifier or label.
+pub fn build_hpoa(
+    cohort: &Value,
+    is_current_term: &dyn Fn(&str) -> bool,
+    orcid: &str,
+    date: &str,
+) -> Result<HpoaPreview, String> {
+    let biocuration = format!("ORCID:{}[{}]", orcid, date);
+    let headers: Vec<(String, String)> = cohort["hpoHeaders"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .map(|(i, d)| {
+            let context = format!("HPO column {}", i + 1);
+            Ok((required_str(d, "hpoId", &context)?, required_str(d, "hpoLabel", &context)?))
+        })
+        .collect::<Result<_, String>>()?;
+    let diseases: Vec<&Value> = cohort["diseaseList"].as_array().map(|d| d.iter().collect()).unwrap_or_default();
+    let rows: Vec<&Value> = cohort["rows"].as_array().map(|r| r.iter().collect()).unwrap_or_default();
+    let mut files = Vec::new();
+    for disease in &diseases {
+        let disease_id = required_str(disease, "diseaseId", "Disease")?;
+        let disease_name = required_str(disease, "diseaseLabel", &disease_id)?;
+        let template = HpoaRow {
+            disease_id: disease_id.clone(),
+            disease_name: disease_name.clone(),
+            evidence: EVIDENCE_PCS.to_string(),
+            biocuration: biocuration.clone(),
+            ..Default::default()
+        };
+        // individuals with this disease, grouped by publication
+        let mut by_publication: BTreeMap<String, Vec<&Value>> = BTreeMap::new();
+        for row in &rows {
+            let disease_ids: Vec<&str> = row["diseaseIdList"].as_array()
+                .map(|ids| ids.iter().filter_map(|id| id.as_str()).collect())
+                .unwrap_or_default();
+            let has_disease = disease_ids.contains(&disease_id.as_str())
+                || (disease_ids.is_empty() && diseases.len() == 1);
+            if has_disease {
+                let publication = publication_id(&str_field(&row["individualData"], "pmid"));
+                by_publication.entry(publication).or_default().push(row);
+            }
+        }
+        let mut hpoa_rows = Vec::new();
+        for (publication, individuals) in &by_publication {
+            hpoa_rows.extend(feature_rows(&template, publication, individuals, &headers));
+            hpoa_rows.extend(onset_rows(&template, publication, individuals));
+        }
+        for moi in disease["modeOfInheritanceList"].as_array().into_iter().flatten() {
+            let context = format!("Mode of inheritance of {}", disease_id);
+            hpoa_rows.push(HpoaRow {
+                phenotype_id: required_str(moi, "hpoId", &context)?,
+                phenotype_name: required_str(moi, "hpoLabel", &context)?,
+                publication: publication_id(&str_field(moi, "citation")),
+                ..template.clone()
+            });
+        }
+        let mut preview = HpoaFilePreview {
+            file_name: hpoa_file_name(&disease_id),
+            disease_id,
+            disease_name,
+            rows: hpoa_rows,
+            errors: vec![],
+        };
+        preview.errors = validate_hpoa_tsv(&to_tsv(&preview.rows));
+        preview.errors.extend(validate_hpoa_terms(&preview.rows, is_current_term));
+        files.push(preview);
+    }
+    Ok(HpoaPreview { files })
+}
+
+/// One row per HPO term that was assessed in at least one individual of the publication
+fn feature_rows(template: &HpoaRow, publication: &str, individuals: &[&Value], headers: &[(String, String)]) -> Vec<HpoaRow> {
+    let mut counts: Vec<FeatureCounts> = headers.iter().map(|_| FeatureCounts::default()).collect();
+    for row in individuals {
+        for (i, cell) in row["hpoData"].as_array().into_iter().flatten().enumerate() {
+            let Some(feature) = counts.get_mut(i) else { continue; };
+            match cell["type"].as_str() {
+                Some("Observed") => feature.observed += 1,
+                Some("OnsetAge") => {
+                    feature.observed += 1;
+                    feature.onsets.insert(onset_category(cell["data"].as_str().unwrap_or_default()));
+                },
+                Some("Excluded") => feature.excluded += 1,
+                _ => continue,
+            }
+            for modifier in cell["modifiers"].as_array().into_iter().flatten().filter_map(|m| m.as_str()) {
+                feature.modifiers.insert(modifier.to_string());
+            }
+        }
+    }
+    headers
+        .iter()
+        .zip(counts)
+        .filter(|(_, c)| c.observed + c.excluded > 0)
+        .map(|((hpo_id, hpo_label), c)| {
+            // the onset of a feature is only given if all individuals with an onset agree
+            let onset = match c.onsets.len() {
+                1 => c.onsets.iter().next().and_then(|o| onset_term(o)),
+                _ => None,
+            };
+            HpoaRow {
+                phenotype_id: hpo_id.clone(),
+                phenotype_name: hpo_label.clone(),
+                onset_id: onset.map(|(id, _)| id.to_string()).unwrap_or_default(),
+                onset_name: onset.map(|(_, label)| label.to_string()).unwrap_or_default(),
+                frequency: format!("{}/{}", c.observed, c.observed + c.excluded),
+                negation: if c.observed == 0 { "NOT".to_string() } else { String::new() },
+                modifier: c.modifiers.into_iter().collect::<Vec<_>>().join(";"),
+                publication: publication.to_string(),
+                ..template.clone()
+            }
+        })
+        .collect()
+}
+
+/// One row per onset category of the individuals of the publication (frequency among those with a known onset)
+fn onset_rows(template: &HpoaRow, publication: &str, individuals: &[&Value]) -> Vec<HpoaRow> {
+    let onsets: Vec<(&str, &str)> = individuals
+        .iter()
+        .filter_map(|row| onset_term(&onset_category(&str_field(&row["individualData"], "ageOfOnset"))))
+        .collect();
+    let mut counts: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+    for onset in &onsets {
+        *counts.entry(*onset).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .map(|((id, label), n)| HpoaRow {
+            phenotype_id: id.to_string(),
+            phenotype_name: label.to_string(),
+            frequency: format!("{}/{}", n, onsets.len()),
+            publication: publication.to_string(),
+            ..template.clone()
+        })
+        .collect()
+}
+
+pub fn to_tsv(rows: &[HpoaRow]) -> String {
+    let mut tsv = HPOA_COLUMNS.join("\t");
+    tsv.push('\n');
+    for row in rows {
+        let fields: Vec<String> = row.fields().iter().map(|f| f.replace(['\t', '\n', '\r'], " ")).collect();
+        tsv.push_str(&fields.join("\t"));
+        tsv.push('\n');
+    }
+    tsv
+}
+
+/// Check an HPOA small file against the column specification; returns one message per problem
+pub fn validate_hpoa_tsv(tsv: &str) -> Vec<String> {
+    let mut errors = Vec::new();
+    let mut lines = tsv.lines();
+    match lines.next() {
+        Some(header) if header.split('\t').eq(HPOA_COLUMNS.iter().copied()) => {},
+        Some(header) => errors.push(format!("Invalid header: '{}'", header)),
+        None => {
+            errors.push("Empty HPOA file".to_string());
+            return errors;
+        }
+    }
+    let mut disease_ids: BTreeSet<String> = BTreeSet::new();
+    let mut n_rows = 0;
+    for (i, line) in lines.enumerate() {
+        let line_number = i + 2;
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != HPOA_COLUMNS.len() {
+            errors.push(format!("Line {}: expected {} columns but found {}", line_number, HPOA_COLUMNS.len(), fields.len()));
+            continue;
+        }
+        n_rows += 1;
+        let mut error = |column: usize, message: &str| {
+            errors.push(format!("Line {}: {} '{}' {}", line_number, HPOA_COLUMNS[column], fields[column], message));
+        };
+        if !DISEASE_ID_RE.is_match(fields[0]) {
+            error(0, "is not a valid disease identifier");
+        }
+        disease_ids.insert(fields[0].to_string());
+        if fields[1].is_empty() {
+            error(1, "must not be empty");
+        }
+        if !HPO_ID_RE.is_match(fields[2]) {
+            error(2, "is not a valid HPO identifier");
+        }
+        if fields[3].is_empty() {
+            error(3, "must not be empty");
+        }
+        if !fields[4].is_empty() && !HPO_ID_RE.is_match(fields[4]) {
+            error(4, "is not a valid HPO identifier");
+        }
+        if !fields[6].is_empty() {
+            match FREQUENCY_RE.captures(fields[6]) {
+                Some(caps) => {
+                    if let (Some(n), Some(m)) = (caps.get(1), caps.get(2)) {
+                        let n: usize = n.as_str().parse().unwrap_or(0);
+                        let m: usize = m.as_str().parse().unwrap_or(0);
+                        if m == 0 || n > m {
+                            error(6, "must satisfy 0 <= n <= m and m > 0");
+                        }
+                    }
+                },
+                None => error(6, "must be n/m, a percentage or an HPO frequency term"),
+            }
+        }
+        if !matches!(fields[7], "" | "MALE" | "FEMALE") {
+            error(7, "must be MALE, FEMALE or empty");
+        }
+        if !matches!(fields[8], "" | "NOT") {
+            error(8, "must be NOT or empty");
+        }
+        if !fields[9].is_empty() && !fields[9].split(';').all(|m| HPO_ID_RE.is_match(m)) {
+            error(9, "must be a semicolon-separated list of HPO identifiers");
+        }
+        if !PUBLICATION_RE.is_match(fields[11]) {
+            error(11, "is not a valid publication identifier");
+        }
+        if !matches!(fields[12], "PCS" | "TAS" | "IEA") {
+            error(12, "must be PCS, TAS or IEA");
+        }
+        if !fields[13].split(';').all(|b| BIOCURATION_RE.is_match(b)) {
+            error(13, "must have the form ORCID:0000-0000-0000-0000[YYYY-MM-DD]");
+        }
+    }
+    if n_rows == 0 {
+        errors.push("The HPOA file has no annotations".to_string());
+    }
+    if disease_ids.len() > 1 {
+        errors.push(format!("An HPOA file must have a single disease but found {}", disease_ids.into_iter().collect::<Vec<_>>().join(", ")));
+    }
+    errors
+}
+
+/// `true` if `hpo_id` is the primary id of a non-obsolete term of the ontology
+pub fn is_current_term(hpo: &FullCsrOntology, hpo_id: &str) -> bool {
+    let Ok(term_id) = hpo_id.parse::<TermId>() else {
+        return false;
+    };
+    hpo.term_by_id(&term_id)
+        .is_some_and(|term| term.identifier() == &term_id && !term.is_obsolete())
+}
+
+/// Check that the phenotype, onset and modifier terms of the rows are current HPO terms; returns one message
+/// per unknown, obsolete or replaced (alternative) identifier
+pub fn validate_hpoa_terms(rows: &[HpoaRow], is_current_term: &dyn Fn(&str) -> bool) -> Vec<String> {
+    let mut invalid: BTreeSet<&str> = BTreeSet::new();
+    for row in rows {
+        let modifiers = row.modifier.split(';').filter(|m| !m.is_empty());
+        for hpo_id in [row.phenotype_id.as_str(), row.onset_id.as_str()].into_iter().filter(|id| !id.is_empty()).chain(modifiers) {
+            if !is_current_term(hpo_id) {
+                invalid.insert(hpo_id);
+            }
+        }
+    }
+    invalid
+        .into_iter()
+        .map(|hpo_id| format!("{} is not a current HPO term (unknown, obsolete or replaced)", hpo_id))
+        .collect()
+}
+
+/// Write one HPOA file per disease to `out_dir`. Nothing is written if any file fails validation.
+pub fn write_hpoa_files(preview: &HpoaPreview, out_dir: &Path) -> Result<HpoaExportSummary, String> {
+    let invalid: Vec<String> = preview.files
+        .iter()
+        .filter(|f| !f.errors.is_empty())
+        .map(|f| format!("{}: {}", f.file_name, f.errors.join("; ")))
+        .collect();
+    if !invalid.is_empty() {
+        return Err(format!("HPOA validation failed: {}", invalid.join(" | ")));
+    }
+    let mut summary = HpoaExportSummary { out_dir: out_dir.to_string_lossy().to_string(), ..Default::default() };
+    for file in &preview.files {
+        let path = out_dir.join(&file.file_name);
+        fs::write(&path, to_tsv(&file.rows)).map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+        summary.files.push(path.to_string_lossy().to_string());
+        summary.n_rows += file.rows.len();
+    }
+    Ok(summary)
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn melded_cohort() -> Value {
+        serde_json::json!({
+            "cohortType": "melded",
+            "diseaseList": [
+                {"diseaseId": "OMIM:154700", "diseaseLabel": "Marfan syndrome",
+                 "modeOfInheritanceList": [{"hpoId": "HP:0000006", "hpoLabel": "Autosomal dominant inheritance", "citation": "PMID:1"}]},
+                {"diseaseId": "OMIM:614100", "diseaseLabel": "Other disease", "modeOfInheritanceList": []}
+            ],
+            "hpoHeaders": [{"hpoId": "HP:0001166", "hpoLabel": "Arachnodactyly"}, {"hpoId": "HP:0001250", "hpoLabel": "Seizure"}],
+            "rows": [
+                {"individualData": {"pmid": "PMID:1", "ageOfOnset": "Congenital onset"}, "diseaseIdList": ["OMIM:154700"],
+                 "hpoData": [{"type": "OnsetAge", "data": "Congenital onset", "modifiers": ["HP:0012828"]}, {"type": "Excluded"}]},
+                {"individualData": {"pmid": "PMID:1", "ageOfOnset": "P2Y"}, "diseaseIdList": ["OMIM:154700"],
+                 "hpoData": [{"type": "Excluded"}, {"type": "Na"}]},
+                {"individualData": {"pmid": "PMID:2", "ageOfOnset": "na"}, "diseaseIdList": ["OMIM:154700", "OMIM:614100"],
+                 "hpoData": [{"type": "Observed"}, {"type": "Observed"}]}
+            ]
+        })
+    }
+
+    #[test]
+    fn test_build_hpoa() {
+        let preview = build_hpoa(&melded_cohort(), &|_| true, "0000-0002-0736-9199", "2025-01-31").unwrap();
+        assert_eq!(2, preview.files.len());
+        let marfan = &preview.files[0];
+        assert_eq!("OMIM-154700.tab", marfan.file_name);
+        assert!(marfan.errors.is_empty(), "{:?}", marfan.errors);
+        let row = |id: &str, publication: &str| marfan.rows.iter().find(|r| r.phenotype_id == id && r.publication == publication).unwrap();
+        let arachnodactyly = row("HP:0001166", "PMID:1");
+        assert_eq!("1/2", arachnodactyly.frequency);
+        assert_eq!("HP:0003577", arachnodactyly.onset_id);
+        assert_eq!("HP:0012828", arachnodactyly.modifier);
+        assert_eq!("NOT", row("HP:0001250", "PMID:1").negation);
+        assert_eq!("1/2", row("HP:0003577", "PMID:1").frequency);
+        assert_eq!("1/2", row("HP:0011463", "PMID:1").frequency);
+        assert_eq!("ORCID:0000-0002-0736-9199[2025-01-31]", row("HP:0000006", "PMID:1").biocuration);
+        // the individual of PMID:2 has both diseases
+        assert_eq!(2, preview.files[1].rows.len());
+    }
+
+    #[test]
+    fn test_missing_identifier_is_an_error() {
+        let mut cohort = melded_cohort();
+        cohort["hpoHeaders"][1]["hpoId"] = Value::Null;
+        assert_eq!(
+            Err("HPO column 2: missing field 'hpoId'".to_string()),
+            build_hpoa(&cohort, &|_| true, "0000-0002-0736-9199", "2025-01-31").map(|_| ())
+        );
+    }
+
+    #[test]
+    fn test_validate_hpoa() {
+        let preview = build_hpoa(&melded_cohort(), &|_| true, "0000-0002-0736-9199", "2025-01-31").unwrap();
+        let mut rows = preview.files[0].rows.clone();
+        rows[0].frequency = "3/2".to_string();
+        rows[1].phenotype_id = "HP:123".to_string();
+        rows[2].disease_id = "OMIM:614100".to_string();
+        let errors = validate_hpoa_tsv(&to_tsv(&rows));
+        assert_eq!(3, errors.len(), "{:?}", errors);
+        assert!(!validate_hpoa_tsv("diseaseID\tphenotypeID\n").is_empty());
+    }
+
+    #[test]
+    fn test_terms_must_be_current() {
+        // e.g., HP:0012828 (Severe) was made obsolete, HP:0001250 replaced by another id
+        let is_current_term = |hpo_id: &str| !matches!(hpo_id, "HP:0012828" | "HP:0001250");
+        let preview = build_hpoa(&melded_cohort(), &is_current_term, "0000-0002-0736-9199", "2025-01-31").unwrap();
+        assert_eq!(
+            vec![
+                "HP:0001250 is not a current HPO term (unknown, obsolete or replaced)",
+                "HP:0012828 is not a current HPO term (unknown, obsolete or replaced)",
+            ],
+            preview.files[0].errors
+        );
+        assert!(write_hpoa_files(&preview, Path::new("/nonexistent")).is_err());
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,329 @@
+//! Convert confirmed text-mining hits into a new row of a cohort
+//!
+//! After mining a clinical text, the curator confirms a list of hits (HPO term, observed/excluded, onset).
+//! Here we add any missing HPO columns to the cohort, check the terms for redundancy, and add the
+//! new row in a single step.
+
+use std::sync::Arc;
+
+use ga4ghphetools::dto::{cohort_dto::{CohortData, IndividualData}, hpo_term_dto::{CellValue, CellValueInner, HpoTermData, HpoTermDuplet}};
+use ontolius::{ontology::{csr::FullCsrOntology, HierarchyQueries}, TermId};
+use serde::{Deserialize, Serialize};
+
+use crate::hpo::{ClinicalStatus, MappedTerm};
+
+
+/// An HPO term that was not added to the new row because it is implied by another term of the row.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedundantTerm {
+    pub hpo_id: String,
+    pub hpo_label: String,
+    pub status: ClinicalStatus,
+    /// The term of the row that makes `hpo_id` redundant
+    pub implied_by_id: String,
+    pub implied_by_label: String,
+}
+
+/// An HPO term that was mined more than once with different statuses (e.g., observed in one
+/// sentence and excluded in another). The first status is kept; the curator should check the text.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusConflict {
+    pub hpo_id: String,
+    pub hpo_label: String,
+    /// The status that was used for the new row
+    pub kept_status: ClinicalStatus,
+    pub dropped_status: ClinicalStatus,
+}
+
+/// Result of adding a row from mined text to a cohort
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MinedRowResult {
+    /// The updated cohort, including the new row
+    pub cohort_data: CohortData,
+    /// HPO ids of the columns that needed to be added to the cohort
+    pub added_columns: Vec<String>,
+    /// Terms that were left out of the row because they were redundant
+    pub redundant_terms: Vec<RedundantTerm>,
+    /// Terms that were mined with conflicting statuses
+    pub status_conflicts: Vec<StatusConflict>,
+}
+
+
+/// Add a new row to the cohort using the confirmed hits of text mining
+///
+/// # Arguments
+/// * `hpo` - reference to the HPO
+/// * `individual_data` - demographic information about the individual
+/// * `mined_terms` - confirmed hits, each with status (observed/excluded) and onset
+/// * `variant_key_list` - keys of validated variants for the new row
+/// * `cohort_data` - the cohort to which we add the row
+///
+/// # Notes
+/// An observed term is redundant if a descendant of it is also observed in the same row
+/// (e.g., *Seizure* is implied by *Focal-onset seizure*), and an excluded term is redundant if
+/// one of its ancestors is also excluded. Redundant terms are reported and not added to the row.
+/// Terms with status `na` are ignored. If a term was mined several times with different statuses,
+/// the first one is used and the conflict is reported.
+pub fn add_mined_row_to_cohort(
+    hpo: Arc<FullCsrOntology>,
+    individual_data: IndividualData,
+    mined_terms: Vec<MappedTerm>,
+    variant_key_list: Vec<String>,
+    cohort_data: CohortData,
+) -> Result<MinedRowResult, String> {
+    let (mined_terms, status_conflicts) = prepare_terms(mined_terms);
+    let (retained, redundant_terms) = partition_redundant(
+        mined_terms, 
+        |ancestor, descendant| hpo.is_ancestor_of(ancestor, descendant))?;
+    let mut cohort = cohort_data;
+    let mut added_columns: Vec<String> = Vec::new();
+    for mt in missing_columns(&cohort.hpo_headers, &retained) {
+        cohort = ga4ghphetools::factory::add_hpo_term_to_cohort(&mt.hpo_id, &mt.hpo_label, hpo.clone(), cohort)?;
+        added_columns.push(mt.hpo_id.clone());
+    }
+    let hpo_annotations: Vec<HpoTermData> = retained
+        .iter()
+        .map(to_hpo_term_data)
+        .collect();
+    let cohort_data = ga4ghphetools::factory::add_new_row_to_cohort(
+        hpo,
+        individual_data,
+        hpo_annotations,
+        variant_key_list,
+        cohort,
+    )?;
+    Ok(MinedRowResult {
+        cohort_data,
+        added_columns,
+        redundant_terms,
+        status_conflicts,
+    })
+}
+
+/// Remove duplicate terms and terms with status `na`
+fn prepare_terms(mined_terms: Vec<MappedTerm>) -> (Vec<MappedTerm>, Vec<StatusConflict>) {
+    let (unique, conflicts) = dedup_terms(mined_terms);
+    let assessed = unique
+        .into_iter()
+        .filter(|mt| mt.status != ClinicalStatus::NotAssessed)
+        .collect();
+    (assessed, conflicts)
+}
+
+/// The same term may be mined from several sentences; keep the first occurrence and
+/// report the occurrences whose status differs from it.
+fn dedup_terms(mined_terms: Vec<MappedTerm>) -> (Vec<MappedTerm>, Vec<StatusConflict>) {
+    let mut unique: Vec<MappedTerm> = Vec::new();
+    let mut conflicts: Vec<StatusConflict> = Vec::new();
+    for mt in mined_terms {
+        if let Some(existing) = unique.iter().find(|u| u.hpo_id == mt.hpo_id) {
+            let already_reported = conflicts
+                .iter()
+                .any(|c| c.hpo_id == mt.hpo_id && c.dropped_status == mt.status);
+            if existing.status != mt.status && ! already_reported {
+                conflicts.push(StatusConflict {
+                    hpo_id: mt.hpo_id.clone(),
+                    hpo_label: mt.hpo_label.clone(),
+                    kept_status: existing.status,
+                    dropped_status: mt.status,
+                });
+            }
+            continue;
+        }
+        unique.push(mt);
+    }
+    (unique, conflicts)
+}
+
+/// Split the terms into those we keep and those that are implied by another term of the same row
+///
+/// `is_ancestor_of(a, b)` must return true if `a` is a (proper) ancestor of `b` in the HPO.
+fn partition_redundant<F>(
+    mined_terms: Vec<MappedTerm>,
+    is_ancestor_of: F,
+) -> Result<(Vec<MappedTerm>, Vec<RedundantTerm>), String> 
+where
+    F: Fn(&TermId, &TermId) -> bool,
+{
+    let term_ids = mined_terms
+        .iter()
+        .map(|mt| mt.hpo_id.parse::<TermId>().map_err(|_| format!("Invalid HPO id: '{}'", mt.hpo_id)))
+        .collect::<Result<Vec<TermId>, String>>()?;
+    let mut retained: Vec<MappedTerm> = Vec::new();
+    let mut redundant: Vec<RedundantTerm> = Vec::new();
+    for (i, mt) in mined_terms.iter().enumerate() {
+        let implied_by = mined_terms.iter().enumerate().find(|(j, other)| {
+            if i == *j || other.status != mt.status {
+                return false;
+            }
+            match mt.status {
+                ClinicalStatus::Observed => is_ancestor_of(&term_ids[i], &term_ids[*j]),
+                ClinicalStatus::Excluded => is_ancestor_of(&term_ids[*j], &term_ids[i]),
+                ClinicalStatus::NotAssessed => false,
+            }
+        });
+        match implied_by {
+            Some((_, other)) => redundant.push(RedundantTerm {
+                hpo_id: mt.hpo_id.clone(),
+                hpo_label: mt.hpo_label.clone(),
+                status: mt.status,
+                implied_by_id: other.hpo_id.clone(),
+                implied_by_label: other.hpo_label.clone(),
+            }),
+            None => retained.push(mt.clone()),
+        }
+    }
+    Ok((retained, redundant))
+}
+
+/// Terms that do not yet have a column in the cohort, in the order of the row
+fn missing_columns<'a>(hpo_headers: &[HpoTermDuplet], terms: &'a [MappedTerm]) -> Vec<&'a MappedTerm> {
+    terms
+        .iter()
+        .filter(|mt| ! hpo_headers.iter().any(|h| h.hpo_id == mt.hpo_id))
+        .collect()
+}
+
+/// Convert a mapped term into the representation used for cells of the cohort table.
+///
+/// An observed term with an onset other than `na` is recorded as onset age, which implies observed.
+fn to_hpo_term_data(mt: &MappedTerm) -> HpoTermData {
+    let onset = mt.onset.trim();
+    let entry = match mt.status {
+        ClinicalStatus::Observed if onset != "na" && !onset.is_empty() => CellValueInner::OnsetAge(onset.to_string()),
+        ClinicalStatus::Observed => CellValueInner::Observed,
+        ClinicalStatus::Excluded => CellValueInner::Excluded,
+        ClinicalStatus::NotAssessed => CellValueInner::Na,
+    };
+    HpoTermData {
+        term_duplet: HpoTermDuplet {
+            hpo_label: mt.hpo_label.clone(),
+            hpo_id: mt.hpo_id.clone(),
+        },
+        entry: CellValue {
+            entry,
+            modifiers: mt.modifiers.clone(),
+        },
+    }
+}
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Small excerpt of the HPO: Seizure > Focal-onset seizure > Focal motor seizure
+    const ANCESTRY: [(&str, &str); 3] = [
+        ("HP:0001250", "HP:0007359"),
+        ("HP:0001250", "HP:0020219"),
+        ("HP:0007359", "HP:0020219"),
+    ];
+
+    fn is_ancestor_of(ancestor: &TermId, descendant: &TermId) -> bool {
+        ANCESTRY.iter().any(|(a, d)| *a == ancestor.to_string() && *d == descendant.to_string())
+    }
+
+    fn term(hpo_id: &str, hpo_label: &str, status: ClinicalStatus) -> MappedTerm {
+        MappedTerm { status, ..MappedTerm::new(hpo_id, hpo_label) }
+    }
+
+    fn ids(terms: &[MappedTerm]) -> Vec<&str> {
+        terms.iter().map(|mt| mt.hpo_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_observed_ancestor_is_redundant() {
+        let terms = vec![
+            term("HP:0001250", "Seizure", ClinicalStatus::Observed),
+            term("HP:0007359", "Focal-onset seizure", ClinicalStatus::Observed),
+        ];
+        let (retained, redundant) = partition_redundant(terms, is_ancestor_of).unwrap();
+        assert_eq!(vec!["HP:0007359"], ids(&retained));
+        assert_eq!(1, redundant.len());
+        assert_eq!("HP:0001250", redundant[0].hpo_id);
+        assert_eq!("HP:0007359", redundant[0].implied_by_id);
+    }
+
+    #[test]
+    fn test_excluded_descendant_is_redundant() {
+        let terms = vec![
+            term("HP:0020219", "Focal motor seizure", ClinicalStatus::Excluded),
+            term("HP:0001250", "Seizure", ClinicalStatus::Excluded),
+        ];
+        let (retained, redundant) = partition_redundant(terms, is_ancestor_of).unwrap();
+        assert_eq!(vec!["HP:0001250"], ids(&retained));
+        assert_eq!(1, redundant.len());
+        assert_eq!("HP:0020219", redundant[0].hpo_id);
+        assert_eq!("HP:0001250", redundant[0].implied_by_id);
+    }
+
+    #[test]
+    fn test_different_status_is_not_redundant() {
+        let terms = vec![
+            term("HP:0001250", "Seizure", ClinicalStatus::Observed),
+            term("HP:0007359", "Focal-onset seizure", ClinicalStatus::Excluded),
+        ];
+        let (retained, redundant) = partition_redundant(terms, is_ancestor_of).unwrap();
+        assert_eq!(2, retained.len());
+        assert!(redundant.is_empty());
+    }
+
+    #[test]
+    fn test_dedup_reports_conflicting_status() {
+        let terms = vec![
+            term("HP:0001250", "Seizure", ClinicalStatus::Observed),
+            term("HP:0001250", "Seizure", ClinicalStatus::Excluded),
+            term("HP:0001250", "Seizure", ClinicalStatus::Excluded),
+            term("HP:0001250", "Seizure", ClinicalStatus::Observed),
+        ];
+        let (unique, conflicts) = dedup_terms(terms);
+        assert_eq!(1, unique.len());
+        assert_eq!(ClinicalStatus::Observed, unique[0].status);
+        assert_eq!(1, conflicts.len());
+        assert_eq!(ClinicalStatus::Observed, conflicts[0].kept_status);
+        assert_eq!(ClinicalStatus::Excluded, conflicts[0].dropped_status);
+    }
+
+    #[test]
+    fn test_not_assessed_terms_are_dropped() {
+        let terms = vec![
+            term("HP:0001250", "Seizure", ClinicalStatus::NotAssessed),
+            term("HP:0001263", "Global developmental delay", ClinicalStatus::Observed),
+        ];
+        let retained = prepare_terms(terms).0;
+        assert_eq!(vec!["HP:0001263"], ids(&retained));
+    }
+
+    #[test]
+    fn test_missing_columns() {
+        let headers = vec![HpoTermDuplet {
+            hpo_label: "Seizure".to_string(),
+            hpo_id: "HP:0001250".to_string(),
+        }];
+        let terms = vec![
+            term("HP:0001250", "Seizure", ClinicalStatus::Observed),
+            term("HP:0001263", "Global developmental delay", ClinicalStatus::Excluded),
+        ];
+        let missing = missing_columns(&headers, &terms);
+        assert_eq!(1, missing.len());
+        assert_eq!("HP:0001263", missing[0].hpo_id);
+    }
+
+    #[test]
+    fn test_onset_is_recorded_for_observed_terms() {
+        let mut mt = term("HP:0001250", "Seizure", ClinicalStatus::Observed);
+        mt.onset = " P2Y ".to_string();
+        let data = to_hpo_term_data(&mt);
+        assert_eq!("HP:0001250", data.term_duplet.hpo_id);
+        assert!(matches!(data.entry.entry, CellValueInner::OnsetAge(ref onset) if onset == "P2Y"));
+        mt.status = ClinicalStatus::Excluded;
+        assert!(matches!(to_hpo_term_data(&mt).entry.entry, CellValueInner::Excluded));
+    }
+}
+
+// endregion: --- Tests
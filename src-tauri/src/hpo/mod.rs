@@ -1,8 +1,18 @@
 
+use std::str::FromStr;
+
 use serde::{Deserialize, Serialize};
 use fenominal::OntologyMatch;
 
+pub mod batch_mining;
 pub mod hpo_etl;
+pub mod hpo_release;
+pub mod hpoa_export;
+pub mod ontology_cache;
+pub mod mined_row;
+pub mod multi_hpo_format;
+pub mod onset;
+pub mod text_annotation;
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -12,9 +22,10 @@ pub enum MiningStatus {
     Skipped,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum ClinicalStatus {
+    #[default]
     Observed,
     Excluded,
     #[serde(rename = "na")]
@@ -32,6 +43,19 @@ impl ClinicalStatus {
     
 }
 
+impl FromStr for ClinicalStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "observed" => Ok(ClinicalStatus::Observed),
+            "excluded" => Ok(ClinicalStatus::Excluded),
+            "na" => Ok(ClinicalStatus::NotAssessed),
+            other => Err(format!("Unrecognized clinical status '{}'", other)),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MiningConcept {
@@ -39,6 +63,10 @@ pub struct MiningConcept {
   pub(crate) row_index_list: Vec<usize>,
   pub(crate) suggested_terms: Vec<OntologyMatch>,
   pub(crate) mining_status: MiningStatus,
+  /// Whether the concept describes an observed or an excluded feature (e.g., "no seizures");
+  /// set by the curator in the concept review
+  #[serde(default)]
+  pub(crate) clinical_status: ClinicalStatus,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -47,7 +75,10 @@ pub struct MappedTerm {
     hpo_id: String,
     hpo_label: String,
     status: ClinicalStatus,
-    onset: String
+    onset: String,
+    /// HPO ids of modifiers such as severity (e.g., HP:0012828, Severe)
+    #[serde(default)]
+    modifiers: Vec<String>,
 }
 
 impl MappedTerm {
@@ -56,7 +87,8 @@ impl MappedTerm {
             hpo_id: hpo_id.to_string(), 
             hpo_label: hpo_label.to_string(), 
             status: ClinicalStatus::Observed, 
-            onset: "na".to_string()
+            onset: "na".to_string(),
+            modifiers: Vec::new(),
         }
     }
 }
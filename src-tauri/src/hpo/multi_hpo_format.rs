@@ -0,0 +1,318 @@
+//! Serialization of multi-HPO cells
+//!
+//! A cell of an ETL column of type "multiple HPO terms" stores all HPO terms of one row as a single string,
+//! so that it can be saved in the ETL JSON file and edited outside of the app. ga4ghphetools parses these
+//! strings when it creates the cohort from the ETL file (`get_cohort_data_from_etl_dto`), so the serializer
+//! writes the form that ga4ghphetools reads:
+//!
+//! ```text
+//! cell     := "" | term (";" term)*
+//! term     := hpo_id "-" status "-" onset
+//! status   := "observed" | "excluded" | "na"
+//! onset    := "na" | any age string (ISO8601 period, HPO onset label, ...)
+//! ```
+//!
+//! for instance `HP:0001250-observed-P2Y;HP:0001251-excluded-na`. Terms with modifiers and onsets that
+//! contain `;` cannot be written in this form and are rejected.
+//!
+//! The parser of this module, which reads the strings back into the app, also accepts an extended form
+//! that ga4ghphetools does not read: the characters `;`, `-`, `,` and `\` may be escaped with a backslash
+//! within a field (e.g., `Late\-onset`), and a fourth field may list modifiers, e.g.,
+//! `HP:0001263-observed-na-HP:0012828,HP:0031796`. Whitespace around fields is ignored, and unescaped
+//! hyphens in the onset field are accepted if the trailing fields do not look like HPO modifiers.
+
+use std::collections::HashMap;
+
+use crate::hpo::{ClinicalStatus, MappedTerm, MinedCell};
+
+const ITEM_SEPARATOR: char = ';';
+const FIELD_SEPARATOR: char = '-';
+const MODIFIER_SEPARATOR: char = ',';
+const ESCAPE: char = '\\';
+
+
+/// Remove the escape characters from a field
+fn unescape(field: &str) -> String {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c == ESCAPE {
+            if let Some(next) = chars.next() {
+                unescaped.push(next);
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    unescaped.trim().to_string()
+}
+
+/// Split at each occurence of `sep` that is not escaped. The escape characters are retained.
+fn split_unescaped(s: &str, sep: char) -> Vec<String> {
+    let mut parts: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == ESCAPE {
+            current.push(c);
+            if let Some(next) = chars.next() {
+                current.push(next);
+            }
+        } else if c == sep {
+            parts.push(std::mem::take(&mut current));
+        } else {
+            current.push(c);
+        }
+    }
+    parts.push(current);
+    parts
+}
+
+fn is_hpo_id(s: &str) -> bool {
+    s.strip_prefix("HP:")
+        .is_some_and(|num| num.len() == 7 && num.chars().all(|c| c.is_ascii_digit()))
+}
+
+
+/// Serialize one mapped term, e.g., `HP:0001250-observed-P2Y`
+///
+/// Returns an error for terms that ga4ghphetools cannot read back (modifiers, `;` in the onset).
+pub fn term_to_string(mt: &MappedTerm) -> Result<String, String> {
+    let onset = if mt.onset.trim().is_empty() { "na" } else { mt.onset.trim() };
+    if !mt.modifiers.is_empty() {
+        return Err(format!("{} ({}): modifiers cannot be stored in multi-HPO cells", mt.hpo_label, mt.hpo_id));
+    }
+    if onset.contains(ITEM_SEPARATOR) {
+        return Err(format!("{} ({}): the onset '{}' must not contain '{}'", mt.hpo_label, mt.hpo_id, onset, ITEM_SEPARATOR));
+    }
+    Ok(format!("{}{}{}{}{}", mt.hpo_id.trim(), FIELD_SEPARATOR, mt.status.to_string(), FIELD_SEPARATOR, onset))
+}
+
+/// Serialize all terms of a cell, separated by `;`
+pub fn terms_to_string(terms: &[MappedTerm]) -> Result<String, String> {
+    let items = terms
+        .iter()
+        .map(term_to_string)
+        .collect::<Result<Vec<String>, String>>()?;
+    Ok(items.join(&ITEM_SEPARATOR.to_string()))
+}
+
+/// Parse one serialized term.
+///
+/// `label_lookup` is used to retrieve the label of the HPO term (and to check that the term exists).
+pub fn parse_term<L>(item: &str, label_lookup: &L) -> Result<MappedTerm, String>
+where
+    L: Fn(&str) -> Option<String>,
+{
+    let fields = split_unescaped(item.trim(), FIELD_SEPARATOR);
+    if fields.len() < 3 {
+        return Err(format!("Malformed multi-HPO item '{}': expected HP:id-status-onset", item));
+    }
+    let hpo_id = unescape(&fields[0]);
+    if !is_hpo_id(&hpo_id) {
+        return Err(format!("Malformed HPO id '{}' in '{}'", hpo_id, item));
+    }
+    let status: ClinicalStatus = unescape(&fields[1]).parse()?;
+    let (onset, modifiers) = parse_onset_and_modifiers(&fields[2..]);
+    for modifier in &modifiers {
+        if !is_hpo_id(modifier) {
+            return Err(format!("Malformed modifier '{}' in '{}'", modifier, item));
+        }
+    }
+    let hpo_label = label_lookup(&hpo_id)
+        .ok_or_else(|| format!("Could not find HPO term for '{}'", hpo_id))?;
+    let mut mt = MappedTerm::new(&hpo_id, &hpo_label);
+    mt.status = status;
+    mt.onset = if onset.is_empty() { "na".to_string() } else { onset };
+    mt.modifiers = modifiers;
+    Ok(mt)
+}
+
+/// The fields after the status are the onset and optionally a list of modifiers.
+/// If there are more than two fields, the onset was written with unescaped hyphens by an earlier version,
+/// unless the last field consists of HPO ids.
+fn parse_onset_and_modifiers(fields: &[String]) -> (String, Vec<String>) {
+    let last_field = fields.last().map(|f| split_unescaped(f, MODIFIER_SEPARATOR)).unwrap_or_default();
+    let last_is_modifiers = fields.len() > 1
+        && last_field.iter().all(|m| is_hpo_id(&unescape(m)));
+    if last_is_modifiers {
+        let onset_fields = &fields[..fields.len() - 1];
+        let onset = onset_fields.iter().map(|f| unescape(f)).collect::<Vec<String>>().join("-");
+        let modifiers = last_field.iter().map(|m| unescape(m)).collect();
+        (onset, modifiers)
+    } else {
+        let onset = fields.iter().map(|f| unescape(f)).collect::<Vec<String>>().join("-");
+        (onset, vec![])
+    }
+}
+
+/// Parse all terms of a cell. An empty cell yields an empty list.
+pub fn parse_cell<L>(cell: &str, label_lookup: &L) -> Result<Vec<MappedTerm>, String>
+where
+    L: Fn(&str) -> Option<String>,
+{
+    split_unescaped(cell, ITEM_SEPARATOR)
+        .iter()
+        .filter(|item| !item.trim().is_empty())
+        .map(|item| parse_term(item, label_lookup))
+        .collect()
+}
+
+/// Parse the multi-HPO strings of a column (one per row) into [`MinedCell`]s.
+///
+/// Rows with identical strings are grouped into one [`MinedCell`]; the cells are ordered by their first row.
+pub fn parse_multi_hpo_strings<L>(cell_values: &[String], label_lookup: &L) -> Result<Vec<MinedCell>, String>
+where
+    L: Fn(&str) -> Option<String>,
+{
+    let mut mined_cells: Vec<MinedCell> = Vec::new();
+    let mut index_by_text: HashMap<&str, usize> = HashMap::new();
+    for (row_index, value) in cell_values.iter().enumerate() {
+        if let Some(&i) = index_by_text.get(value.as_str()) {
+            mined_cells[i].row_index_list.push(row_index);
+            continue;
+        }
+        let mapped_term_list = parse_cell(value, label_lookup)
+            .map_err(|e| format!("Row {}: {}", row_index, e))?;
+        index_by_text.insert(value.as_str(), mined_cells.len());
+        mined_cells.push(MinedCell {
+            cell_text: value.clone(),
+            row_index_list: vec![row_index],
+            mapped_term_list,
+        });
+    }
+    Ok(mined_cells)
+}
+
+/// Get one multi-HPO string per row from the [`MinedCell`]s.
+///
+/// # Arguments
+/// * `mined_cells` - mapped terms, each with the list of rows to which they apply
+/// * `n_rows` - the number of rows in the column. If `None`, the highest row index of the cells is used.
+///
+/// # Notes
+/// Rows that are not covered by any cell get an empty string. If a row is covered by more than one cell,
+/// the terms are merged (the first occurrence of a term wins). Returns an error if a term cannot be
+/// serialized (see [`term_to_string`]).
+pub fn get_multi_hpo_strings(mined_cells: Vec<MinedCell>, n_rows: Option<usize>) -> Result<Vec<String>, String> {
+    let max_index = mined_cells
+        .iter()
+        .flat_map(|c| c.row_index_list.iter().copied())
+        .max();
+    let n_rows = match (n_rows, max_index) {
+        (Some(n), Some(max)) => n.max(max + 1),
+        (Some(n), None) => n,
+        (None, Some(max)) => max + 1,
+        (None, None) => 0,
+    };
+    let mut terms_by_row: Vec<Vec<MappedTerm>> = vec![Vec::new(); n_rows];
+    for cell in mined_cells {
+        for i in cell.row_index_list {
+            let row_terms = &mut terms_by_row[i];
+            for mt in &cell.mapped_term_list {
+                if !row_terms.iter().any(|existing| existing.hpo_id == mt.hpo_id) {
+                    row_terms.push(mt.clone());
+                }
+            }
+        }
+    }
+    terms_by_row
+        .iter()
+        .enumerate()
+        .map(|(i, terms)| terms_to_string(terms).map_err(|e| format!("Row {}: {}", i, e)))
+        .collect()
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(hpo_id: &str) -> Option<String> {
+        match hpo_id {
+            "HP:0001250" => Some("Seizure".to_string()),
+            "HP:0001251" => Some("Ataxia".to_string()),
+            _ => None,
+        }
+    }
+
+    fn term(hpo_id: &str, status: ClinicalStatus, onset: &str, modifiers: &[&str]) -> MappedTerm {
+        let mut mt = MappedTerm::new(hpo_id, &lookup(hpo_id).unwrap());
+        mt.status = status;
+        mt.onset = onset.to_string();
+        mt.modifiers = modifiers.iter().map(|m| m.to_string()).collect();
+        mt
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let terms = vec![
+            term("HP:0001250", ClinicalStatus::Observed, "P2Y", &[]),
+            term("HP:0001251", ClinicalStatus::Excluded, "na", &[]),
+        ];
+        let cell = terms_to_string(&terms).unwrap();
+        assert_eq!("HP:0001250-observed-P2Y;HP:0001251-excluded-na", cell);
+        let parsed = parse_cell(&cell, &lookup).unwrap();
+        assert_eq!(cell, terms_to_string(&parsed).unwrap());
+        assert_eq!("Seizure", parsed[0].hpo_label);
+    }
+
+    #[test]
+    fn test_terms_that_ga4ghphetools_cannot_read() {
+        let with_modifier = term("HP:0001250", ClinicalStatus::Observed, "na", &["HP:0012828"]);
+        assert!(term_to_string(&with_modifier).unwrap_err().contains("modifiers"));
+        let with_separator = term("HP:0001250", ClinicalStatus::Observed, "P2Y; adult", &[]);
+        assert!(term_to_string(&with_separator).is_err());
+    }
+
+    #[test]
+    fn test_parse_extended_form() {
+        let parsed = parse_cell(r"HP:0001250-observed-Late\-onset\; adult-HP:0012828,HP:0031796;HP:0001251-excluded-na", &lookup).unwrap();
+        assert_eq!(2, parsed.len());
+        assert_eq!("Late-onset; adult", parsed[0].onset);
+        assert_eq!(vec!["HP:0012828", "HP:0031796"], parsed[0].modifiers);
+        assert_eq!(ClinicalStatus::Excluded, parsed[1].status);
+    }
+
+    #[test]
+    fn test_legacy_unescaped_onset() {
+        let parsed = parse_cell("HP:0001250-observed-Late-onset", &lookup).unwrap();
+        assert_eq!(1, parsed.len());
+        assert_eq!("Late-onset", parsed[0].onset);
+        assert!(parsed[0].modifiers.is_empty());
+    }
+
+    #[test]
+    fn test_malformed_items() {
+        assert!(parse_cell("HP:0001250-observed", &lookup).is_err());
+        assert!(parse_cell("HP:0001250-present-na", &lookup).is_err());
+        assert!(parse_cell("HP:9999999-observed-na", &lookup).is_err());
+        assert!(parse_cell("", &lookup).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_partial_rows() {
+        let cells = vec![MinedCell {
+            cell_text: "seizures".to_string(),
+            row_index_list: vec![1, 3],
+            mapped_term_list: vec![term("HP:0001250", ClinicalStatus::Observed, "na", &[])],
+        }];
+        let strings = get_multi_hpo_strings(cells, Some(5)).unwrap();
+        assert_eq!(vec!["", "HP:0001250-observed-na", "", "HP:0001250-observed-na", ""], strings);
+    }
+
+    #[test]
+    fn test_parse_groups_identical_rows() {
+        let values: Vec<String> = vec!["HP:0001250-observed-na", "", "HP:0001250-observed-na"]
+            .into_iter().map(String::from).collect();
+        let cells = parse_multi_hpo_strings(&values, &lookup).unwrap();
+        assert_eq!(2, cells.len());
+        assert_eq!(vec![0, 2], cells[0].row_index_list);
+        assert_eq!(vec![1], cells[1].row_index_list);
+    }
+}
+
+// endregion: --- Tests
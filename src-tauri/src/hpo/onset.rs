@@ -0,0 +1,114 @@
+//! HPO onset terms and the binning of ages of onset into onset categories
+//!
+//! Ages of onset are recorded either as ISO 8601 durations (e.g., `P3Y2M`) or as the label of an HPO onset
+//! term (e.g., *Congenital onset*). The statistics and the HPOA export both need the onset category of an age.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// HPO onset terms (subclasses of Onset, HP:0003674)
+pub const ONSET_TERMS: [(&str, &str); 13] = [
+    ("HP:0030674", "Antenatal onset"),
+    ("HP:0011460", "Embryonal onset"),
+    ("HP:0011461", "Fetal onset"),
+    ("HP:0003577", "Congenital onset"),
+    ("HP:0003623", "Neonatal onset"),
+    ("HP:0003593", "Infantile onset"),
+    ("HP:0011463", "Childhood onset"),
+    ("HP:0003621", "Juvenile onset"),
+    ("HP:0410280", "Pediatric onset"),
+    ("HP:0003581", "Adult onset"),
+    ("HP:0011462", "Young adult onset"),
+    ("HP:0003596", "Middle age onset"),
+    ("HP:0003584", "Late onset"),
+];
+
+/// ISO 8601 age, e.g., `P3Y`, `P1Y6M` or `P10D`
+static ISO8601_AGE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^P(\d+Y)?(\d+M)?(\d+W)?(\d+D)?$").unwrap());
+
+
+/// `true` for an ISO 8601 age (e.g., `P3Y2M`) or the label of an HPO onset term (e.g., `Congenital onset`)
+pub fn is_valid_onset(value: &str) -> bool {
+    (value.len() > 1 && ISO8601_AGE_RE.is_match(value)) || ONSET_TERMS.iter().any(|(_, label)| *label == value)
+}
+
+/// Identifier and label of the HPO onset term with the given label (case-insensitive)
+pub fn onset_term(label: &str) -> Option<(&'static str, &'static str)> {
+    ONSET_TERMS.iter().copied().find(|(_, l)| l.eq_ignore_ascii_case(label))
+}
+
+/// Assign an age of onset to an HPO onset category.
+///
+/// ISO 8601 durations (e.g., `P3Y2M`) are binned according to the HPO onset subclasses; HPO onset labels
+/// (e.g., *Congenital onset*) are kept as they are.
+pub fn onset_category(age_of_onset: &str) -> String {
+    let onset = age_of_onset.trim();
+    if onset.is_empty() || onset.eq_ignore_ascii_case("na") {
+        return "not available".to_string();
+    }
+    match iso8601_days(onset) {
+        Some(days) if days < 28.0 => "Neonatal onset".to_string(),
+        Some(days) if days < 365.0 => "Infantile onset".to_string(),
+        Some(days) if days < 5.0 * 365.0 => "Childhood onset".to_string(),
+        Some(days) if days < 16.0 * 365.0 => "Juvenile onset".to_string(),
+        Some(_) => "Adult onset".to_string(),
+        None => onset.to_string(),
+    }
+}
+
+/// Approximate length of an ISO 8601 duration such as `P1Y6M`, `P3W` or `P10D` in days
+fn iso8601_days(duration: &str) -> Option<f64> {
+    let rest = duration.strip_prefix('P')?;
+    if rest.is_empty() {
+        return None;
+    }
+    let mut days = 0.0;
+    let mut number = String::new();
+    for c in rest.chars() {
+        if c.is_ascii_digit() || c == '.' {
+            number.push(c);
+            continue;
+        }
+        let value: f64 = number.parse().ok()?;
+        number.clear();
+        days += match c {
+            'Y' => value * 365.25,
+            'M' => value * 30.44,
+            'W' => value * 7.0,
+            'D' => value,
+            _ => { return None; }
+        };
+    }
+    if number.is_empty() { Some(days) } else { None }
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_onset_category() {
+        assert_eq!("Neonatal onset", onset_category("P10D"));
+        assert_eq!("Infantile onset", onset_category("P6M"));
+        assert_eq!("Juvenile onset", onset_category("P15Y11M"));
+        assert_eq!("Adult onset", onset_category("P40Y"));
+        assert_eq!("Antenatal onset", onset_category("Antenatal onset"));
+        assert_eq!("not available", onset_category("na"));
+        assert_eq!(Some(("HP:0003577", "Congenital onset")), onset_term("congenital onset"));
+    }
+
+    #[test]
+    fn test_is_valid_onset() {
+        assert!(is_valid_onset("P1Y6M"));
+        assert!(is_valid_onset("Infantile onset"));
+        assert!(!is_valid_onset("P"));
+        assert!(!is_valid_onset("3 years"));
+    }
+}
+
+// endregion: --- Tests
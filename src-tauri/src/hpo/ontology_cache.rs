@@ -0,0 +1,189 @@
+//! Cache of the HPO for fast startup
+//!
+//! Parsing the pretty-printed hp.json takes several seconds, most of it spent on whitespace and on sections that
+//! we do not use (logical definitions and other OWL axioms). If there is no current cache for hp.json, we parse
+//! hp.json once, write a compact copy without these sections to `~/.phenoboard/cache`, and load the ontology from
+//! that copy. Later starts load the compact copy directly if the key (path, size and modification time of hp.json,
+//! and the cache format) still matches.
+//!
+//! The cache has the obographs format so that it is read by the same parser as hp.json itself; a stale or damaged
+//! cache is never used (we fall back to hp.json). This only shortens parsing. A binary cache of the parsed ontology
+//! and of the autocompletion index would avoid parsing altogether, but requires serialization support for
+//! `FullCsrOntology` (ontolius) and `AutoCompleter` (fenominal), which these crates do not offer yet.
+
+use std::{fs::{self, File}, io::{BufReader, BufWriter}, path::{Path, PathBuf}, time::UNIX_EPOCH};
+
+use ontolius::{io::OntologyLoaderBuilder, ontology::csr::FullCsrOntology};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// Increment if the content of the cache changes
+const CACHE_FORMAT_VERSION: u32 = 1;
+/// Sections of an obographs graph that are not needed to build the ontology
+const UNUSED_GRAPH_SECTIONS: [&str; 4] = [
+    "logicalDefinitionAxioms",
+    "domainRangeAxioms",
+    "propertyChainAxioms",
+    "equivalentNodesSets",
+];
+
+
+/// Identifies the hp.json file from which a cache was built
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheKey {
+    pub hp_json_path: String,
+    pub size: u64,
+    /// Modification time in seconds since the Unix epoch
+    pub modified: u64,
+    pub format_version: u32,
+    pub phenoboard_version: String,
+}
+
+impl CacheKey {
+    pub fn for_file(hp_json: &Path) -> Result<Self, String> {
+        let metadata = fs::metadata(hp_json)
+            .map_err(|e| format!("Could not read {}: {}", hp_json.display(), e))?;
+        let modified = metadata.modified()
+            .ok()
+            .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Ok(Self {
+            hp_json_path: hp_json.to_string_lossy().to_string(),
+            size: metadata.len(),
+            modified,
+            format_version: CACHE_FORMAT_VERSION,
+            phenoboard_version: env!("CARGO_PKG_VERSION").to_string(),
+        })
+    }
+}
+
+/// Paths of the cache and key files for an hp.json file (one cache per hp.json path)
+fn cache_paths(hp_json: &Path, cache_dir: &Path) -> (PathBuf, PathBuf) {
+    let digest = format!("{:x}", Sha256::digest(hp_json.to_string_lossy().as_bytes()));
+    let stem = format!("hpo-{}", &digest[..16]);
+    (cache_dir.join(format!("{}.json", stem)), cache_dir.join(format!("{}.key.json", stem)))
+}
+
+/// Is there a cache for the current version of the hp.json file?
+pub fn is_cache_valid(hp_json: &Path, cache_dir: &Path) -> bool {
+    let (cache_file, key_file) = cache_paths(hp_json, cache_dir);
+    let Ok(current) = CacheKey::for_file(hp_json) else {
+        return false;
+    };
+    let cached: Option<CacheKey> = fs::read_to_string(key_file)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok());
+    cache_file.is_file() && cached.as_ref() == Some(&current)
+}
+
+/// Load the ontology from the cache; returns None if the cache is missing, stale or cannot be read
+pub fn load_cached_ontology(hp_json: &Path, cache_dir: &Path) -> Option<FullCsrOntology> {
+    if !is_cache_valid(hp_json, cache_dir) {
+        return None;
+    }
+    let (cache_file, _) = cache_paths(hp_json, cache_dir);
+    let reader = BufReader::new(File::open(cache_file).ok()?);
+    let loader = OntologyLoaderBuilder::new().obographs_parser().build();
+    loader.load_from_read(reader).ok()
+}
+
+/// Remove the sections that are not needed to build the ontology
+fn prune_graph_document(document: &mut Value) {
+    for graph in document["graphs"].as_array_mut().into_iter().flatten() {
+        if let Some(graph) = graph.as_object_mut() {
+            for section in UNUSED_GRAPH_SECTIONS {
+                graph.remove(section);
+            }
+        }
+    }
+}
+
+/// Write the compact cache for hp.json. The key is written last, so an interrupted write leaves no valid cache.
+pub fn write_cache(hp_json: &Path, cache_dir: &Path) -> Result<(), String> {
+    let key = CacheKey::for_file(hp_json)?;
+    let (cache_file, key_file) = cache_paths(hp_json, cache_dir);
+    fs::create_dir_all(cache_dir)
+        .map_err(|e| format!("Could not create {}: {}", cache_dir.display(), e))?;
+    let _ = fs::remove_file(&key_file);
+    let file = File::open(hp_json).map_err(|e| format!("Could not open {}: {}", hp_json.display(), e))?;
+    let mut document: Value = serde_json::from_reader(BufReader::new(file))
+        .map_err(|e| format!("Could not parse {}: {}", hp_json.display(), e))?;
+    prune_graph_document(&mut document);
+    let out = File::create(&cache_file)
+        .map_err(|e| format!("Could not create {}: {}", cache_file.display(), e))?;
+    serde_json::to_writer(BufWriter::new(out), &document)
+        .map_err(|e| format!("Could not write {}: {}", cache_file.display(), e))?;
+    let key_json = serde_json::to_string_pretty(&key).map_err(|e| e.to_string())?;
+    fs::write(&key_file, key_json).map_err(|e| format!("Could not write {}: {}", key_file.display(), e))
+}
+
+/// Load the ontology of hp.json, using the cache if it is current.
+///
+/// A missing or stale cache is rebuilt first and the ontology is loaded from the new cache, so that hp.json is
+/// parsed only once. If the cache cannot be written or read, the ontology is loaded from hp.json and the problem
+/// is added to `warnings` (which are shown in the status of the application).
+pub fn load_ontology(hp_json: &Path, cache_dir: &Path, warnings: &mut Vec<String>) -> Result<FullCsrOntology, String> {
+    if !is_cache_valid(hp_json, cache_dir) {
+        if let Err(e) = write_cache(hp_json, cache_dir) {
+            warnings.push(format!("Could not write HPO cache: {}", e));
+        }
+    }
+    if let Some(ontology) = load_cached_ontology(hp_json, cache_dir) {
+        return Ok(ontology);
+    }
+    let file = File::open(hp_json).map_err(|e| format!("Could not open {}: {}", hp_json.display(), e))?;
+    let loader = OntologyLoaderBuilder::new().obographs_parser().build();
+    loader.load_from_read(BufReader::new(file))
+        .map_err(|e| format!("Could not load {}: {}", hp_json.display(), e))
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_cache() {
+        let dir = TempDir::new().unwrap();
+        let hp_json = dir.path().join("hp.json");
+        let cache_dir = dir.path().join("cache");
+        fs::write(&hp_json, r#"{
+            "graphs": [{
+                "id": "http://purl.obolibrary.org/obo/hp.json",
+                "nodes": [{"id": "http://purl.obolibrary.org/obo/HP_0000118", "lbl": "Phenotypic abnormality"}],
+                "edges": [],
+                "logicalDefinitionAxioms": [{"definedClassId": "http://purl.obolibrary.org/obo/HP_0000118"}]
+            }]
+        }"#).unwrap();
+        assert!(!is_cache_valid(&hp_json, &cache_dir));
+        write_cache(&hp_json, &cache_dir).unwrap();
+        assert!(is_cache_valid(&hp_json, &cache_dir));
+        let (cache_file, _) = cache_paths(&hp_json, &cache_dir);
+        let cached: Value = serde_json::from_str(&fs::read_to_string(cache_file).unwrap()).unwrap();
+        assert!(cached["graphs"][0].get("logicalDefinitionAxioms").is_none());
+        assert_eq!("Phenotypic abnormality", cached["graphs"][0]["nodes"][0]["lbl"]);
+        // a changed hp.json invalidates the cache
+        fs::write(&hp_json, "{}").unwrap();
+        assert!(!is_cache_valid(&hp_json, &cache_dir));
+    }
+
+    #[test]
+    fn test_cache_error_is_reported() {
+        let dir = TempDir::new().unwrap();
+        let mut warnings = Vec::new();
+        // the file is missing, so the cache cannot be written and the ontology cannot be loaded
+        let result = load_ontology(&dir.path().join("hp.json"), &dir.path().join("cache"), &mut warnings);
+        assert!(result.is_err());
+        assert_eq!(1, warnings.len());
+        assert!(warnings[0].starts_with("Could not write HPO cache"));
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,253 @@
+//! Positional highlighting of fenominal text-mining results
+//!
+//! fenominal returns a list of sentences, each of which is split into segments
+//! that are either plain text or a hit (an HPO term). The frontend needs to know
+//! exactly which words of the original text produced each term, so here we map
+//! the hits back onto the original text and render an HTML-safe version of it.
+//!
+//! All offsets that we return (`start`, `end`) are UTF-16 code unit offsets into the
+//! original text, which is what JavaScript uses for `String.prototype.substring`.
+
+use std::ops::Range;
+
+use fenominal::{FenominalSegment, FenominalSentence};
+use serde::{Deserialize, Serialize};
+
+
+/// One fenominal hit, located in the original text.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HitSpan {
+    /// HPO term identifier, e.g., HP:0001250
+    pub term_id: String,
+    /// HPO term label, e.g., Seizure
+    pub label: String,
+    /// The words of the original text that produced the hit
+    pub matched_text: String,
+    /// UTF-16 offset of the first character of the hit in the original text
+    pub start: usize,
+    /// UTF-16 offset one past the last character of the hit in the original text
+    pub end: usize,
+    /// true if fenominal determined that the term was negated (excluded)
+    pub negated: bool,
+    /// Zero-based index of the sentence in which the hit was found
+    pub sentence_index: usize,
+}
+
+/// A fenominal hit whose words could not be found in the original text (it is not highlighted)
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UnlocatedHit {
+    pub term_id: String,
+    pub label: String,
+    /// The text of the hit as reported by fenominal
+    pub matched_text: String,
+}
+
+/// The original text, together with all hits and an HTML rendering in which the hits are highlighted.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HighlightedText {
+    pub original_text: String,
+    pub hits: Vec<HitSpan>,
+    pub html: String,
+    /// Hits that could not be mapped back onto the original text, so that the curator can check them
+    pub unlocated_hits: Vec<UnlocatedHit>,
+}
+
+/// Transport object for fenominal hits; corresponds to `TextAnnotationDto` in the frontend.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextAnnotationDto {
+    pub is_fenominal_hit: bool,
+    pub term_id: String,
+    pub label: String,
+    pub start: usize,
+    pub end: usize,
+    pub is_observed: bool,
+    pub original_text: String,
+    pub onset_string: String,
+}
+
+impl From<&HitSpan> for TextAnnotationDto {
+    fn from(hit: &HitSpan) -> Self {
+        Self {
+            is_fenominal_hit: true,
+            term_id: hit.term_id.clone(),
+            label: hit.label.clone(),
+            start: hit.start,
+            end: hit.end,
+            is_observed: !hit.negated,
+            original_text: hit.matched_text.clone(),
+            onset_string: "na".to_string(),
+        }
+    }
+}
+
+
+/// Map the fenominal sentences back onto `text` and create the highlighted representation.
+pub fn highlight_text(text: &str, sentences: &[FenominalSentence]) -> HighlightedText {
+    let (hits, unlocated_hits) = extract_hit_spans(text, sentences);
+    let html = render_html(text, &hits);
+    HighlightedText {
+        original_text: text.to_string(),
+        hits,
+        html,
+        unlocated_hits,
+    }
+}
+
+/// Get one [`HitSpan`] for each hit segment, ordered by position in the original text, and the hits that
+/// could not be located in the original text.
+pub fn extract_hit_spans(text: &str, sentences: &[FenominalSentence]) -> (Vec<HitSpan>, Vec<UnlocatedHit>) {
+    let mut hits: Vec<HitSpan> = Vec::new();
+    let mut unlocated: Vec<UnlocatedHit> = Vec::new();
+    for (sentence_index, sentence) in sentences.iter().enumerate() {
+        for segment in &sentence.segments {
+            if let FenominalSegment::Hit { text: hit_text, hit } = segment {
+                let byte_range = match locate_hit(text, sentence, hit_text, &hit.span) {
+                    Some(range) => range,
+                    None => {
+                        unlocated.push(UnlocatedHit {
+                            term_id: hit.term_id.to_string(),
+                            label: hit.label.clone(),
+                            matched_text: hit_text.to_string(),
+                        });
+                        continue;
+                    }
+                };
+                hits.push(HitSpan {
+                    term_id: hit.term_id.to_string(),
+                    label: hit.label.clone(),
+                    matched_text: text[byte_range.clone()].to_string(),
+                    start: utf16_offset(text, byte_range.start),
+                    end: utf16_offset(text, byte_range.end),
+                    negated: hit.excluded,
+                    sentence_index,
+                });
+            }
+        }
+    }
+    hits.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+    (hits, unlocated)
+}
+
+/// Determine the byte range of a hit in the original text.
+///
+/// fenominal reports the span of a hit relative to its sentence, but we do not want to rely on that
+/// blindly (e.g., if the text was normalized). We therefore accept the first candidate whose slice
+/// equals the text of the hit segment, and fall back to a search within the sentence.
+fn locate_hit(text: &str, sentence: &FenominalSentence, hit_text: &str, span: &Range<usize>) -> Option<Range<usize>> {
+    let candidates = [
+        (sentence.start + span.start)..(sentence.start + span.end),
+        span.clone(),
+    ];
+    for candidate in candidates {
+        if text.get(candidate.clone()) == Some(hit_text) {
+            return Some(candidate);
+        }
+    }
+    let sentence_end = (sentence.start + sentence.original_text.len()).min(text.len());
+    let haystack = text.get(sentence.start..sentence_end)?;
+    haystack
+        .find(hit_text)
+        .map(|pos| (sentence.start + pos)..(sentence.start + pos + hit_text.len()))
+}
+
+/// Convert a byte offset into a UTF-16 code unit offset
+fn utf16_offset(text: &str, byte_offset: usize) -> usize {
+    text[..byte_offset].encode_utf16().count()
+}
+
+/// Convert a UTF-16 code unit offset back into a byte offset
+fn byte_offset(text: &str, utf16_offset: usize) -> usize {
+    let mut n_units = 0;
+    for (idx, c) in text.char_indices() {
+        if n_units >= utf16_offset {
+            return idx;
+        }
+        n_units += c.len_utf16();
+    }
+    text.len()
+}
+
+/// Render the text as HTML in which each hit is wrapped in a `span` element.
+///
+/// The text is escaped, so the result can be inserted directly into the DOM. Observed hits get the
+/// class `hpo-hit`, negated hits additionally get `hpo-hit-excluded`. If hits overlap, only the first
+/// (and longest) hit at a given position is highlighted.
+pub fn render_html(text: &str, hits: &[HitSpan]) -> String {
+    let mut html = String::with_capacity(text.len() * 2);
+    let mut cursor: usize = 0; // byte offset
+    for hit in hits {
+        let start = byte_offset(text, hit.start);
+        let end = byte_offset(text, hit.end);
+        if start < cursor || end <= start {
+            continue;
+        }
+        html.push_str(&html_escape::encode_text(&text[cursor..start]));
+        let class = if hit.negated { "hpo-hit hpo-hit-excluded" } else { "hpo-hit" };
+        html.push_str(&format!(
+            "<span class=\"{}\" data-term-id=\"{}\" data-sentence=\"{}\" title=\"{}\">{}</span>",
+            class,
+            html_escape::encode_double_quoted_attribute(&hit.term_id),
+            hit.sentence_index,
+            html_escape::encode_double_quoted_attribute(&format!("{} ({})", hit.label, hit.term_id)),
+            html_escape::encode_text(&text[start..end]),
+        ));
+        cursor = end;
+    }
+    html.push_str(&html_escape::encode_text(&text[cursor..]));
+    html
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(term_id: &str, start: usize, end: usize, negated: bool) -> HitSpan {
+        HitSpan {
+            term_id: term_id.to_string(),
+            label: "label".to_string(),
+            matched_text: String::default(),
+            start,
+            end,
+            negated,
+            sentence_index: 0,
+        }
+    }
+
+    #[test]
+    fn test_render_escapes_text() {
+        let text = "Seizures <3 & no ataxia";
+        let hits = vec![hit("HP:0001250", 0, 8, false), hit("HP:0001251", 17, 23, true)];
+        let html = render_html(text, &hits);
+        assert_eq!(
+            html,
+            "<span class=\"hpo-hit\" data-term-id=\"HP:0001250\" data-sentence=\"0\" title=\"label (HP:0001250)\">Seizures</span> \
+&lt;3 &amp; no <span class=\"hpo-hit hpo-hit-excluded\" data-term-id=\"HP:0001251\" data-sentence=\"0\" title=\"label (HP:0001251)\">ataxia</span>"
+        );
+    }
+
+    #[test]
+    fn test_overlapping_hits_are_rendered_once() {
+        let text = "short stature";
+        let hits = vec![hit("HP:0004322", 0, 13, false), hit("HP:0000000", 6, 13, false)];
+        let html = render_html(text, &hits);
+        assert_eq!(1, html.matches("<span").count());
+    }
+
+    #[test]
+    fn test_utf16_offsets() {
+        let text = "Café seizures";
+        assert_eq!(5, utf16_offset(text, "Café ".len()));
+        assert_eq!("Café ".len(), byte_offset(text, 5));
+        assert_eq!(text.len(), byte_offset(text, 100));
+    }
+}
+
+// endregion: --- Tests
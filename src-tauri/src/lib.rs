@@ -1,3 +1,4 @@
+mod cohort_export;
 mod directory_manager;
 mod dto;
 mod phenoboard;
@@ -10,16 +11,17 @@ use ga4ghphetools::dto::intergenic_variant::IntergenicHgvsVariant;
 use ga4ghphetools::tauri::{pick_file_and_process, load_ontology, OntologyLoadEvent};
 use ontolius::ontology::MetadataAware;
 use ontolius::ontology::OntologyTerms;
+use ontolius::{term::MinimalTerm, TermId};
 use phenoboard::PhenoboardSingleton;
 use serde::Serialize;
 use tauri::{AppHandle, Emitter, Runtime, WindowEvent};
 use tauri_plugin_dialog::{DialogExt};
-use std::{collections::HashMap, fs, sync::{Arc, Mutex}};
+use std::{collections::{BTreeSet, HashMap}, fs, sync::{Arc, Mutex}};
 use tauri_plugin_fs::{init};
 use fenominal::OntologyMatch;
 
 
-use crate::{dto::{pmid_dto::PmidDto, status_dto::{StatusDto,PpktSaveCheckResult}}, hpo::{MinedCell, MiningConcept}, util::HgncBundle};
+use crate::{cohort_export::{analysis_bundle::AnalysisBundleSummary, phenopacket_collections::PhenopacketCollectionSummary}, directory_manager::{batch_export::BatchExportReport, cohort_metadata::CohortOverviewRow, cohort_qc::RepositoryPhenopacketQc, git_status::{CohortCommit, RepositoryGitStatus}, layout::{RegisteredRepositories, RepositoryConfig}, release::ReleaseSummary, search::{SearchQuery, SearchResult}, statistics::RepositoryStatistics}, dto::{pmid_dto::PmidDto, status_dto::{StatusDto,PpktSaveCheckResult}}, settings::{curator_profile::{CuratorProfile, CuratorProfiles}, project_settings::TranscriptPolicy, recent_files::{RecentFile, RecentFileKind}}, hpo::{MappedTerm, MinedCell, MiningConcept, batch_mining::BatchMiningReport, hpo_release::{HpoRelease, HpoReleaseSource, InstalledHpoRelease}, hpoa_export::{HpoaExportSummary, HpoaPreview}, hpo_etl::CellMappingResult, mined_row::MinedRowResult, text_annotation::{HighlightedText, TextAnnotationDto}}, util::HgncBundle};
 
 struct AppState {
     phenoboard: Mutex<PhenoboardSingleton>,
@@ -30,6 +32,7 @@ pub fn run() {
     let app_state = Arc::new(AppState {
         phenoboard: Mutex::new(PhenoboardSingleton::new()),
     });
+    let setup_state = app_state.clone();
 
     tauri::Builder::default()
         .manage(app_state)
@@ -39,16 +42,28 @@ pub fn run() {
         .plugin(init())     
         .invoke_handler(tauri::generate_handler![
             add_hpo_term_to_cohort,
+            add_mined_row_to_cohort,
             add_new_row_to_cohort,
+            activate_hpo_release,
+            batch_export_phenopackets,
             check_existing_phenopackets,
+            check_repository_phenopackets,
+            clear_recent_files,
+            commit_cohort,
             compare_two_phenopackets,
             create_canonical_dictionary,
             create_cell_mappings,
             create_new_cohort_data,
             create_new_melded_cohort,
+            create_release_archive,
+            download_hpo_release,
             emit_backend_status,
+            export_analysis_bundle,
+            export_cohort_sheet,
             export_hpoa,
+            export_phenopacket_collections,
             export_ppkt,
+            export_repository_statistics,
             fetch_hgnc_data,
             fetch_pmid_title,
             fetch_repo_qc,
@@ -56,40 +71,75 @@ pub fn run() {
             get_biocurator_orcid,
             get_cohort_age_strings,
             get_cohort_data_from_etl_dto,
+            get_curator_profiles,
+            get_git_status,
             get_hp_json_path,
             get_hpo_parent_and_children_terms,
             get_hpo_terms_by_toplevel,
             get_hpo_modifiers,
+            get_hpo_release_source,
+            get_installed_hpo_releases,
             get_modifiers,
             get_multi_hpo_strings,
             get_status_dto,
             get_ppkt_store_json,
+            get_ppkt_store_overview,
+            get_recent_files,
+            get_repository_statistics,
             get_pt_template_path,
+            get_repositories,
             get_variant_analysis,
+            highlight_text_with_hits,
+            import_cohort_sheet,
+            list_hpo_releases,
             load_external_excel,
             load_external_template_json,
             load_ptools_json,
             load_hpo,
+            mine_case_report_directory,
+            map_text_to_annotations,
             merge_cohort_data_from_etl_dto,
             mine_clinical_text,
             mine_multi_hpo_column,
+            parse_multi_hpo_strings,
             perform_hpo_autocomplete,
+            preview_hpoa,
             process_allele_column,
+            register_repository,
             reset_pt_template_path,
+            remove_curator_profile,
             remove_na_columns,
+            remove_orphaned_phenopackets,
+            remove_repository,
+            reopen_recent_excel,
+            reopen_recent_external_template,
+            reopen_recent_template,
             sanitize_cohort_data,
             save_biocurator_orcid,
             save_cohort_data,
+            save_curator_profile,
             save_external_template_json,
             save_html_report,
+            search_repository,
+            select_curator_profile,
+            select_repository,
+            set_hpo_release_source,
             sort_cohort_by_rows,
            // submit_autocompleted_hpo_term,
             validate_hgvs_variant,
             validate_intergenic_variant,
             validate_structural_variant,
             validate_template,    
+            watch_ppkt_store,
         ])
-        .setup(|_app| {
+        .setup(move |app| {
+            // Report changes of the repository index to the frontend (see watch_ppkt_store)
+            let app_handle = app.handle().clone();
+            setup_state.phenoboard.lock()
+                .map_err(|_| "Failed to acquire lock on HPO State".to_string())?
+                .set_repo_change_listener(move |change| {
+                    let _ = app_handle.emit("ppkt-store-changed", &change);
+                })?;
             Ok(())
         })
         .on_window_event(|window, event| {
@@ -135,6 +185,117 @@ async fn load_hpo(
 }
 
 
+/// Load the hp.json file of an installed HPO release and make it the active ontology
+fn activate_hpo_json<R: Runtime>(app: &AppHandle<R>, state: &AppState, hp_json: &std::path::Path) -> Result<StatusDto, String> {
+    let hpo_json_path = hp_json.to_string_lossy().to_string();
+    let _ = app.emit("hpo-load-event", OntologyLoadEvent::loading());
+    match load_ontology(&hpo_json_path) {
+        Ok(ontology) => {
+            let mut singleton = state.phenoboard.lock()
+                .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+            let n_terms = ontology.len();
+            let hpo_version = ontology.version().to_string();
+            singleton.set_hpo(ontology, &hpo_json_path);
+            let _ = app.emit("hpo-load-event", OntologyLoadEvent::success(&hpo_version, n_terms));
+            Ok(singleton.get_status())
+        },
+        Err(e) => {
+            let _ = app.emit("hpo-load-event", OntologyLoadEvent::error(e.to_string()));
+            Err(e.to_string())
+        }
+    }
+}
+
+#[tauri::command]
+fn get_hpo_release_source(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<HpoReleaseSource, String> {
+    let singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    Ok(singleton.get_hpo_release_source())
+}
+
+/// Choose where HPO releases are downloaded from (GitHub releases, PURL, or a local mirror directory)
+#[tauri::command]
+fn set_hpo_release_source(
+    state: tauri::State<'_, Arc<AppState>>,
+    source: HpoReleaseSource,
+) -> Result<(), String> {
+    let mut singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    singleton.set_hpo_release_source(source)
+}
+
+/// List the HPO releases that are available from the configured source
+#[tauri::command]
+async fn list_hpo_releases(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<HpoRelease>, String> {
+    let source = {
+        let singleton = state.phenoboard.lock()
+            .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+        singleton.get_hpo_release_source()
+    };
+    tokio::task::spawn_blocking(move || {
+        let release_dir = settings::get_hpo_release_dir()?;
+        crate::hpo::hpo_release::list_releases(&source, &release_dir)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// List the HPO releases in ~/.phenoboard/hpo
+#[tauri::command]
+fn get_installed_hpo_releases() -> Result<Vec<InstalledHpoRelease>, String> {
+    let release_dir = settings::get_hpo_release_dir()?;
+    Ok(crate::hpo::hpo_release::installed_releases(&release_dir))
+}
+
+/// Download and verify an HPO release and make it the active ontology.
+/// Emits "progress-update" with the number of kilobytes downloaded.
+#[tauri::command]
+async fn download_hpo_release<R>(
+    state: tauri::State<'_, Arc<AppState>>,
+    app: AppHandle<R>,
+    release: HpoRelease,
+) -> Result<StatusDto, String> where R: Runtime {
+    let state_handle = state.inner().clone();
+    tokio::task::spawn_blocking(move || {
+        let release_dir = settings::get_hpo_release_dir()?;
+        let pb = |current: u64, total: u64| {
+            let _ = app.emit("progress-update", ProgressPayload {
+                current: (current / 1024) as u32,
+                total: (total / 1024) as u32,
+            });
+        };
+        let hp_json = crate::hpo::hpo_release::download_release(&release, &release_dir, pb)?;
+        activate_hpo_json(&app, &state_handle, &hp_json)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Make an installed HPO release the active ontology
+#[tauri::command]
+async fn activate_hpo_release<R>(
+    state: tauri::State<'_, Arc<AppState>>,
+    app: AppHandle<R>,
+    version: String,
+) -> Result<StatusDto, String> where R: Runtime {
+    let state_handle = state.inner().clone();
+    tokio::task::spawn_blocking(move || {
+        let release_dir = settings::get_hpo_release_dir()?;
+        let release = crate::hpo::hpo_release::installed_releases(&release_dir)
+            .into_iter()
+            .find(|r| r.version == version)
+            .ok_or_else(|| format!("HPO release {} is not installed", version))?;
+        activate_hpo_json(&app, &state_handle, std::path::Path::new(&release.path))
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+
 /// Allow the user to choose an existing PheTools JSON file from the file system and load it
 #[tauri::command]
 async fn load_ptools_json(
@@ -190,17 +351,320 @@ async fn mine_clinical_text(
 }
 
 
+/// Mine the text and return it together with the character spans of each hit and an HTML rendering
+/// in which the hits are highlighted, so that curators can see which words produced each term.
+#[tauri::command]
+async fn highlight_text_with_hits(
+    state: tauri::State<'_, Arc<AppState>>,
+    input_text: String,
+) -> Result<HighlightedText, String> {
+    let singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    singleton.highlight_clinical_text(&input_text)
+}
+
+/// Mine the text and return one annotation per fenominal hit (with position in the original text)
+#[tauri::command]
+async fn map_text_to_annotations(
+    state: tauri::State<'_, Arc<AppState>>,
+    input_text: String,
+) -> Result<Vec<TextAnnotationDto>, String> {
+    let singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    let highlighted = singleton.highlight_clinical_text(&input_text)?;
+    Ok(highlighted.hits.iter().map(TextAnnotationDto::from).collect())
+}
+
 
 /// Get a JSON object that represents the directory and file structure of the Phenopacket Store
 #[tauri::command]
 fn get_ppkt_store_json(
     state: tauri::State<'_, Arc<AppState>>,
 ) ->  Result<serde_json::Value, String> {
-    let singleton = state.phenoboard.lock()
+    let mut singleton = state.phenoboard.lock()
         .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
     singleton.get_ppkt_store_json()
 }
 
+/// Create the release of Phenopacket Store (zip of all valid phenopackets, TSV manifest with SHA-256
+/// checksums, summary JSON) in a directory chosen by the user. Emits "progress-update" for each phenopacket.
+#[tauri::command]
+async fn create_release_archive<R>(
+    state: tauri::State<'_, Arc<AppState>>,
+    app: AppHandle<R>,
+    version: String,
+) -> Result<ReleaseSummary, String> where R: Runtime {
+    let phenopackets = {
+        let mut singleton = state.phenoboard.lock()
+            .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+        singleton.get_phenopacket_paths()?
+    };
+    tokio::task::spawn_blocking(move || {
+        let folder = app.dialog().file()
+            .set_title("Select Release Output Directory")
+            .blocking_pick_folder()
+            .ok_or_else(|| "User cancelled directory selection".to_string())?;
+        let out_dir = folder.as_path()
+            .ok_or_else(|| "Failed to extract path from FileDialogPath".to_string())?
+            .to_path_buf();
+        let pb = |current: u32, total: u32| {
+            let _ = app.emit("progress-update", ProgressPayload { current, total });
+        };
+        crate::directory_manager::release::create_release(&phenopackets, &version, &out_dir, pb)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Get the phenopacket repositories registered in the settings and the name of the selected one
+#[tauri::command]
+fn get_repositories(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<RegisteredRepositories, String> {
+    let singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    Ok(singleton.get_repositories())
+}
+
+/// Register a repository (base directory and layout of the cohort directories) and select it.
+/// A repository with the same name is replaced.
+#[tauri::command]
+fn register_repository(
+    state: tauri::State<'_, Arc<AppState>>,
+    config: RepositoryConfig,
+) -> Result<RegisteredRepositories, String> {
+    let mut singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    singleton.register_repository(config)?;
+    Ok(singleton.get_repositories())
+}
+
+/// Select a registered repository; with `None`, the repository is derived from the template path
+#[tauri::command]
+fn select_repository(
+    state: tauri::State<'_, Arc<AppState>>,
+    name: Option<String>,
+) -> Result<RegisteredRepositories, String> {
+    let mut singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    singleton.select_repository(name)?;
+    Ok(singleton.get_repositories())
+}
+
+#[tauri::command]
+fn remove_repository(
+    state: tauri::State<'_, Arc<AppState>>,
+    name: String,
+) -> Result<RegisteredRepositories, String> {
+    let mut singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    singleton.remove_repository(&name)?;
+    Ok(singleton.get_repositories())
+}
+
+/// Get one summary row per cohort (genes, diseases, number of rows and phenopackets, HPO version, curators)
+/// for the sortable overview table of the repository page
+#[tauri::command]
+fn get_ppkt_store_overview(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<CohortOverviewRow>, String> {
+    let mut singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    singleton.get_ppkt_store_overview()
+}
+
+/// Regenerate the phenopackets of all cohorts of Phenopacket Store (e.g., after an HPO update).
+/// Only phenopackets whose content changed are written; with `dry_run`, nothing is written.
+/// Emits "progress-update" after each cohort template.
+#[tauri::command]
+async fn batch_export_phenopackets<R>(
+    state: tauri::State<'_, Arc<AppState>>,
+    app: AppHandle<R>,
+    dry_run: bool,
+) -> Result<BatchExportReport, String> where R: Runtime {
+    let state_handle = state.inner().clone();
+    tokio::task::spawn_blocking(move || {
+        // Release the lock before exporting so that the UI stays responsive
+        let (jobs, hpo, orcid) = {
+            let mut singleton = state_handle.phenoboard.lock()
+                .map_err(|_| "Failed to acquire lock".to_string())?;
+            let hpo = singleton.get_hpo()
+                .ok_or_else(|| "HPO not initialized".to_string())?;
+            let orcid = singleton.get_orcid_id()?;
+            singleton.check_project_settings(None)?;
+            (singleton.get_export_jobs()?, hpo, orcid)
+        };
+        let pb = |current: u32, total: u32| {
+            let _ = app.emit("progress-update", ProgressPayload { current, total });
+        };
+        let report = crate::directory_manager::batch_export::run_batch_export(&jobs, hpo, &orcid, dry_run, pb);
+        if !dry_run {
+            let changed: BTreeSet<String> = report.cohorts
+                .iter()
+                .filter(|c| !c.written.is_empty())
+                .map(|c| c.cohort_name.clone())
+                .collect();
+            state_handle.phenoboard.lock()
+                .map_err(|_| "Failed to acquire lock".to_string())?
+                .refresh_repo_cohorts(&changed)?;
+        }
+        Ok(report)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Compare the template rows of each cohort with its phenopackets and report orphaned, missing, stale and
+/// differing phenopackets. Emits "progress-update" after each cohort.
+#[tauri::command]
+async fn check_repository_phenopackets<R>(
+    state: tauri::State<'_, Arc<AppState>>,
+    app: AppHandle<R>,
+) -> Result<RepositoryPhenopacketQc, String> where R: Runtime {
+    let state_handle = state.inner().clone();
+    tokio::task::spawn_blocking(move || {
+        let (jobs, hpo, orcid) = {
+            let mut singleton = state_handle.phenoboard.lock()
+                .map_err(|_| "Failed to acquire lock".to_string())?;
+            let hpo = singleton.get_hpo()
+                .ok_or_else(|| "HPO not initialized".to_string())?;
+            let orcid = singleton.get_orcid_id()?;
+            (singleton.get_export_jobs()?, hpo, orcid)
+        };
+        let pb = |current: u32, total: u32| {
+            let _ = app.emit("progress-update", ProgressPayload { current, total });
+        };
+        Ok(crate::directory_manager::cohort_qc::check_repository(&jobs, hpo, &orcid, pb))
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Delete the indicated orphaned phenopackets of a cohort (files that are no longer orphans are kept).
+/// Returns the names of the deleted files.
+#[tauri::command]
+async fn remove_orphaned_phenopackets(
+    state: tauri::State<'_, Arc<AppState>>,
+    cohort_name: String,
+    file_names: Vec<String>,
+) -> Result<Vec<String>, String> {
+    let state_handle = state.inner().clone();
+    tokio::task::spawn_blocking(move || {
+        let (jobs, hpo, orcid) = {
+            let mut singleton = state_handle.phenoboard.lock()
+                .map_err(|_| "Failed to acquire lock".to_string())?;
+            let hpo = singleton.get_hpo()
+                .ok_or_else(|| "HPO not initialized".to_string())?;
+            let orcid = singleton.get_orcid_id()?;
+            (singleton.get_export_jobs()?, hpo, orcid)
+        };
+        let removed = crate::directory_manager::cohort_qc::remove_orphaned_phenopackets(&cohort_name, &jobs, &file_names, hpo, &orcid)?;
+        if !removed.is_empty() {
+            state_handle.phenoboard.lock()
+                .map_err(|_| "Failed to acquire lock".to_string())?
+                .refresh_repo_cohorts(&BTreeSet::from([cohort_name]))?;
+        }
+        Ok(removed)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Get the uncommitted (modified, untracked, deleted) files of each cohort of Phenopacket Store
+#[tauri::command]
+fn get_git_status(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<RepositoryGitStatus, String> {
+    let singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    singleton.get_git_status()
+}
+
+/// Commit the changed files of one cohort to the local git repository of Phenopacket Store.
+/// If no message is provided, one is generated (cohort, PMIDs added, number of rows).
+#[tauri::command]
+fn commit_cohort(
+    state: tauri::State<'_, Arc<AppState>>,
+    cohort_name: String,
+    message: Option<String>,
+) -> Result<CohortCommit, String> {
+    let singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    singleton.commit_cohort(&cohort_name, message)
+}
+
+/// Get summary statistics of Phenopacket Store (counts, distributions and growth over time)
+#[tauri::command]
+fn get_repository_statistics(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<RepositoryStatistics, String> {
+    let mut singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    singleton.get_repository_statistics()
+}
+
+/// Save the repository statistics as JSON or CSV (depending on the extension chosen by the user)
+#[tauri::command]
+async fn export_repository_statistics(
+    app: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<(), String> {
+    let statistics = {
+        let mut singleton = state.phenoboard.lock()
+            .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+        singleton.get_repository_statistics()?
+    };
+    let app_handle = app.clone();
+    tokio::task::spawn_blocking(move || {
+        let file = app_handle.dialog().file()
+            .add_filter("JSON files", &["json"])
+            .add_filter("CSV files", &["csv"])
+            .set_file_name("phenopacket_store_statistics.json")
+            .blocking_save_file()
+            .ok_or_else(|| "User cancelled file selection".to_string())?;
+        let path = file.as_path()
+            .ok_or_else(|| "Failed to extract path from FileDialogPath".to_string())?
+            .to_path_buf();
+        let contents = if path.extension().map_or(false, |ext| ext == "csv") {
+            statistics.to_csv()
+        } else {
+            serde_json::to_string_pretty(&statistics)
+                .map_err(|e| format!("Failed to serialize statistics: {}", e))?
+        };
+        fs::write(&path, contents)
+            .map_err(|e| format!("Failed to write file: {}", e))
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Search all cohorts of Phenopacket Store by HPO term (optionally including descendants), gene, variant,
+/// disease, PMID, individual ID and curator ORCID
+#[tauri::command]
+async fn search_repository(
+    state: tauri::State<'_, Arc<AppState>>,
+    query: SearchQuery,
+) -> Result<SearchResult, String> {
+    let mut singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    singleton.search_repository(query)
+}
+
+/// Build the index of the Phenopacket Store directory, which starts watching it. Emits a
+/// "ppkt-store-changed" event with the affected cohorts whenever files change on disk.
+#[tauri::command]
+fn watch_ppkt_store(
+    app: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<(), String> {
+    let mut singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    singleton.watch_ppkt_store(move |change| {
+        let _ = app.emit("ppkt-store-changed", &change);
+    })
+}
+
+
 
 #[tauri::command]
 fn emit_backend_status(
@@ -390,7 +854,7 @@ fn save_cohort_data(
     state: tauri::State<'_, Arc<AppState>>,
     cohort_dto: CohortData) 
 -> Result<(), String> {
-    let singleton = state.phenoboard.lock()
+    let mut singleton = state.phenoboard.lock()
         .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
     singleton.save_template_json(cohort_dto)
 }
@@ -439,28 +903,124 @@ fn export_ppkt(
     cohort: CohortData, 
     overwrite: bool
 ) -> Result<usize, String> {
+    let path = std::path::PathBuf::from(&directory);
     // acquire lock only as long as needed
     let (orcid, hpo) = {
-        let singleton = state.phenoboard.lock()
+        let mut singleton = state.phenoboard.lock()
             .map_err(|_| "Failed to acquire lock on phenoboard".to_string())?;
         let orcid = singleton.get_orcid_id()?;
         let hpo = singleton.get_hpo()
             .ok_or_else(|| "could not retieve HPO".to_string())?;
+        singleton.check_project_settings(Some(&cohort.cohort_type))?;
+        singleton.record_recent_file(&path, RecentFileKind::ExportDir);
         (orcid, hpo)
     };
-    let path = std::path::PathBuf::from(&directory);
     ga4ghphetools::ppkt::write_phenopackets(cohort, path, orcid, hpo, overwrite)
 }
 
 #[tauri::command]
 fn export_hpoa(
     state: tauri::State<'_, Arc<AppState>>,
-    cohort_dto: CohortData) -> Result<String, String> {
+    cohort_dto: CohortData) -> Result<HpoaExportSummary, String> {
     let mut singleton = state.phenoboard.lock()
         .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
     singleton.export_hpoa(cohort_dto)
 }
 
+/// Export a ready-to-run bundle for GPSEA and PheVal (phenopackets, GPSEA config, PheVal corpus with VCF files)
+#[tauri::command]
+fn export_analysis_bundle(
+    state: tauri::State<'_, Arc<AppState>>,
+    cohort_dto: CohortData) -> Result<AnalysisBundleSummary, String> {
+    let mut singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    singleton.export_analysis_bundle(cohort_dto)
+}
+
+/// Export the cohort as Phenopacket v2 Cohort message and Family messages (families are derived from the
+/// individual ids, e.g., "Family 2 II-1")
+#[tauri::command]
+fn export_phenopacket_collections(
+    state: tauri::State<'_, Arc<AppState>>,
+    cohort_dto: CohortData) -> Result<PhenopacketCollectionSummary, String> {
+    let mut singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    singleton.export_phenopacket_collections(cohort_dto)
+}
+
+/// Export the cohort as Excel workbook (one row per individual, with a legend sheet) or TSV for collaborators.
+/// Returns the path of the file that was written.
+#[tauri::command]
+async fn export_cohort_sheet(
+    app: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    cohort_dto: CohortData,
+) -> Result<String, String> {
+    let state_handle = state.inner().clone();
+    let app_handle = app.clone();
+    tokio::task::spawn_blocking(move || {
+        let acronym = serde_json::to_value(&cohort_dto).ok()
+            .and_then(|cohort| cohort["cohortAcronym"].as_str().map(String::from));
+        let file_name = match acronym {
+            Some(acronym) => format!("{}_cohort.xlsx", acronym),
+            None => "cohort.xlsx".to_string(),
+        };
+        let file = app_handle.dialog().file()
+            .add_filter("Excel workbook", &["xlsx"])
+            .add_filter("Tab-separated values", &["tsv"])
+            .set_file_name(&file_name)
+            .blocking_save_file()
+            .ok_or_else(|| "User cancelled file selection".to_string())?;
+        let mut path = file.as_path()
+            .ok_or_else(|| "failed to extract path from FileDialogPath".to_string())?
+            .to_path_buf();
+        if !path.extension().is_some_and(|ext| ext == "xlsx" || ext == "tsv") {
+            path.set_extension("xlsx");
+        }
+        let mut singleton = state_handle.phenoboard.lock()
+            .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+        singleton.export_cohort_sheet(&cohort_dto, &path)?;
+        Ok(path.to_string_lossy().to_string())
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Read an edited Excel/TSV export back into the cohort (the rows of the sheet replace the rows of the cohort)
+#[tauri::command]
+async fn import_cohort_sheet(
+    app: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    cohort_dto: CohortData,
+) -> Result<CohortData, String> {
+    let state_handle = state.inner().clone();
+    let app_handle = app.clone();
+    tokio::task::spawn_blocking(move || {
+        let file = app_handle.dialog().file()
+            .add_filter("Cohort sheet", &["xlsx", "xls", "tsv"])
+            .blocking_pick_file()
+            .ok_or_else(|| "User cancelled file selection".to_string())?;
+        let path = file.as_path()
+            .ok_or_else(|| "failed to extract path from FileDialogPath".to_string())?
+            .to_path_buf();
+        let mut singleton = state_handle.phenoboard.lock()
+            .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+        singleton.import_cohort_sheet(&cohort_dto, &path)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Rows of the HPOA files that `export_hpoa` would write, with the problems found by the validator
+#[tauri::command]
+fn preview_hpoa(
+    state: tauri::State<'_, Arc<AppState>>,
+    cohort_dto: CohortData) -> Result<HpoaPreview, String> {
+    let singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    singleton.preview_hpoa(&cohort_dto)
+}
+
 
 
 #[tauri::command]
@@ -494,15 +1054,66 @@ fn add_new_row_to_cohort(
     ga4ghphetools::factory::add_new_row_to_cohort(hpo, individual_data, hpo_annotations, variant_key_list, cohort_data)
 }
 
+/// Add a new row to the cohort from the confirmed hits of text mining (see [`mine_clinical_text`]).
+/// HPO columns that are not yet in the cohort are added automatically.
+#[tauri::command]
+fn add_mined_row_to_cohort(
+    state: tauri::State<'_, Arc<AppState>>,
+    individual_data: IndividualData,
+    mined_terms: Vec<MappedTerm>,
+    variant_key_list: Vec<String>,
+    cohort_data: CohortData)
+-> Result<MinedRowResult, String> {
+    let singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    let hpo = match singleton.get_hpo(){
+        Some(ontology) => ontology.clone(),
+        None => { return Err("HPO not initialized".to_string()); },
+    };
+    crate::hpo::mined_row::add_mined_row_to_cohort(hpo, individual_data, mined_terms, variant_key_list, cohort_data)
+}
+
 
+/// Validate an HGVS variant. If the project settings require MANE Select transcripts, the transcript
+/// is first checked against the MANE Select transcript of the gene (retrieved from HGNC). If HGNC cannot be
+/// reached or the gene has no MANE Select transcript, the check is skipped with a warning so that curation
+/// also works offline.
 #[tauri::command]
-fn validate_hgvs_variant(
-    symbol: &str,
-    hgnc: &str,
-    transcript: &str,
-    allele: &str) 
+async fn validate_hgvs_variant(
+    state: tauri::State<'_, Arc<AppState>>,
+    symbol: String,
+    hgnc: String,
+    transcript: String,
+    allele: String) 
 -> Result<HgvsVariant, String> {
-    ga4ghphetools::variant::validate_hgvs_variant(symbol, hgnc, transcript, allele)
+    let project_settings = {
+        let singleton = state.phenoboard.lock()
+            .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+        singleton.get_project_settings()?
+    };
+    if let Some(project_settings) = project_settings.filter(|p| p.transcript_policy == Some(TranscriptPolicy::ManeSelect)) {
+        let warning = match util::fetch_hgnc_data(&symbol).await {
+            Ok(bundle) if bundle.mane_select().trim().is_empty() => Some(format!(
+                "{} has no MANE Select transcript; {} was not checked against the project settings", symbol, transcript)),
+            Ok(bundle) => {
+                project_settings.check_transcript(&symbol, &transcript, bundle.mane_select())?;
+                None
+            },
+            Err(e) => Some(format!(
+                "Could not retrieve the MANE Select transcript of {} from HGNC ({}); {} was not checked against the project settings",
+                symbol, e, transcript)),
+        };
+        if let Some(warning) = warning {
+            let singleton = state.phenoboard.lock()
+                .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+            singleton.add_background_warning(warning);
+        }
+    }
+    tokio::task::spawn_blocking(move || {
+        ga4ghphetools::variant::validate_hgvs_variant(&symbol, &hgnc, &transcript, &allele)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
 }
 
 #[tauri::command]
@@ -537,10 +1148,11 @@ async fn load_external_excel(
     tokio::task::spawn_blocking(move || {
         match app_handle.dialog().file().blocking_pick_file() {
             Some(file) => {
-                let singleton = state_handle.phenoboard.lock().unwrap();
+                let mut singleton = state_handle.phenoboard.lock().unwrap();
                 let path_str = file.to_string();
                 match excel::read_external_excel_to_dto(&path_str, row_based) {
                     Ok(dto) => {
+                        singleton.record_recent_file(std::path::Path::new(&path_str), RecentFileKind::Excel);
                         let status = singleton.get_status();
                         let _ = app_handle.emit("backend_status", &status);
                         Ok(dto)
@@ -570,8 +1182,10 @@ async fn load_external_excel(
 #[tauri::command]
 async fn save_external_template_json(
     app: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
     template: EtlDto
 ) -> Result<(), String> {
+    let state_handle = state.inner().clone();
     let app_handle = app.clone();
     //println!("save_external_template_json -- {:?}", template);
     tokio::task::spawn_blocking(move || {
@@ -589,6 +1203,9 @@ async fn save_external_template_json(
                     .map_err(|e| format!("Failed to serialize template: {}", e))?;
                 fs::write(&path, json)
                     .map_err(|e| format!("Failed to write file: {}", e))?;
+                if let Ok(mut singleton) = state_handle.phenoboard.lock() {
+                    singleton.record_recent_file(&path, RecentFileKind::EtlJson);
+                }
                 Ok(())
             } else {
                 let _ = app_handle.emit("templateLoaded", "failure");
@@ -608,26 +1225,95 @@ async fn save_external_template_json(
 #[tauri::command]
 async fn load_external_template_json(
     app: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
 ) -> Result<EtlDto, String> {
+    let state_handle = state.inner().clone();
     let app_handle = app.clone();
     tokio::task::spawn_blocking(move || {
         let fpath =  app_handle.dialog().file().blocking_pick_file();
         match fpath {
-            Some(file_path) => {
-                let path_str = file_path.to_string();
-                let contents = fs::read_to_string(path_str)
-                    .map_err(|e| format!("Failed to read file: {}", e))?;
-                let dto: EtlDto = serde_json::from_str(&contents)
-                    .map_err(|e| format!("Failed to deserialize JSON: {}", e))?;
-                Ok(dto)
-            }
-            None => todo!(),
+            Some(file_path) => read_external_template_json(&state_handle, &file_path.to_string()),
+            None => Err("User cancelled file selection".to_string()),
         }
     })
     .await
     .map_err(|e| format!("Task join error: {}", e))?
 }
 
+/// Read an external template JSON file and add it to the recent files
+fn read_external_template_json(state: &AppState, path_str: &str) -> Result<EtlDto, String> {
+    let contents = fs::read_to_string(path_str)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let dto: EtlDto = serde_json::from_str(&contents)
+        .map_err(|e| format!("Failed to deserialize JSON: {}", e))?;
+    if let Ok(mut singleton) = state.phenoboard.lock() {
+        singleton.record_recent_file(std::path::Path::new(path_str), RecentFileKind::EtlJson);
+    }
+    Ok(dto)
+}
+
+/// Get the recently used files of one kind (or of all kinds), most recent first
+#[tauri::command]
+fn get_recent_files(
+    state: tauri::State<'_, Arc<AppState>>,
+    kind: Option<RecentFileKind>,
+) -> Result<Vec<RecentFile>, String> {
+    let singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    Ok(singleton.get_recent_files(kind))
+}
+
+#[tauri::command]
+fn clear_recent_files(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<(), String> {
+    let mut singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    singleton.clear_recent_files()
+}
+
+/// Load a cohort template from the recent files (without a file dialog)
+#[tauri::command]
+async fn reopen_recent_template(
+    app: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    path: String,
+) -> Result<CohortData, String> {
+    let mut singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    let result = singleton.load_ptools_json(&path);
+    let mut status = singleton.get_status();
+    if let Err(e) = &result {
+        status.has_error = true;
+        status.error_message = e.clone();
+    }
+    let _ = app.emit("backend_status", &status);
+    result
+}
+
+/// Load an external template JSON file from the recent files (without a file dialog)
+#[tauri::command]
+async fn reopen_recent_external_template(
+    state: tauri::State<'_, Arc<AppState>>,
+    path: String,
+) -> Result<EtlDto, String> {
+    read_external_template_json(&state, &path)
+}
+
+/// Load an external Excel file from the recent files (without a file dialog)
+#[tauri::command]
+async fn reopen_recent_excel(
+    state: tauri::State<'_, Arc<AppState>>,
+    path: String,
+    row_based: bool,
+) -> Result<ColumnTableDto, String> {
+    let dto = excel::read_external_excel_to_dto(&path, row_based)?;
+    let mut singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    singleton.record_recent_file(std::path::Path::new(&path), RecentFileKind::Excel);
+    Ok(dto)
+}
+
 
 /// Remove all columns from our CohortData that just have "na" values (these can accumulate during curation and are not needed)
 #[tauri::command]
@@ -657,6 +1343,45 @@ async fn save_biocurator_orcid(
     singleton.save_biocurator_orcid(orcid)
 }
 
+#[tauri::command]
+async fn get_curator_profiles(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<CuratorProfiles, String> {
+    let singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    Ok(singleton.get_curator_profiles())
+}
+
+#[tauri::command]
+async fn save_curator_profile(
+    state: tauri::State<'_, Arc<AppState>>,
+    profile: CuratorProfile
+) -> Result<StatusDto, String> {
+    let mut singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    singleton.save_curator_profile(profile)
+}
+
+#[tauri::command]
+async fn select_curator_profile(
+    state: tauri::State<'_, Arc<AppState>>,
+    name: String
+) -> Result<StatusDto, String> {
+    let mut singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    singleton.select_curator_profile(&name)
+}
+
+#[tauri::command]
+async fn remove_curator_profile(
+    state: tauri::State<'_, Arc<AppState>>,
+    name: String
+) -> Result<StatusDto, String> {
+    let mut singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    singleton.remove_curator_profile(&name)
+}
+
 #[tauri::command]
 async fn get_variant_analysis(
     state: tauri::State<'_, Arc<AppState>>,
@@ -702,6 +1427,39 @@ async fn process_allele_column<R>(
     }).await.map_err(|e| e.to_string())?
 }
 
+/// Mine all case reports (*.txt, *.md) in a directory and create a draft cohort with one row per file.
+/// Emits "progress-update" signals after each file.
+#[tauri::command]
+async fn mine_case_report_directory<R>(
+    state: tauri::State<'_, Arc<AppState>>,
+    app: AppHandle<R>,
+    directory: String,
+    disease_data: DiseaseData,
+    cohort_type: CohortType,
+    acronym: String,
+) -> Result<BatchMiningReport, String> where R: Runtime {
+    let state_handle = state.inner().clone();
+    tokio::task::spawn_blocking(move || {
+        let singleton = state_handle.phenoboard.lock()
+            .map_err(|_| "Failed to acquire lock".to_string())?;
+        let hpo = singleton.get_hpo()
+            .ok_or_else(|| "HPO not initialized".to_string())?;
+        singleton.check_project_settings(Some(&cohort_type))?;
+        let pb = |current: u32, total: u32| {
+            let _ = app.emit("progress-update", ProgressPayload { current, total });
+        };
+        crate::hpo::batch_mining::mine_case_report_directory(
+            hpo,
+            std::path::Path::new(&directory),
+            disease_data,
+            cohort_type,
+            acronym,
+            |text| singleton.mine_clinical_text(text),
+            pb,
+        )
+    }).await.map_err(|e| e.to_string())?
+}
+
 /// This command creates a CohortData object from the current EtlDto and should
 /// be called after the user has finished transformation
 #[tauri::command]
@@ -856,57 +1614,52 @@ async fn mine_multi_hpo_column(
 }
 
 /// Get list of unique concepts (in which the row number is fake) for the 
-/// first phase of text mining
+/// first phase of text mining. The concepts are returned in order of first occurrence.
 #[tauri::command]
 async fn create_canonical_dictionary(
     mining_results: Vec<MiningConcept>,
 ) -> Result<Vec<MiningConcept>, String> {
-    let mut unique_map: HashMap<String, MiningConcept> = HashMap::new();
-
-    for mut concept in mining_results {
-        unique_map
-            .entry(concept.original_text.clone())
-            .and_modify(|existing| {
-                // Merge row indices
-                existing
-                    .row_index_list
-                    .extend(concept.row_index_list.drain(..));
-            })
-            .or_insert_with(|| {
-                // First time we see this concept → becomes canonical
-                concept
-            });
-    }
-
-    // Optional but usually a good idea: deduplicate + sort row indices
-    for concept in unique_map.values_mut() {
-        concept.row_index_list.sort_unstable();
-        concept.row_index_list.dedup();
-    }
-
-    // Convert back to Vec and sort by text for stability
-    let mut result: Vec<MiningConcept> = unique_map.into_values().collect();
-    result.sort_by(|a, b| a.original_text.cmp(&b.original_text));
-
-    Ok(result)
+    Ok(crate::hpo::hpo_etl::create_canonical_dictionary(mining_results))
 }
 
 
-/// Get list of unique concepts (in which the row number is fake) for the 
-/// first phase of text mining
-
+/// Distribute the confirmed concepts back to the cells of the column (one [`MinedCell`] per unique
+/// cell text, ordered by first row) and report terms with conflicting observed/excluded status.
 #[tauri::command]
 async fn create_cell_mappings(
     mining_results: Vec<MiningConcept>,
     cell_values: Vec<String>,
-) -> Result<Vec<MinedCell>, String> {
+) -> Result<CellMappingResult, String> {
     crate::hpo::hpo_etl::create_cell_mappings(cell_values, mining_results)
 }
 
 
+/// Serialize the mined cells into one multi-HPO string per row in the form read by ga4ghphetools
+/// (see [`crate::hpo::multi_hpo_format`]). Rows that are not covered by any cell get an empty string;
+/// `n_rows` is the number of rows of the column.
+#[tauri::command]
+async fn get_multi_hpo_strings(
+    mined_cells: Vec<MinedCell>,
+    n_rows: Option<usize>,
+) -> Result<Vec<String>, String> {
+    crate::hpo::multi_hpo_format::get_multi_hpo_strings(mined_cells, n_rows)
+}
+
+/// Parse multi-HPO strings (e.g., from an ETL file that was edited outside of the app) back into mined cells
 #[tauri::command]
-async fn get_multi_hpo_strings(mined_cells: Vec<MinedCell>) -> Result<Vec<String>, String> {
-    crate::hpo::hpo_etl::get_multi_hpo_strings(mined_cells)
+async fn parse_multi_hpo_strings(
+    state: tauri::State<'_, Arc<AppState>>,
+    cell_values: Vec<String>,
+) -> Result<Vec<MinedCell>, String> {
+    let singleton = state.phenoboard.lock()
+        .map_err(|_| "Failed to acquire lock on HPO State".to_string())?;
+    let hpo = singleton.get_hpo()
+        .ok_or_else(|| "HPO not initialized".to_string())?;
+    let label_lookup = |hpo_id: &str| -> Option<String> {
+        let term_id: TermId = hpo_id.parse().ok()?;
+        hpo.term_by_id(&term_id).map(|term| term.name().to_string())
+    };
+    crate::hpo::multi_hpo_format::parse_multi_hpo_strings(&cell_values, &label_lookup)
 }
   
 
@@ -2,15 +2,16 @@
 //!
 
 
-use crate::{directory_manager::DirectoryManager, dto::{pmid_dto::PmidDto}, hpo::MiningConcept, settings::HpoCuratorSettings, util::{pubmed_retrieval::PubmedRetriever}};
-use std::{collections::HashSet, env, fs::File, io::Write, path::{Path, PathBuf},  sync::Arc};
+use crate::{cohort_export::{analysis_bundle::{self, AnalysisBundleSummary}, flat_sheet, phenopacket_collections::{self, PhenopacketCollectionSummary}}, directory_manager::{DirectoryManager, batch_export::{ExportJob, temp_export_dir}, cohort_metadata::CohortOverviewRow, git_status::{self, CohortCommit, RepositoryGitStatus}, layout::{RegisteredRepositories, RepositoryConfig, RepositoryLayout}, search::{SearchQuery, SearchResult}, statistics::{self, RepositoryStatistics}, watcher::{RepositoryChangeEvent, RepositoryWatcher}}, dto::{pmid_dto::PmidDto}, hpo::{MiningConcept, hpo_release::HpoReleaseSource, hpoa_export::{self, HpoaExportSummary, HpoaPreview}, ontology_cache, text_annotation::{self, HighlightedText}}, settings::{self, HpoCuratorSettings, curator_profile::{CuratorProfile, CuratorProfiles}, project_settings::ProjectSettings, recent_files::{RecentFile, RecentFileKind}}, util::{pubmed_retrieval::PubmedRetriever}};
+use std::{collections::{BTreeSet, HashSet}, env, fs::File, io::Write, path::{Path, PathBuf},  sync::{Arc, Mutex, OnceLock}, time::{SystemTime, UNIX_EPOCH}};
 
 
-use ontolius::{io::OntologyLoaderBuilder, ontology::{MetadataAware, OntologyTerms, csr::FullCsrOntology}};
+use ontolius::{io::OntologyLoaderBuilder, ontology::{HierarchyQueries, MetadataAware, OntologyTerms, csr::FullCsrOntology}, TermId};
 use fenominal::{AutoCompleter, Fenominal, FenominalSentence, OntologyMatch};
-use ga4ghphetools::{dto::{cohort_dto::{CohortData, CohortType, DiseaseData}, etl_dto::EtlDto, hpo_term_dto::{ CellValueInner, HpoTermDuplet}, variant_dto::VariantDto}, hpoa, repo::repo_qc::RepoQc, tauri::models::HierarchyMapItem};
+use ga4ghphetools::{dto::{cohort_dto::{CohortData, CohortType, DiseaseData}, etl_dto::EtlDto, hpo_term_dto::{ CellValueInner, HpoTermDuplet}, variant_dto::VariantDto}, repo::repo_qc::RepoQc, tauri::models::HierarchyMapItem};
 use ga4ghphetools;
 use rfd::FileDialog;
+use tempfile::TempDir;
 use crate::dto::status_dto::StatusDto;
 
 
@@ -25,13 +26,25 @@ pub struct PhenoboardSingleton {
     /// Path to the directory where we store the template and the phenopackets
     pt_template_dir_path: Option<PathBuf>,
     ///  Autocompletion from fenominal library
-    autocompleter: Option<AutoCompleter>,
+    autocompleter: OnceLock<AutoCompleter>,
+    /// Cached index of the cohort directories of Phenopacket Store
+    repo_index: Option<Arc<Mutex<DirectoryManager>>>,
+    /// Keeps `repo_index` current; started whenever the index is built
+    repo_watcher: Option<RepositoryWatcher>,
+    /// Problems of background tasks (e.g., writing the HPO cache), reported in the status
+    background_warnings: Arc<Mutex<Vec<String>>>,
+    /// Called when the repository index changed (e.g., to emit an event to the frontend)
+    repo_change_listener: Arc<Mutex<Option<RepositoryChangeListener>>>,
 }
 
+/// Callback for changes of the repository index (see [`PhenoboardSingleton::watch_ppkt_store`])
+type RepositoryChangeListener = Box<dyn Fn(RepositoryChangeEvent) + Send>;
+
 impl PhenoboardSingleton {
     /// Create a new instance of PhenoboardSingleton
     /// 
-    /// The constructor will try to load the HPO from the settings file if available;
+    /// The constructor will try to load the HPO from the settings file if available (using the
+    /// cache in `~/.phenoboard/cache` if it is current, see [`ontology_cache`]);
     /// if something does not work, it will leave the ontology field as None
     pub fn new() -> Self {
         let mut singleton = PhenoboardSingleton::default();
@@ -40,14 +53,21 @@ impl PhenoboardSingleton {
             return singleton;
         }
         let hpo_json = hpo_json_result.unwrap();
-        let loader = OntologyLoaderBuilder::new().obographs_parser().build();
-        let ontology_opt: Option<FullCsrOntology> = loader.load_from_path(hpo_json.clone()).ok();
+        let mut warnings: Vec<String> = Vec::new();
+        let ontology_opt: Option<FullCsrOntology> = match settings::get_cache_dir() {
+            Ok(cache_dir) => ontology_cache::load_ontology(Path::new(&hpo_json), &cache_dir, &mut warnings).ok(),
+            Err(e) => {
+                warnings.push(format!("Could not use HPO cache: {}", e));
+                let loader = OntologyLoaderBuilder::new().obographs_parser().build();
+                loader.load_from_path(hpo_json.clone()).ok()
+            }
+        };
         if let Some(hpo) = ontology_opt {
-            let hpo_arc = Arc::new(hpo);
-            singleton.ontology = Some(hpo_arc.clone());
-            singleton.autocompleter = Some(AutoCompleter::new(hpo_arc.clone()));
-            singleton.set_hpo(hpo_arc.clone(), &hpo_json);
-        }           
+            singleton.set_hpo(Arc::new(hpo), &hpo_json);
+        }
+        for warning in warnings {
+            singleton.add_background_warning(warning);
+        }
         return singleton;
     }
 
@@ -55,10 +75,17 @@ impl PhenoboardSingleton {
 
     pub fn set_hpo(&mut self, ontology: Arc<FullCsrOntology>, hpo_json_path: &str) {
         self.ontology = Some(ontology.clone());
-        self.autocompleter = Some(AutoCompleter::new(ontology.clone()));
+        // the autocompleter for the new ontology is built when it is first used
+        self.autocompleter = OnceLock::new();
         let _ = self.settings.set_hp_json_path(hpo_json_path);
     }
 
+    /// The autocompleter is built on first use, since building it takes a noticeable time at startup
+    fn autocompleter(&self) -> Option<&AutoCompleter> {
+        let hpo = self.ontology.as_ref()?;
+        Some(self.autocompleter.get_or_init(|| AutoCompleter::new(hpo.clone())))
+    }
+
     pub fn get_hpo(&self) -> Option<Arc<FullCsrOntology>> {
         match &self.ontology {
             Some(hpo) => Some(hpo.clone()),
@@ -99,6 +126,7 @@ impl PhenoboardSingleton {
                 row_index_list: vec![idx],
                 suggested_terms: suggested,
                 mining_status: crate::hpo::MiningStatus::Pending,
+                clinical_status: crate::hpo::ClinicalStatus::Observed,
             });
         }
         concepts
@@ -108,8 +136,7 @@ impl PhenoboardSingleton {
     /// Provide Strings with TermId - Label that will be used for autocompletion
     /// fenominal functionality
     pub fn search_hpo(&self, query: &str, limit: usize) -> Vec<OntologyMatch> {
-        self.autocompleter
-            .as_ref()
+        self.autocompleter()
             .map(|ac| ac.search_hpo(query, limit))
             .unwrap_or_default()
     }
@@ -123,8 +150,7 @@ impl PhenoboardSingleton {
     /// We want to get the single best match of any HPO term label to the query string
     /// using the fenominal autocompletion functionality
     pub fn get_best_hpo_match(&self, query: String) -> Option<OntologyMatch> {
-        self.autocompleter
-            .as_ref()
+        self.autocompleter()
             .map(|ac| ac.get_best_hpo_match(query))
             .unwrap_or_default()
     }
@@ -170,8 +196,11 @@ impl PhenoboardSingleton {
     ) -> Result<CohortData, String> {
         match Self::get_parent_dir(json_file) {
             Some(project_dir) => {
-                ga4ghphetools::persistence::initialize_project_dir(project_dir)?;
-                ga4ghphetools::factory::load_json_cohort(json_file)
+                ga4ghphetools::persistence::initialize_project_dir(project_dir.clone())?;
+                let cohort = ga4ghphetools::factory::load_json_cohort(json_file)?;
+                self.pt_template_dir_path = Some(project_dir);
+                self.record_recent_file(Path::new(json_file), RecentFileKind::Template);
+                Ok(cohort)
             },
             None => Err("Could not load JSON template because we could not initialize the parent directory".to_string()),
         }
@@ -205,22 +234,260 @@ impl PhenoboardSingleton {
             .unwrap_or_else(|_| "Not Initialized".to_string()); 
         status.biocurator_orcid =self.settings.get_biocurator_orcid().clone()
             .unwrap_or_else(|_| "Not Set".to_string());
+        status.curator_name = self.settings.get_active_profile()
+            .map(|p| p.name.clone())
+            .unwrap_or_default();
+        status.settings_warnings = self.settings.get_warnings().to_vec();
+        if let Ok(warnings) = self.background_warnings.lock() {
+            status.background_warnings = warnings.clone();
+        }
+        match self.get_project_settings() {
+            Ok(project_settings) => status.project_settings = project_settings,
+            Err(e) => status.settings_warnings.push(e),
+        }
         return status;
     }
 
 
-    pub fn get_ppkt_store_json(&self) ->  Result<serde_json::Value, String> {
+    /// The directory with all cohort subdirectories of the selected repository, or, if no repository
+    /// was selected in the settings, of Phenopacket Store as derived from the template path
+    fn get_ppkt_store_dir(&self) -> Result<PathBuf, String> {
+        if let Some(repository) = self.settings.get_active_repository() {
+            return Ok(PathBuf::from(&repository.base_dir));
+        }
         let file_path = match &self.pt_template_path {
             Some(path) => path,
             None => {return Err(format!("Template path not initialized"));},
         };
         let path = Path::new(&file_path);
-        let parent = match path.parent() {
-            Some(parent_path) => parent_path,
-            None => {return Err(format!("Template parent path not initialized"));},
+        match path.parent() {
+            Some(parent_path) => Ok(parent_path.to_path_buf()),
+            None => Err(format!("Template parent path not initialized")),
+        }
+    }
+
+    /// The project settings of the current cohort or repository (None if there is no project settings file).
+    ///
+    /// The file is read on each call so that changes (e.g., after a git pull) take effect immediately.
+    pub fn get_project_settings(&self) -> Result<Option<ProjectSettings>, String> {
+        let start_dir = match &self.pt_template_dir_path {
+            Some(cohort_dir) => cohort_dir.clone(),
+            None => match self.get_ppkt_store_dir() {
+                Ok(store_dir) => store_dir,
+                Err(_) => { return Ok(None); },
+            },
+        };
+        ProjectSettings::discover(&start_dir)
+    }
+
+    /// Add a warning that is shown with the status (each message only once)
+    pub fn add_background_warning(&self, warning: String) {
+        if let Ok(mut warnings) = self.background_warnings.lock() {
+            if !warnings.contains(&warning) {
+                warnings.push(warning);
+            }
+        }
+    }
+
+    /// Check the HPO version and cohort type against the project settings before an export
+    pub fn check_project_settings(&self, cohort_type: Option<&CohortType>) -> Result<(), String> {
+        let Some(project_settings) = self.get_project_settings()? else {
+            return Ok(());
+        };
+        if let Some(hpo) = &self.ontology {
+            project_settings.check_hpo_version(hpo.version())?;
+        }
+        if let Some(cohort_type) = cohort_type {
+            let cohort_type = serde_json::to_value(cohort_type)
+                .map_err(|e| e.to_string())?
+                .as_str()
+                .unwrap_or_default()
+                .to_string();
+            project_settings.check_cohort_type(&cohort_type)?;
+        }
+        Ok(())
+    }
+
+    /// The layout of the cohort directories of the selected repository (phenopacket-store layout by default)
+    fn get_repository_layout(&self) -> RepositoryLayout {
+        self.settings
+            .get_active_repository()
+            .map(|repository| repository.layout.clone())
+            .unwrap_or_default()
+    }
+
+    /// Get the cached index of Phenopacket Store, building it if needed (or if the store directory or layout changed).
+    ///
+    /// Whenever the index is built, we start a [`RepositoryWatcher`] that keeps it current. If the
+    /// watcher cannot be started, the index is rebuilt on every call so that it is never stale.
+    pub fn get_repo_index(&mut self) -> Result<Arc<Mutex<DirectoryManager>>, String> {
+        let store_dir = self.get_ppkt_store_dir()?;
+        let layout = self.get_repository_layout();
+        if let Some(index) = &self.repo_index {
+            let is_current = index.lock()
+                .map(|dirman| dirman.base_dir() == store_dir.as_path() && dirman.layout() == &layout)
+                .unwrap_or(false);
+            if is_current && self.repo_watcher.is_some() {
+                return Ok(index.clone());
+            }
+        }
+        // Drop the previous watcher (if any) before the new one is started
+        self.repo_watcher = None;
+        let dirman = DirectoryManager::with_layout(store_dir.to_string_lossy(), layout)?;
+        let index = Arc::new(Mutex::new(dirman));
+        let listener = self.repo_change_listener.clone();
+        let on_change = move |change: RepositoryChangeEvent| {
+            if let Ok(listener) = listener.lock() {
+                if let Some(listener) = listener.as_ref() {
+                    listener(change);
+                }
+            }
+        };
+        match RepositoryWatcher::start(index.clone(), on_change) {
+            Ok(watcher) => self.repo_watcher = Some(watcher),
+            Err(e) => {
+                index.lock()
+                    .map_err(|_| "Failed to acquire lock on repository index".to_string())?
+                    .add_scan_warning(format!("{}; the repository is rescanned on every request", e));
+            }
+        }
+        self.repo_index = Some(index.clone());
+        Ok(index)
+    }
+
+    /// Register the callback for changes of the repository index. `on_change` is called with the
+    /// affected cohorts whenever files of Phenopacket Store change on disk (e.g., after a git pull).
+    pub fn set_repo_change_listener<F>(&self, on_change: F) -> Result<(), String>
+    where
+        F: Fn(RepositoryChangeEvent) + Send + 'static,
+    {
+        *self.repo_change_listener.lock()
+            .map_err(|_| "Failed to acquire lock on repository listener".to_string())? = Some(Box::new(on_change));
+        Ok(())
+    }
+
+    /// Register `on_change` (see [`Self::set_repo_change_listener`]) and build the index (which starts watching it)
+    pub fn watch_ppkt_store<F>(&mut self, on_change: F) -> Result<(), String>
+    where
+        F: Fn(RepositoryChangeEvent) + Send + 'static,
+    {
+        self.set_repo_change_listener(on_change)?;
+        self.get_repo_index()?;
+        Ok(())
+    }
+
+    /// Rescan the indicated cohorts after we changed their files ourselves (e.g., batch export), so
+    /// that the index is current even before the file system notifications arrive.
+    pub fn refresh_repo_cohorts(&mut self, cohort_names: &BTreeSet<String>) -> Result<(), String> {
+        if cohort_names.is_empty() {
+            return Ok(());
+        }
+        let index = self.get_repo_index()?;
+        let change = index.lock()
+            .map_err(|_| "Failed to acquire lock on repository index".to_string())?
+            .refresh_cohorts(cohort_names);
+        if !change.is_empty() {
+            if let Some(listener) = self.repo_change_listener.lock()
+                .map_err(|_| "Failed to acquire lock on repository listener".to_string())?
+                .as_ref() {
+                listener(change);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_ppkt_store_json(&mut self) ->  Result<serde_json::Value, String> {
+        let index = self.get_repo_index()?;
+        let dirman = index.lock()
+            .map_err(|_| "Failed to acquire lock on repository index".to_string())?;
+        dirman.get_json()
+    }
+
+    /// Search all cohort templates (and optionally phenopackets) of Phenopacket Store.
+    ///
+    /// If descendants are requested, an annotation matches if it is the queried HPO term or one of its
+    /// descendants (e.g., a query for *Seizure* finds individuals annotated with *Focal-onset seizure*).
+    pub fn search_repository(&mut self, query: SearchQuery) -> Result<SearchResult, String> {
+        let hpo = match (&self.ontology, query.include_descendants) {
+            (Some(hpo), true) => Some(hpo.clone()),
+            (None, true) => { return Err("HPO must be loaded to search for descendants".to_string()); },
+            _ => None,
         };
-        let dirman = DirectoryManager::new(parent.to_string_lossy())?;
-        return dirman.get_json();
+        let term_matches = |annotated: &str, queried: &str| -> bool {
+            if annotated == queried {
+                return true;
+            }
+            match (&hpo, annotated.parse::<TermId>(), queried.parse::<TermId>()) {
+                (Some(hpo), Ok(annotated_id), Ok(queried_id)) => hpo.is_descendant_of(&annotated_id, &queried_id),
+                _ => false,
+            }
+        };
+        let index = self.get_repo_index()?;
+        let dirman = index.lock()
+            .map_err(|_| "Failed to acquire lock on repository index".to_string())?;
+        Ok(dirman.search(&query, &term_matches))
+    }
+
+    /// Get one summary row per cohort of the Phenopacket Store (see [`DirectoryManager::get_overview`])
+    pub fn get_ppkt_store_overview(&mut self) -> Result<Vec<CohortOverviewRow>, String> {
+        let index = self.get_repo_index()?;
+        let dirman = index.lock()
+            .map_err(|_| "Failed to acquire lock on repository index".to_string())?;
+        Ok(dirman.get_overview())
+    }
+
+    /// Get summary statistics of Phenopacket Store (see [`RepositoryStatistics`])
+    pub fn get_repository_statistics(&mut self) -> Result<RepositoryStatistics, String> {
+        let index = self.get_repo_index()?;
+        let dirman = index.lock()
+            .map_err(|_| "Failed to acquire lock on repository index".to_string())?;
+        Ok(dirman.get_statistics())
+    }
+
+    pub fn get_repositories(&self) -> RegisteredRepositories {
+        RegisteredRepositories {
+            repositories: self.settings.get_repositories().to_vec(),
+            active_repository: self.settings.get_active_repository().map(|r| r.name.clone()),
+        }
+    }
+
+    /// Register a repository with its layout and select it
+    pub fn register_repository(&mut self, config: RepositoryConfig) -> Result<(), String> {
+        self.settings.register_repository(config)
+    }
+
+    pub fn select_repository(&mut self, name: Option<String>) -> Result<(), String> {
+        self.settings.select_repository(name)
+    }
+
+    pub fn remove_repository(&mut self, name: &str) -> Result<(), String> {
+        self.settings.remove_repository(name)
+    }
+
+    /// Get the cohort name and path of each phenopacket of Phenopacket Store
+    pub fn get_phenopacket_paths(&mut self) -> Result<Vec<(String, PathBuf)>, String> {
+        let index = self.get_repo_index()?;
+        let dirman = index.lock()
+            .map_err(|_| "Failed to acquire lock on repository index".to_string())?;
+        Ok(dirman.phenopacket_paths())
+    }
+
+    /// Get the uncommitted changes of each cohort (if Phenopacket Store is a git work tree)
+    pub fn get_git_status(&self) -> Result<RepositoryGitStatus, String> {
+        git_status::get_git_status(&self.get_ppkt_store_dir()?, &self.get_repository_layout())
+    }
+
+    /// Create a local git commit with the changes of one cohort (message is generated if None)
+    pub fn commit_cohort(&self, cohort_name: &str, message: Option<String>) -> Result<CohortCommit, String> {
+        git_status::commit_cohort(&self.get_ppkt_store_dir()?, &self.get_repository_layout(), cohort_name, message)
+    }
+
+    /// Get one export job for each cohort template of Phenopacket Store (see [`DirectoryManager::export_jobs`])
+    pub fn get_export_jobs(&mut self) -> Result<Vec<ExportJob>, String> {
+        let index = self.get_repo_index()?;
+        let dirman = index.lock()
+            .map_err(|_| "Failed to acquire lock on repository index".to_string())?;
+        Ok(dirman.export_jobs())
     }
 
     pub fn mine_clinical_text(
@@ -232,8 +499,17 @@ impl PhenoboardSingleton {
         fenominal.mine_sentences(text).map_err(|e|e.to_string())
     }
 
+    /// Mine the text with fenominal and map each hit back onto its position in the original text
+    pub fn highlight_clinical_text(
+        &self,
+        text: &str
+    ) -> Result<HighlightedText, String> {
+        let sentences = self.mine_clinical_text(text)?;
+        Ok(text_annotation::highlight_text(text, &sentences))
+    }
+
     pub fn perform_hpo_autocomplete(&self, query: String) -> Result<Vec<OntologyMatch>, String> {
-        let autocompleter = self.autocompleter.as_ref().ok_or_else(|| "Autocomplete not initialized".to_string())?;
+        let autocompleter = self.autocompleter().ok_or_else(|| "Autocomplete not initialized".to_string())?;
         let n_term_limit = 20;
         Ok(autocompleter.search_hpo(&query, n_term_limit))
     }
@@ -253,7 +529,7 @@ impl PhenoboardSingleton {
         ga4ghphetools::factory::extract_template_name(cohort_dto)
     }
 
-    pub fn save_template_json(&self, cohort_dto: CohortData) -> Result<(), String> {
+    pub fn save_template_json(&mut self, cohort_dto: CohortData) -> Result<(), String> {
         let save_dir = match &self.pt_template_dir_path {
             Some(dir) => dir.clone(),
             None => {
@@ -273,8 +549,8 @@ impl PhenoboardSingleton {
             let json = serde_json::to_string_pretty(&cohort_dto).map_err(|_|"Could not serialize to JSON".to_string())?;
             let mut file = File::create(&path).map_err(|_|"Could not create file".to_string())?;
             file.write_all(json.as_bytes()).map_err(|_|"Could not write file".to_string())?;
-
-            println!("Saved JSON to {:?}", path);
+            self.pt_template_dir_path = path.parent().map(|p| p.to_path_buf());
+            self.record_recent_file(&path, RecentFileKind::Template);
         } else {
             return Err("Save cancelled by user".to_string());
         };
@@ -283,10 +559,23 @@ impl PhenoboardSingleton {
 
 
     /// Get the default directory for the current cohort. Used to figure out where to save files.
+    /// A malformed project settings file must not block the file dialogs, so in that case we fall back
+    /// to the next candidate and report the error as a warning.
     fn get_default_dir(&self) -> Result<PathBuf, String> {
+        match self.get_project_settings() {
+            Ok(project_settings) => {
+                if let Some(output_dir) = project_settings.and_then(|p| p.get_output_dir()) {
+                    return Ok(output_dir);
+                }
+            },
+            Err(e) => self.add_background_warning(format!("Ignoring output directory of project settings: {}", e)),
+        }
         if let Some(cohort_dir) = &self.pt_template_dir_path {
             return Ok(cohort_dir.clone());
         }
+        if let Some(recent) = self.settings.get_recent_files(Some(RecentFileKind::ExportDir)).first() {
+            return Ok(PathBuf::from(&recent.path));
+        }
         let home_dir = env::var("HOME")
             .or_else(|_| env::var("USERPROFILE")) // Windows fallback
             .map_err(|_| "Failed to get home directory".to_string())?;
@@ -345,14 +634,17 @@ impl PhenoboardSingleton {
         -> Result<usize, String> 
     {
         let path = PathBuf::from(&directory);
+        self.check_project_settings(Some(&cohort.cohort_type))?;
         let orcid = match self.settings.get_biocurator_orcid() {
             Ok(orcid_id) => orcid_id,
             Err(e) => { return Err(format!("Cannot save phenopackets without ORCID id: {}", e)); }
         };
-        match &self.ontology {
-            Some(hpo) =>  ga4ghphetools::ppkt::write_phenopackets(cohort, path, orcid, hpo.clone(), overwrite),
-            None => Err("Cannot export phenopackets because HPO not initialized".to_string()),
-        }
+        let n_written = match &self.ontology {
+            Some(hpo) =>  ga4ghphetools::ppkt::write_phenopackets(cohort, path.clone(), orcid, hpo.clone(), overwrite)?,
+            None => { return Err("Cannot export phenopackets because HPO not initialized".to_string()); },
+        };
+        self.record_recent_file(&path, RecentFileKind::ExportDir);
+        Ok(n_written)
     }
 
 
@@ -364,6 +656,28 @@ impl PhenoboardSingleton {
         ga4ghphetools::repo::get_repo_qc(&out_dir)
     }
 
+    /// Create the rows of the HPOA files of a cohort (one file per disease) without writing them.
+    ///
+    /// Each file preview contains the problems found by the HPOA validator, including HPO terms that are not
+    /// current terms of the loaded ontology; files with problems cannot be exported.
+    pub fn preview_hpoa(
+        &self,
+        cohort_dto: &CohortData)
+    -> Result<HpoaPreview, String> {
+        let orcid = match self.settings.get_biocurator_orcid() {
+            Ok(orcid_id) => orcid_id.to_string(),
+            Err(e) => { return Err(format!("Cannot create HPOA without ORCID id: {}", e)); }
+        };
+        let hpo = self.ontology.clone()
+            .ok_or_else(|| "Cannot create HPOA because HPO not initialized".to_string())?;
+        let cohort_json = serde_json::to_value(cohort_dto)
+            .map_err(|e| format!("Could not serialize cohort: {}", e))?;
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let (year, month, day) = statistics::date_from_unix_seconds(seconds);
+        let date = format!("{:04}-{:02}-{:02}", year, month, day);
+        hpoa_export::build_hpoa(&cohort_json, &|hpo_id| hpoa_export::is_current_term(&hpo, hpo_id), &orcid, &date)
+    }
+
     /// Exports the HPOA (Human Phenotype Ontology Annotations) for a given cohort.
     ///
     /// # Arguments
@@ -373,34 +687,103 @@ impl PhenoboardSingleton {
     ///
     /// # Returns
     ///
-    /// * `Ok(String)` - A string to summarize the successful result.
+    /// * `Ok(HpoaExportSummary)` - The files that were written.
     /// * `Err(String)` - An error message if the export fails.
     ///
     /// # Notes
     ///
-    /// - The output format follows the HPO project guidelines for phenotype annotations.
-    /// - Each individual in the cohort is converted to one or more HPOA rows.
-    /// - This function may fail if required fields in `CohortDto` are missing or invalid.
+    /// - The output format follows the HPO project guidelines for phenotype annotations ("small files").
+    /// - Melded cohorts are split into one file per disease (e.g., `OMIM-154700.tab`).
+    /// - Nothing is written if any of the files does not pass validation.
     pub fn export_hpoa(
         &mut self,
         cohort_dto: CohortData)
-    -> Result<String, String> {
+    -> Result<HpoaExportSummary, String> {
+        self.check_project_settings(Some(&cohort_dto.cohort_type))?;
+        let preview = self.preview_hpoa(&cohort_dto)?;
         let out_dir = self.get_phenopackets_output_dir()?;
+        let summary = hpoa_export::write_hpoa_files(&preview, &out_dir)?;
+        self.record_recent_file(&out_dir, RecentFileKind::ExportDir);
+        Ok(summary)
+    }
+
+
+    /// Export the cohort as a GA4GH Phenopacket v2 Cohort message and one Family message per family.
+    ///
+    /// The individual phenopackets are created in a temporary directory (as by `export_ppkt`) and collected into
+    /// the messages, which are written to a directory chosen by the user.
+    pub fn export_phenopacket_collections(
+        &mut self,
+        cohort_dto: CohortData)
+    -> Result<PhenopacketCollectionSummary, String> {
+        self.check_project_settings(Some(&cohort_dto.cohort_type))?;
+        let out_dir = self.get_phenopackets_output_dir()?;
+        let tmp_dir = self.write_phenopackets_to_temp_dir(&cohort_dto)?;
+        let summary = phenopacket_collections::write_phenopacket_collections(&cohort_dto, tmp_dir.path(), &out_dir)?;
+        self.record_recent_file(&out_dir, RecentFileKind::ExportDir);
+        Ok(summary)
+    }
+
+    /// Export a bundle for GPSEA and PheVal analyses (phenopackets, GPSEA configuration and PheVal corpus with
+    /// VCF files) to `<cohort>_analysis_bundle` in a directory chosen by the user. The cohort must pass the QC.
+    pub fn export_analysis_bundle(
+        &mut self,
+        cohort_dto: CohortData)
+    -> Result<AnalysisBundleSummary, String> {
+        self.check_project_settings(Some(&cohort_dto.cohort_type))?;
+        let hpo = self.ontology.clone()
+            .ok_or_else(|| "Cannot export phenopackets because HPO not initialized".to_string())?;
+        ga4ghphetools::factory::qc_assessment(hpo, &cohort_dto).map_err(|e| e.to_string())?;
+        let out_dir = self.get_phenopackets_output_dir()?;
+        let bundle_dir = out_dir.join(format!("{}_analysis_bundle", phenopacket_collections::cohort_id(&cohort_dto)));
+        let tmp_dir = self.write_phenopackets_to_temp_dir(&cohort_dto)?;
+        let summary = analysis_bundle::write_analysis_bundle(&cohort_dto, tmp_dir.path(), &bundle_dir)?;
+        self.record_recent_file(&out_dir, RecentFileKind::ExportDir);
+        Ok(summary)
+    }
+
+    /// Write the phenopackets of the cohort (as by `export_ppkt`) to a fresh temporary directory, which is removed
+    /// when the returned `TempDir` is dropped
+    fn write_phenopackets_to_temp_dir(
+        &self,
+        cohort_dto: &CohortData)
+    -> Result<TempDir, String> {
         let orcid = match self.settings.get_biocurator_orcid() {
-            Ok(orcid_id) => orcid_id.to_string(),
-            Err(e) => { return Err(format!("Cannot save HPOA without ORCID id: {}", e)); }
+            Ok(orcid_id) => orcid_id,
+            Err(e) => { return Err(format!("Cannot save phenopackets without ORCID id: {}", e)); }
         };
-        match &self.ontology {
-            Some(hpo) => {
-                hpoa::write_hpoa_tsv(cohort_dto, hpo.clone(), &orcid, &out_dir)?;
-                Ok(format!("Wrote HPOA file to {}", out_dir.to_string_lossy()))
-            },
-            None => {
-                Err("Phetools template not initialized".to_string())
-            },
+        let hpo = self.ontology.clone()
+            .ok_or_else(|| "Cannot export phenopackets because HPO not initialized".to_string())?;
+        let tmp_dir = temp_export_dir(&phenopacket_collections::cohort_id(cohort_dto))?;
+        ga4ghphetools::ppkt::write_phenopackets(cohort_dto.clone(), tmp_dir.path().to_path_buf(), orcid, hpo, true)?;
+        Ok(tmp_dir)
+    }
+
+    /// Write the cohort as a flat table for collaborators (Excel workbook with legend, or TSV if `path` ends in .tsv)
+    pub fn export_cohort_sheet(
+        &mut self,
+        cohort_dto: &CohortData,
+        path: &Path)
+    -> Result<(), String> {
+        flat_sheet::export_cohort_sheet(cohort_dto, path)?;
+        if let Some(dir) = path.parent() {
+            self.record_recent_file(dir, RecentFileKind::ExportDir);
         }
+        Ok(())
     }
 
+    /// Read an edited flat table (see [`Self::export_cohort_sheet`]) back into the cohort; the rows of the table
+    /// replace the rows of `cohort_dto`
+    pub fn import_cohort_sheet(
+        &mut self,
+        cohort_dto: &CohortData,
+        path: &Path)
+    -> Result<CohortData, String> {
+        let table = flat_sheet::read_cohort_sheet(path)?;
+        let cohort_data = flat_sheet::table_to_cohort(cohort_dto, &table)?;
+        self.record_recent_file(path, RecentFileKind::Excel);
+        Ok(cohort_data)
+    }
 
     pub fn add_hpo_term_to_cohort(
         &mut self,
@@ -427,6 +810,7 @@ impl PhenoboardSingleton {
             Some(onto) => onto.clone(),
             None => { return Err("HPO object not initialized".to_string()); }
         };
+        self.check_project_settings(Some(&cohort_type))?;
         ga4ghphetools::factory::create_new_cohort_data(cohort_type, dto, acronym, hpo)     
     }
 
@@ -448,6 +832,51 @@ impl PhenoboardSingleton {
         Ok(self.get_status())
     }
 
+    /// Remember a file or directory for the recent-files list (failures to save the settings are ignored)
+    pub fn record_recent_file(&mut self, path: &Path, kind: RecentFileKind) {
+        let _ = self.settings.add_recent_file(path, kind);
+    }
+
+    pub fn get_recent_files(&self, kind: Option<RecentFileKind>) -> Vec<RecentFile> {
+        self.settings.get_recent_files(kind)
+    }
+
+    pub fn clear_recent_files(&mut self) -> Result<(), String> {
+        self.settings.clear_recent_files()
+    }
+
+    pub fn get_hpo_release_source(&self) -> HpoReleaseSource {
+        self.settings.get_hpo_release_source().clone()
+    }
+
+    pub fn set_hpo_release_source(&mut self, source: HpoReleaseSource) -> Result<(), String> {
+        self.settings.set_hpo_release_source(source)
+    }
+
+    pub fn get_curator_profiles(&self) -> CuratorProfiles {
+        CuratorProfiles {
+            profiles: self.settings.get_curator_profiles().to_vec(),
+            active_profile: self.settings.get_active_profile().map(|p| p.name.clone()),
+        }
+    }
+
+    /// Add or replace a curator profile
+    pub fn save_curator_profile(&mut self, profile: CuratorProfile) -> Result<StatusDto, String> {
+        self.settings.save_curator_profile(profile)?;
+        Ok(self.get_status())
+    }
+
+    /// Select the curator whose ORCID is used for exports
+    pub fn select_curator_profile(&mut self, name: &str) -> Result<StatusDto, String> {
+        self.settings.select_curator_profile(name)?;
+        Ok(self.get_status())
+    }
+
+    pub fn remove_curator_profile(&mut self, name: &str) -> Result<StatusDto, String> {
+        self.settings.remove_curator_profile(name)?;
+        Ok(self.get_status())
+    }
+
     pub fn get_variant_analysis(
         &self,
         cohort_dto: CohortData
@@ -533,7 +962,11 @@ impl Default for PhenoboardSingleton {
             ontology: None, 
             pt_template_path: None, 
             pt_template_dir_path: None,
-            autocompleter: None,
+            autocompleter: OnceLock::new(),
+            repo_index: None,
+            repo_watcher: None,
+            background_warnings: Arc::new(Mutex::new(Vec::new())),
+            repo_change_listener: Arc::new(Mutex::new(None)),
         }
     }
 }
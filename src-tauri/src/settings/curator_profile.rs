@@ -0,0 +1,51 @@
+//! Biocurator profiles
+//!
+//! Several biocurators may share a workstation. Each of them has a named profile with the ORCID identifier
+//! that is written into the metadata of the phenopackets they export.
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings::orcid::parse_orcid;
+
+
+/// A biocurator who uses this installation
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CuratorProfile {
+    pub name: String,
+    /// ORCID identifier, e.g., 0000-0002-0736-9199
+    pub orcid: String,
+    #[serde(default)]
+    pub affiliation: Option<String>,
+}
+
+impl CuratorProfile {
+    /// Check the name and the ORCID (including its check digit)
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Curator name must not be empty".to_string());
+        }
+        parse_orcid(&self.orcid)
+            .map(|_| ())
+            .map_err(|e| format!("Curator '{}': {}", self.name, e))
+    }
+
+    /// Validate the profile and bring the ORCID into the form `0000-0002-0736-9199`
+    pub fn normalize(&mut self) -> Result<(), String> {
+        self.validate()?;
+        self.name = self.name.trim().to_string();
+        self.orcid = parse_orcid(&self.orcid)?;
+        self.affiliation = self.affiliation.take()
+            .map(|a| a.trim().to_string())
+            .filter(|a| !a.is_empty());
+        Ok(())
+    }
+}
+
+/// The curator profiles and the name of the active one
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CuratorProfiles {
+    pub profiles: Vec<CuratorProfile>,
+    pub active_profile: Option<String>,
+}
@@ -0,0 +1,77 @@
+//! Migration of settings files written by older versions of phenoboard
+//!
+//! The settings file has a `schema_version` entry (files without it have version 1). On load, the file is
+//! parsed as a generic TOML table and upgraded step by step to [`CURRENT_SCHEMA_VERSION`] before it is
+//! deserialized into [`HpoCuratorSettings`](crate::settings::HpoCuratorSettings).
+//!
+//! - Version 1: `hp_json_file`, `user_name`, `orcid_id`
+//! - Version 2: `user_name` and `orcid_id` replaced by the list of curator `profiles` and `active_profile`
+
+use toml::{Table, Value};
+
+pub const CURRENT_SCHEMA_VERSION: i64 = 2;
+
+/// Name of the profile created from a version 1 file without a user name
+const DEFAULT_PROFILE_NAME: &str = "Default";
+
+
+/// Upgrade the settings table to the current schema version.
+///
+/// Returns the version of the file before migration, or an error if the file was written by a newer
+/// version of phenoboard.
+pub fn migrate(table: &mut Table) -> Result<i64, String> {
+    let version = table.get("schema_version").and_then(Value::as_integer).unwrap_or(1);
+    if version > CURRENT_SCHEMA_VERSION {
+        return Err(format!(
+            "settings file has schema version {} but this version of phenoboard supports up to {}",
+            version, CURRENT_SCHEMA_VERSION
+        ));
+    }
+    if version < 2 {
+        migrate_v1_to_v2(table);
+    }
+    table.insert("schema_version".to_string(), Value::Integer(CURRENT_SCHEMA_VERSION));
+    Ok(version)
+}
+
+/// Convert `user_name` and `orcid_id` into a curator profile
+fn migrate_v1_to_v2(table: &mut Table) {
+    let user_name = table.remove("user_name").and_then(|v| v.as_str().map(|s| s.trim().to_string()));
+    let orcid = table.remove("orcid_id").and_then(|v| v.as_str().map(|s| s.trim().to_string()));
+    if let Some(orcid) = orcid.filter(|o| !o.is_empty()) {
+        let name = user_name.filter(|n| !n.is_empty()).unwrap_or_else(|| DEFAULT_PROFILE_NAME.to_string());
+        let mut profile = Table::new();
+        profile.insert("name".to_string(), Value::String(name.clone()));
+        profile.insert("orcid".to_string(), Value::String(orcid));
+        table.insert("profiles".to_string(), Value::Array(vec![Value::Table(profile)]));
+        table.insert("active_profile".to_string(), Value::String(name));
+    }
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_v1() {
+        let mut table: Table = toml::from_str(r#"
+            hp_json_file = "/data/hp.json"
+            user_name = "Jane Doe"
+            orcid_id = "0000-0002-0736-9199"
+        "#).unwrap();
+        assert_eq!(Ok(1), migrate(&mut table));
+        assert_eq!(Some(2), table["schema_version"].as_integer());
+        assert!(!table.contains_key("orcid_id"));
+        assert_eq!(Some("Jane Doe"), table["active_profile"].as_str());
+        assert_eq!(Some("0000-0002-0736-9199"), table["profiles"][0]["orcid"].as_str());
+        assert_eq!(Ok(2), migrate(&mut table));
+        let mut newer: Table = toml::from_str("schema_version = 99").unwrap();
+        assert!(migrate(&mut newer).is_err());
+    }
+}
+
+// endregion: --- Tests
@@ -0,0 +1,482 @@
+//! Module to persist settings including the location of the hp.json file
+//!
+//! The settings are stored in `~/.phenoboard/settings.toml`. The file has a schema version; files written by
+//! older versions are migrated on load (see [`migration`]). Each field is validated on load, and problems are
+//! reported as warnings (shown in the status bar) rather than silently discarded. If the file cannot be parsed,
+//! a copy is kept next to it before the defaults are used.
+
+pub mod curator_profile;
+pub mod migration;
+pub mod orcid;
+pub mod project_settings;
+pub mod recent_files;
+
+use dirs::home_dir;
+use serde::{Serialize, Deserialize};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::directory_manager::layout::RepositoryConfig;
+use crate::hpo::hpo_release::HpoReleaseSource;
+use crate::settings::curator_profile::CuratorProfile;
+use crate::settings::recent_files::{push_recent_file, RecentFile, RecentFileKind};
+
+
+/// Settings to persist between sessions.
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HpoCuratorSettings {
+    #[serde(default)]
+    schema_version: i64,
+    hp_json_file: Option<String>,
+    /// Name of the curator whose ORCID is used for exports
+    #[serde(default)]
+    active_profile: Option<String>,
+    /// Curators who use this installation
+    #[serde(default)]
+    profiles: Vec<CuratorProfile>,
+    /// Phenopacket repositories registered by the user
+    #[serde(default)]
+    repositories: Vec<RepositoryConfig>,
+    /// Name of the selected repository; if None, the repository is derived from the template path
+    #[serde(default)]
+    active_repository: Option<String>,
+    /// Recently used templates, ETL files, Excel files and export directories (most recent first)
+    #[serde(default)]
+    recent_files: Vec<RecentFile>,
+    /// Where HPO releases are downloaded from
+    #[serde(default)]
+    hpo_release_source: HpoReleaseSource,
+    /// Problems found while loading the settings
+    #[serde(skip)]
+    warnings: Vec<String>,
+    /// File from which the settings were loaded and to which they are saved
+    #[serde(skip)]
+    config_file: Option<PathBuf>,
+}
+
+impl HpoCuratorSettings {
+    
+    fn empty() -> Self {
+        Self {
+            schema_version: migration::CURRENT_SCHEMA_VERSION,
+            hp_json_file: None,
+            active_profile: None,
+            profiles: vec![],
+            repositories: vec![],
+            active_repository: None,
+            recent_files: vec![],
+            hpo_release_source: HpoReleaseSource::default(),
+            warnings: vec![],
+            config_file: None,
+        }
+    }
+
+    pub fn set_hp_json_path(&mut self, hp_json: &str) -> Result<(), String> {
+        let path = Path::new(hp_json);
+        if ! path.is_file() {
+            return Err(format!("Did not find file at {hp_json}"));
+        }
+        self.hp_json_file = Some(hp_json.to_string());
+        println!("set_hp_json_path to {}", hp_json);
+        self.save_settings()?;
+        Ok(())
+    }
+
+    pub fn get_hp_json_path(&self) -> Result<String, String> {
+        match &self.hp_json_file {
+            Some (hp_json) => Ok(hp_json.clone()),
+            None => Err(format!("hp.json file not initialized"))
+        }
+    }
+
+    /// ORCID of the active curator profile
+    pub fn get_biocurator_orcid(&self) -> Result<String, String> {
+        match self.get_active_profile() {
+            Some(profile) => Ok(profile.orcid.clone()),
+            None => Err("ORCID not initialized".to_string()),
+        }
+    }
+
+    /// Set the ORCID of the active curator profile (a profile is created if there is none)
+    pub fn save_biocurator_orcid(&mut self, orcid: String) -> Result<(), String> {
+        let profile = match self.get_active_profile() {
+            Some(active) => CuratorProfile { orcid, ..active.clone() },
+            None => CuratorProfile { name: "Default".to_string(), orcid, affiliation: None },
+        };
+        self.save_curator_profile(profile)
+    }
+
+    pub fn get_curator_profiles(&self) -> &[CuratorProfile] {
+        &self.profiles
+    }
+
+    pub fn get_active_profile(&self) -> Option<&CuratorProfile> {
+        let name = self.active_profile.as_ref()?;
+        self.profiles.iter().find(|p| &p.name == name)
+    }
+
+    /// Add a curator profile (or replace the profile with the same name). The profile becomes
+    /// active if no other profile is active.
+    pub fn save_curator_profile(&mut self, mut profile: CuratorProfile) -> Result<(), String> {
+        profile.normalize()?;
+        let name = profile.name.clone();
+        match self.profiles.iter_mut().find(|p| p.name == name) {
+            Some(existing) => *existing = profile,
+            None => self.profiles.push(profile),
+        }
+        if self.get_active_profile().is_none() {
+            self.active_profile = Some(name);
+        }
+        self.save_settings()
+    }
+
+    pub fn select_curator_profile(&mut self, name: &str) -> Result<(), String> {
+        if !self.profiles.iter().any(|p| p.name == name) {
+            return Err(format!("Curator profile '{}' not found", name));
+        }
+        self.active_profile = Some(name.to_string());
+        self.save_settings()
+    }
+
+    pub fn remove_curator_profile(&mut self, name: &str) -> Result<(), String> {
+        let n_before = self.profiles.len();
+        self.profiles.retain(|p| p.name != name);
+        if self.profiles.len() == n_before {
+            return Err(format!("Curator profile '{}' not found", name));
+        }
+        if self.active_profile.as_deref() == Some(name) {
+            self.active_profile = None;
+        }
+        self.save_settings()
+    }
+
+    /// Remember a file or directory that was just used
+    pub fn add_recent_file(&mut self, path: &Path, kind: RecentFileKind) -> Result<(), String> {
+        push_recent_file(&mut self.recent_files, path, kind);
+        self.save_settings()
+    }
+
+    /// Recently used files (all kinds if `kind` is None) that still exist, most recent first
+    pub fn get_recent_files(&self, kind: Option<RecentFileKind>) -> Vec<RecentFile> {
+        self.recent_files
+            .iter()
+            .filter(|f| kind.is_none_or(|k| f.kind == k))
+            .filter(|f| Path::new(&f.path).exists())
+            .cloned()
+            .collect()
+    }
+
+    pub fn clear_recent_files(&mut self) -> Result<(), String> {
+        self.recent_files.clear();
+        self.save_settings()
+    }
+
+    pub fn get_hpo_release_source(&self) -> &HpoReleaseSource {
+        &self.hpo_release_source
+    }
+
+    pub fn set_hpo_release_source(&mut self, source: HpoReleaseSource) -> Result<(), String> {
+        if let HpoReleaseSource::LocalMirror { directory } = &source {
+            if !Path::new(directory).is_dir() {
+                return Err(format!("HPO mirror directory not found: {}", directory));
+            }
+        }
+        self.hpo_release_source = source;
+        self.save_settings()
+    }
+
+    /// Problems found while loading the settings
+    pub fn get_warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    pub fn get_repositories(&self) -> &[RepositoryConfig] {
+        &self.repositories
+    }
+
+    pub fn get_active_repository(&self) -> Option<&RepositoryConfig> {
+        let name = self.active_repository.as_ref()?;
+        self.repositories.iter().find(|r| &r.name == name)
+    }
+
+    /// Add a repository (or replace the repository with the same name) and select it
+    pub fn register_repository(&mut self, config: RepositoryConfig) -> Result<(), String> {
+        config.validate()?;
+        let name = config.name.clone();
+        match self.repositories.iter_mut().find(|r| r.name == name) {
+            Some(existing) => *existing = config,
+            None => self.repositories.push(config),
+        }
+        self.active_repository = Some(name);
+        self.save_settings()
+    }
+
+    /// Select one of the registered repositories (None: derive the repository from the template path)
+    pub fn select_repository(&mut self, name: Option<String>) -> Result<(), String> {
+        if let Some(name) = &name {
+            if !self.repositories.iter().any(|r| &r.name == name) {
+                return Err(format!("Repository '{}' is not registered", name));
+            }
+        }
+        self.active_repository = name;
+        self.save_settings()
+    }
+
+    pub fn remove_repository(&mut self, name: &str) -> Result<(), String> {
+        let n_before = self.repositories.len();
+        self.repositories.retain(|r| r.name != name);
+        if self.repositories.len() == n_before {
+            return Err(format!("Repository '{}' is not registered", name));
+        }
+        if self.active_repository.as_deref() == Some(name) {
+            self.active_repository = None;
+        }
+        self.save_settings()
+    }
+
+
+    pub fn load_settings() -> HpoCuratorSettings {
+        let path = match ensure_config_directory().and_then(|_| get_config_file()) {
+            Ok(path) => path,
+            Err(e) => {
+                let mut settings = HpoCuratorSettings::empty();
+                settings.warnings.push(format!("Settings cannot be saved: {}", e));
+                return settings;
+            }
+        };
+        Self::load_from_path(&path)
+    }
+
+    /// Load, migrate and validate the settings file at `path` (defaults are written if it does not exist)
+    fn load_from_path(path: &Path) -> HpoCuratorSettings {
+        if !path.exists() {
+            // Write default settings if file doesn't exist
+            let mut default_settings = HpoCuratorSettings::empty();
+            default_settings.config_file = Some(path.to_path_buf());
+            if let Err(e) = default_settings.save_settings() {
+                default_settings.warnings.push(e);
+            }
+            return default_settings;
+        }
+        let (mut settings, original_version) = match Self::parse_settings_file(path) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                let mut settings = HpoCuratorSettings::empty();
+                settings.config_file = Some(path.to_path_buf());
+                match backup_settings_file(path, "corrupt") {
+                    Ok(backup) => settings.warnings.push(format!(
+                        "Could not load {} ({}). Using defaults; the file was saved as {}",
+                        path.display(), e, backup.display()
+                    )),
+                    Err(backup_error) => settings.warnings.push(format!(
+                        "Could not load {} ({}). Using defaults; the file could not be backed up: {}",
+                        path.display(), e, backup_error
+                    )),
+                }
+                return settings;
+            }
+        };
+        settings.config_file = Some(path.to_path_buf());
+        let n_warnings = settings.validate();
+        let migrated = original_version < migration::CURRENT_SCHEMA_VERSION;
+        if migrated || n_warnings > 0 {
+            // keep the original file, since migration or validation may have removed entries
+            let suffix = if migrated { format!("v{}", original_version) } else { "invalid".to_string() };
+            if let Err(e) = backup_settings_file(path, &suffix).and_then(|_| settings.save_settings()) {
+                settings.warnings.push(e);
+            }
+        }
+        settings
+    }
+
+    /// Parse and migrate the settings file; returns the settings and the schema version of the file
+    fn parse_settings_file(path: &Path) -> Result<(HpoCuratorSettings, i64), String> {
+        let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let mut table: toml::Table = toml::from_str(&contents).map_err(|e| e.to_string())?;
+        let original_version = migration::migrate(&mut table)?;
+        let settings: HpoCuratorSettings = toml::Value::Table(table)
+            .try_into()
+            .map_err(|e: toml::de::Error| e.to_string())?;
+        Ok((settings, original_version))
+    }
+
+    /// Check each field and remove invalid entries; returns the number of problems found
+    fn validate(&mut self) -> usize {
+        let mut warnings: Vec<String> = Vec::new();
+        let mut profiles: Vec<CuratorProfile> = Vec::new();
+        for mut profile in std::mem::take(&mut self.profiles) {
+            if let Err(e) = profile.normalize() {
+                warnings.push(format!("Removed curator profile: {}", e));
+            } else if profiles.iter().any(|p| p.name == profile.name) {
+                warnings.push(format!("Removed duplicate curator profile '{}'", profile.name));
+            } else {
+                profiles.push(profile);
+            }
+        }
+        self.profiles = profiles;
+        if let Some(name) = &self.active_profile {
+            if !self.profiles.iter().any(|p| &p.name == name) {
+                warnings.push(format!("Active curator profile '{}' not found", name));
+                self.active_profile = None;
+            }
+        }
+        if let Some(name) = &self.active_repository {
+            if !self.repositories.iter().any(|r| &r.name == name) {
+                warnings.push(format!("Active repository '{}' not found", name));
+                self.active_repository = None;
+            }
+        }
+        let n_problems = warnings.len();
+        // A missing hp.json file and repositories on unmounted drives are kept; they are only reported
+        if let Some(hp_json) = &self.hp_json_file {
+            if !Path::new(hp_json).is_file() {
+                self.warnings.push(format!("hp.json file not found at {}; please select it again if it was moved", hp_json));
+            }
+        }
+        for repository in &self.repositories {
+            if let Err(e) = repository.validate() {
+                self.warnings.push(format!("Repository '{}': {}", repository.name, e));
+            }
+        }
+        self.warnings.extend(warnings);
+        n_problems
+    }
+
+    pub fn save_settings(&self) -> Result<(), String> {
+        let config_file = match &self.config_file {
+            Some(path) => path.clone(),
+            None => get_config_file()?,
+        };
+        let toml_string = toml::to_string_pretty(&self)
+            .map_err(|e| format!("Could not serialize settings: {}", e))?;
+        let mut file = File::create(config_file)
+            .map_err(|e| format!("Could not create settings file: {}", e))?;
+
+        file.write_all(toml_string.as_bytes())
+            .map_err(|e| format!("Could not write to settings file: {}", e))?;
+        Ok(())
+
+    }
+
+
+}
+
+
+
+fn get_config_path() -> Result<PathBuf, String> {
+    match home_dir() {
+        Some(mut home) => {
+            home.push(".phenoboard");
+            Ok(home)
+        }
+        None => Err(format!("Could not determine home directory"))
+    }
+}
+
+/// Directory with caches that can be rebuilt at any time (`~/.phenoboard/cache`)
+pub fn get_cache_dir() -> Result<PathBuf, String> {
+    Ok(get_config_path()?.join("cache"))
+}
+
+/// Directory with the downloaded HPO releases (`~/.phenoboard/hpo`)
+pub fn get_hpo_release_dir() -> Result<PathBuf, String> {
+    Ok(get_config_path()?.join("hpo"))
+}
+
+fn get_config_file() -> Result<PathBuf, String> {
+    let mut config_file = get_config_path()?;
+    config_file.push("settings.toml"); // ~/.phenoboard/settings.toml
+    Ok(config_file)
+}
+
+fn ensure_config_directory() -> Result<(), String> {
+    let config_dir = get_config_path()?;
+    if !config_dir.exists() {
+        fs::create_dir_all(&config_dir)
+            .map_err(|e| format!("Failed to create config directory {}: {}", config_dir.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Copy the settings file to `settings.toml.{suffix}-{seconds since epoch}` and return the path of the copy
+fn backup_settings_file(path: &Path, suffix: &str) -> Result<PathBuf, String> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let file_name = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+    let backup = path.with_file_name(format!("{}.{}-{}", file_name, suffix, timestamp));
+    fs::copy(path, &backup)
+        .map_err(|e| format!("Could not back up {}: {}", path.display(), e))?;
+    Ok(backup)
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn backups(dir: &Path) -> Vec<String> {
+        fs::read_dir(dir).unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|f| f != "settings.toml")
+            .collect()
+    }
+
+    #[test]
+    fn test_load_and_migrate_v1() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("settings.toml");
+        fs::write(&path, "user_name = \"Jane Doe\"\norcid_id = \"0000-0002-0736-9199\"\n").unwrap();
+        let settings = HpoCuratorSettings::load_from_path(&path);
+        assert_eq!(Ok("0000-0002-0736-9199".to_string()), settings.get_biocurator_orcid());
+        assert_eq!("Jane Doe", settings.get_active_profile().unwrap().name);
+        assert!(settings.get_warnings().is_empty());
+        assert!(fs::read_to_string(&path).unwrap().contains("schema_version = 2"));
+        assert_eq!(1, backups(dir.path()).len());
+    }
+
+    #[test]
+    fn test_corrupt_file_is_backed_up() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("settings.toml");
+        fs::write(&path, "hp_json_file = [unclosed").unwrap();
+        let settings = HpoCuratorSettings::load_from_path(&path);
+        assert_eq!(1, settings.get_warnings().len());
+        let backups = backups(dir.path());
+        assert!(backups[0].starts_with("settings.toml.corrupt-"));
+        assert_eq!("hp_json_file = [unclosed", fs::read_to_string(dir.path().join(&backups[0])).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_fields_are_reported() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("settings.toml");
+        fs::write(&path, r#"
+schema_version = 2
+hp_json_file = "/does/not/exist/hp.json"
+active_profile = "Nobody"
+
+[[profiles]]
+name = "Jane Doe"
+orcid = "not-an-orcid"
+"#).unwrap();
+        let mut settings = HpoCuratorSettings::load_from_path(&path);
+        assert_eq!(3, settings.get_warnings().len());
+        assert_eq!(Ok("/does/not/exist/hp.json".to_string()), settings.get_hp_json_path());
+        assert!(settings.get_curator_profiles().is_empty());
+        settings.save_biocurator_orcid("https://orcid.org/0000-0002-0736-9199".to_string()).unwrap();
+        assert_eq!("Default", settings.get_active_profile().unwrap().name);
+        assert_eq!(Ok("0000-0002-0736-9199".to_string()), settings.get_biocurator_orcid());
+        assert!(settings.save_biocurator_orcid("0000-0002-0736-9198".to_string()).is_err());
+        assert_eq!(Ok("0000-0002-0736-9199".to_string()), settings.get_biocurator_orcid());
+    }
+}
+
+// endregion: --- Tests
+
+
@@ -0,0 +1,84 @@
+//! Parse and validate ORCID identifiers
+//!
+//! The ORCID of the biocurator is written into the metadata of every exported phenopacket and HPOA file.
+//! Users paste it in different forms (e.g., `https://orcid.org/0000-0002-0736-9199`), so we accept the common
+//! variants, verify the ISO 7064 MOD 11-2 check digit, and always store the bare form `0000-0002-0736-9199`.
+
+const ORCID_URL_PREFIXES: [&str; 4] = ["https://orcid.org/", "http://orcid.org/", "orcid.org/", "orcid:"];
+
+
+/// Parse an ORCID identifier and return it in the normalized form `0000-0002-0736-9199`.
+///
+/// Accepted are the bare identifier (with or without hyphens, with a lower-case `x` as check digit), the
+/// `https://orcid.org/` URL and the `ORCID:` prefix.
+pub fn parse_orcid(input: &str) -> Result<String, String> {
+    let trimmed = input.trim();
+    let lower = trimmed.to_ascii_lowercase();
+    let bare = ORCID_URL_PREFIXES
+        .iter()
+        .find(|prefix| lower.starts_with(*prefix))
+        .map(|prefix| &trimmed[prefix.len()..])
+        .unwrap_or(trimmed)
+        .trim_end_matches('/');
+    let chars: Vec<char> = bare.chars().filter(|c| *c != '-').map(|c| c.to_ascii_uppercase()).collect();
+    let well_formed = chars.len() == 16
+        && chars[..15].iter().all(|c| c.is_ascii_digit())
+        && (chars[15].is_ascii_digit() || chars[15] == 'X')
+        && (bare.len() == 16 || is_hyphenated(bare));
+    if !well_formed {
+        return Err(format!(
+            "Invalid ORCID '{}': expected 16 digits such as 0000-0002-0736-9199 or https://orcid.org/0000-0002-0736-9199",
+            trimmed
+        ));
+    }
+    let expected = check_digit(&chars[..15]);
+    if chars[15] != expected {
+        return Err(format!(
+            "Invalid ORCID '{}': the check digit is '{}' but should be '{}' (please check for typos)",
+            trimmed, chars[15], expected
+        ));
+    }
+    let digits: String = chars.iter().collect();
+    Ok(format!("{}-{}-{}-{}", &digits[0..4], &digits[4..8], &digits[8..12], &digits[12..16]))
+}
+
+/// Hyphens are allowed only between the four blocks of four characters
+fn is_hyphenated(bare: &str) -> bool {
+    let blocks: Vec<&str> = bare.split('-').collect();
+    blocks.len() == 4 && blocks.iter().all(|b| b.len() == 4)
+}
+
+/// ISO 7064 MOD 11-2 check digit of the first 15 digits
+fn check_digit(base_digits: &[char]) -> char {
+    let total = base_digits
+        .iter()
+        .filter_map(|c| c.to_digit(10))
+        .fold(0, |total, digit| (total + digit) * 2);
+    let result = (12 - total % 11) % 11;
+    if result == 10 { 'X' } else { char::from_digit(result, 10).unwrap_or('0') }
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_orcid() {
+        let expected = Ok("0000-0002-0736-9199".to_string());
+        assert_eq!(expected, parse_orcid("0000-0002-0736-9199"));
+        assert_eq!(expected, parse_orcid(" https://orcid.org/0000-0002-0736-9199/ "));
+        assert_eq!(expected, parse_orcid("ORCID:0000000207369199"));
+        // check digit X, given in lower case
+        assert_eq!(Ok("0000-0002-1694-233X".to_string()), parse_orcid("http://orcid.org/0000-0002-1694-233x"));
+        assert!(parse_orcid("0000-0002-0736-9198").is_err());
+        assert!(parse_orcid("0000-0002-0736-919").is_err());
+        assert!(parse_orcid("00-00-0002-0736-9199").is_err());
+        assert!(parse_orcid("Jane Doe").is_err());
+    }
+}
+
+// endregion: --- Tests
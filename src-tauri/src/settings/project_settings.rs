@@ -0,0 +1,186 @@
+//! Project settings shared by the curators of a repository
+//!
+//! A repository may contain a `.phenoboard.toml` file at its root (or in any directory above the cohort) with
+//! the conventions of the team, for instance
+//!
+//! ```toml
+//! required_hpo_version = "2025-05-06"
+//! allowed_cohort_types = ["mendelian", "melded"]
+//! output_dir = "hpoa"
+//! transcript_policy = "mane_select"
+//! ```
+//!
+//! The file is optional. Where a value is given, it takes precedence over the user settings and the choices
+//! made in the GUI, so that every curator exports with the same parameters.
+
+use std::{fs, path::{Path, PathBuf}};
+
+use serde::{Deserialize, Serialize};
+
+pub const PROJECT_SETTINGS_FILE: &str = ".phenoboard.toml";
+
+
+/// Which transcripts may be used for HGVS variants
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscriptPolicy {
+    /// The MANE Select transcript of the gene
+    ManeSelect,
+    /// Any transcript chosen by the curator
+    Any,
+}
+
+/// Conventions of a repository (the file uses snake_case keys; the frontend receives camelCase)
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase"), deny_unknown_fields)]
+pub struct ProjectSettings {
+    /// HPO release that must be loaded for exports, e.g., `2025-05-06`
+    #[serde(default)]
+    pub required_hpo_version: Option<String>,
+    /// Cohort types that may be created and exported (all if empty)
+    #[serde(default)]
+    pub allowed_cohort_types: Vec<String>,
+    /// Output directory for exports, relative to the directory of the project settings file
+    #[serde(default)]
+    pub output_dir: Option<String>,
+    /// With `mane_select`, HGVS variants must use the MANE Select transcript of their gene
+    #[serde(default)]
+    pub transcript_policy: Option<TranscriptPolicy>,
+    /// Path of the file from which the settings were read
+    #[serde(skip_deserializing)]
+    pub path: String,
+}
+
+impl ProjectSettings {
+    /// Search for the project settings file in `start_dir` and its parents. The search stops at the root
+    /// of the git repository (the first directory with a `.git` entry).
+    pub fn find(start_dir: &Path) -> Option<PathBuf> {
+        for dir in start_dir.ancestors() {
+            let candidate = dir.join(PROJECT_SETTINGS_FILE);
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+            if dir.join(".git").exists() {
+                break;
+            }
+        }
+        None
+    }
+
+    /// Find and read the project settings for `start_dir`; returns None if there is no project settings file
+    pub fn discover(start_dir: &Path) -> Result<Option<ProjectSettings>, String> {
+        match Self::find(start_dir) {
+            Some(path) => Self::load(&path).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<ProjectSettings, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        let mut settings: ProjectSettings = toml::from_str(&contents)
+            .map_err(|e| format!("Invalid project settings {}: {}", path.display(), e))?;
+        settings.path = path.to_string_lossy().to_string();
+        Ok(settings)
+    }
+
+    /// The output directory (resolved relative to the project settings file), if one was given
+    pub fn get_output_dir(&self) -> Option<PathBuf> {
+        let output_dir = self.output_dir.as_ref()?;
+        let project_dir = Path::new(&self.path).parent().unwrap_or(Path::new(""));
+        Some(project_dir.join(output_dir))
+    }
+
+    /// Check that the loaded HPO is the release required by the project, e.g., `2025-05-06`
+    /// also matches `http://purl.obolibrary.org/obo/hp/releases/2025-05-06/hp.json`
+    pub fn check_hpo_version(&self, hpo_version: &str) -> Result<(), String> {
+        let Some(required) = &self.required_hpo_version else {
+            return Ok(());
+        };
+        let strip = |v: &str| v.trim().trim_start_matches('v').to_string();
+        let loaded = strip(hpo_version);
+        let required = strip(required);
+        if loaded == required || loaded.split('/').any(|segment| strip(segment) == required) {
+            Ok(())
+        } else {
+            Err(format!(
+                "The project settings ({}) require HPO version {}, but version {} is loaded",
+                self.path, required, hpo_version
+            ))
+        }
+    }
+
+    /// Check a cohort type (`mendelian`, `melded`, `digenic`) against the allowed types
+    pub fn check_cohort_type(&self, cohort_type: &str) -> Result<(), String> {
+        if self.allowed_cohort_types.is_empty()
+            || self.allowed_cohort_types.iter().any(|t| t.eq_ignore_ascii_case(cohort_type))
+        {
+            Ok(())
+        } else {
+            Err(format!(
+                "The project settings ({}) do not allow {} cohorts (allowed: {})",
+                self.path, cohort_type, self.allowed_cohort_types.join(", ")
+            ))
+        }
+    }
+
+    /// Check the transcript of an HGVS variant against the transcript policy. With `mane_select`, the
+    /// accession must be that of the MANE Select transcript of the gene (e.g., `NM_004333.6`); the version
+    /// is not compared, because MANE releases update it.
+    pub fn check_transcript(&self, symbol: &str, transcript: &str, mane_select: &str) -> Result<(), String> {
+        if self.transcript_policy != Some(TranscriptPolicy::ManeSelect) {
+            return Ok(());
+        }
+        let accession = |t: &str| t.trim().split('.').next().unwrap_or_default().to_string();
+        if accession(transcript) == accession(mane_select) {
+            Ok(())
+        } else {
+            Err(format!(
+                "The project settings ({}) require the MANE Select transcript of {} ({}), but {} was used",
+                self.path, symbol, mane_select, transcript
+            ))
+        }
+    }
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_discover_project_settings() {
+        let tmp = TempDir::new().unwrap();
+        let base = tmp.path();
+        let cohort_dir = base.join("notebooks").join("FBN1").join("input");
+        fs::create_dir_all(&cohort_dir).unwrap();
+        assert_eq!(Ok(None), ProjectSettings::discover(&cohort_dir));
+        fs::write(base.join(PROJECT_SETTINGS_FILE), r#"
+required_hpo_version = "2025-05-06"
+allowed_cohort_types = ["mendelian"]
+output_dir = "hpoa"
+transcript_policy = "mane_select"
+"#).unwrap();
+        let settings = ProjectSettings::discover(&cohort_dir).unwrap().unwrap();
+        assert_eq!(Some(TranscriptPolicy::ManeSelect), settings.transcript_policy);
+        assert_eq!(Some(base.join("hpoa")), settings.get_output_dir());
+        assert!(settings.check_hpo_version("2025-05-06").is_ok());
+        assert!(settings.check_hpo_version("http://purl.obolibrary.org/obo/hp/releases/2025-05-06/hp.json").is_ok());
+        assert!(settings.check_hpo_version("2024-04-26").is_err());
+        assert!(settings.check_cohort_type("mendelian").is_ok());
+        assert!(settings.check_cohort_type("melded").is_err());
+        assert!(settings.check_transcript("BRAF", "NM_004333.4", "NM_004333.6").is_ok());
+        assert!(settings.check_transcript("BRAF", "NM_001354609.2", "NM_004333.6").is_err());
+        // the search stops at the root of the git repository
+        fs::create_dir_all(base.join("notebooks").join(".git")).unwrap();
+        assert_eq!(None, ProjectSettings::find(&cohort_dir));
+        fs::write(base.join("notebooks").join(PROJECT_SETTINGS_FILE), "unknown_key = 1").unwrap();
+        assert!(ProjectSettings::discover(&cohort_dir).is_err());
+    }
+}
+
+// endregion: --- Tests
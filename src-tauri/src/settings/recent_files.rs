@@ -0,0 +1,78 @@
+//! Recently used files and directories
+//!
+//! The list is stored in the settings so that the file dialogs open where the curator last worked and
+//! recent cohorts can be reopened from the home page.
+
+use std::{path::Path, time::{SystemTime, UNIX_EPOCH}};
+
+use serde::{Deserialize, Serialize};
+
+/// Number of entries kept for each kind of file
+pub const MAX_RECENT_FILES_PER_KIND: usize = 10;
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RecentFileKind {
+    /// Cohort template (`*_individuals.json`)
+    Template,
+    /// External template JSON (intermediate file of the ETL of an external table)
+    EtlJson,
+    /// Excel file with an external table (e.g., supplemental material)
+    Excel,
+    /// Directory to which phenopackets or HPOA files were exported
+    ExportDir,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentFile {
+    pub path: String,
+    pub kind: RecentFileKind,
+    /// Seconds since the Unix epoch
+    pub last_used: u64,
+}
+
+/// Put `path` at the front of the list (removing an older entry for the same file) and drop the oldest
+/// entries of this kind beyond [`MAX_RECENT_FILES_PER_KIND`]
+pub fn push_recent_file(recent_files: &mut Vec<RecentFile>, path: &Path, kind: RecentFileKind) {
+    let path = path.to_string_lossy().to_string();
+    recent_files.retain(|f| !(f.kind == kind && f.path == path));
+    let last_used = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    recent_files.insert(0, RecentFile { path, kind, last_used });
+    let mut n_of_kind = 0;
+    recent_files.retain(|f| {
+        if f.kind != kind {
+            return true;
+        }
+        n_of_kind += 1;
+        n_of_kind <= MAX_RECENT_FILES_PER_KIND
+    });
+}
+
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_recent_file() {
+        let mut recent_files = Vec::new();
+        for i in 0..12 {
+            push_recent_file(&mut recent_files, Path::new(&format!("/data/cohort{}.json", i)), RecentFileKind::Template);
+        }
+        push_recent_file(&mut recent_files, Path::new("/data/out"), RecentFileKind::ExportDir);
+        push_recent_file(&mut recent_files, Path::new("/data/cohort5.json"), RecentFileKind::Template);
+        assert_eq!(MAX_RECENT_FILES_PER_KIND + 1, recent_files.len());
+        assert_eq!("/data/cohort5.json", recent_files[0].path);
+        assert_eq!(1, recent_files.iter().filter(|f| f.path == "/data/cohort5.json").count());
+        assert_eq!(RecentFileKind::ExportDir, recent_files[1].kind);
+        // the oldest templates were dropped
+        assert!(!recent_files.iter().any(|f| f.path == "/data/cohort1.json"));
+    }
+}
+
+// endregion: --- Tests
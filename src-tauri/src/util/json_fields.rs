@@ -0,0 +1,46 @@
+//! Read fields of cohort templates and phenopackets that we handle as JSON
+//!
+//! The repository index reads templates and phenopackets written by different versions of ga4ghphetools,
+//! so we do not deserialize them into the DTOs and read the fields we need with [`str_field`] instead.
+//! Fields without which the output would be wrong (identifiers) are read with [`required_str`], which reports
+//! a missing field instead of defaulting to an empty string.
+
+use serde_json::Value;
+
+
+/// The trimmed string value of `key`, or an empty string if the field is missing or not a string
+pub fn str_field(json: &Value, key: &str) -> String {
+    json[key].as_str().unwrap_or_default().trim().to_string()
+}
+
+/// The trimmed string value of `key`; an error if the field is missing, empty, or not a string
+///
+/// `context` describes the object in the error message, e.g., `"HPO column 3"`.
+pub fn required_str(json: &Value, key: &str, context: &str) -> Result<String, String> {
+    match json[key].as_str().map(str::trim) {
+        Some(value) if !value.is_empty() => Ok(value.to_string()),
+        Some(_) => Err(format!("{}: field '{}' is empty", context, key)),
+        None => Err(format!("{}: missing field '{}'", context, key)),
+    }
+}
+
+
+// region:    --- Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fields() {
+        let json = serde_json::json!({"hpoId": " HP:0001250 ", "hpoLabel": "", "count": 2});
+        assert_eq!("HP:0001250", str_field(&json, "hpoId"));
+        assert_eq!("", str_field(&json, "hpoLabel"));
+        assert_eq!("", str_field(&json, "count"));
+        assert_eq!("HP:0001250", required_str(&json, "hpoId", "column 1").unwrap());
+        assert_eq!("column 1: field 'hpoLabel' is empty", required_str(&json, "hpoLabel", "column 1").unwrap_err());
+        assert_eq!("column 1: missing field 'comment'", required_str(&json, "comment", "column 1").unwrap_err());
+    }
+}
+
+// endregion: --- Tests
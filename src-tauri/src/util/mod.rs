@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 
+pub mod json_fields;
 pub mod pubmed_retrieval;
 mod hgnc_rest;
 
@@ -10,6 +11,13 @@ pub struct HgncBundle {
     mane_select: String,
 }
 
+impl HgncBundle {
+    /// RefSeq accession of the MANE Select transcript, e.g., `NM_004333.6`
+    pub fn mane_select(&self) -> &str {
+        &self.mane_select
+    }
+}
+
 pub async fn fetch_hgnc_data(symbol: &str) -> Result<HgncBundle, String> {
     let bundle = hgnc_rest::fetch_gene_data(symbol)
         .await
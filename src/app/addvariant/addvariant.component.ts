@@ -25,6 +25,7 @@ import {
   IntergenicHgvsVariant,
 } from '../../../libs/ui/src/lib/models/variant_dto';
 import { CohortDtoService } from '../services/cohort_dto_service';
+import { AppStatusService } from '../services/app_status_service';
 import { GeneTranscriptData } from '../../../libs/ui/src/lib/models/cohort_dto';
 
 export enum VariantKind {
@@ -58,6 +59,7 @@ export class AddVariantComponent implements OnInit {
   private configService = inject(ConfigService);
   private cohortService = inject(CohortDtoService);
   private cdr = inject(ChangeDetectorRef);
+  private statusService = inject(AppStatusService);
 
   data = input.required<VariantKind>();
   result = output<VariantDto | undefined>();
@@ -245,6 +247,8 @@ export class AddVariantComponent implements OnInit {
         this.variantValidated = true;
         cohortDto.hgvsVariants[hgvs.variantKey] = hgvs;
         this.cdr.markForCheck();
+        // e.g., the MANE Select check was skipped because HGNC could not be reached
+        this.statusService.refreshAndShowWarnings();
       })
       .catch((error) => {
         alert(String(error));
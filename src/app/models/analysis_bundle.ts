@@ -0,0 +1,11 @@
+/** Result of the export of a GPSEA/PheVal analysis bundle */
+export interface AnalysisBundleSummary {
+  bundleDir: string;
+  nPhenopackets: number;
+  /** Individuals with at least one SNV with genomic coordinates */
+  nVcfFiles: number;
+  /** Path of gpsea/cohort_config.json */
+  gpseaConfig: string;
+  phevalCorpusDir: string;
+  warnings: string[];
+}
@@ -0,0 +1,18 @@
+/** Result of regenerating the phenopackets of one cohort template */
+export interface CohortExportSummary {
+  cohortName: string;
+  templateFile: string;
+  /** Phenopackets that are new or changed (would be written in dry-run mode) */
+  written: string[];
+  unchanged: string[];
+  error?: string | null;
+}
+
+/** Summary of the batch re-export of all phenopackets in phenopacket-store */
+export interface BatchExportReport {
+  dryRun: boolean;
+  cohorts: CohortExportSummary[];
+  nWritten: number;
+  nUnchanged: number;
+  nFailedCohorts: number;
+}
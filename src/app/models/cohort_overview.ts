@@ -0,0 +1,23 @@
+/** One row of the overview table of the phenopacket-store repository (one per cohort directory) */
+export interface CohortOverviewRow {
+  cohortName: string;
+  templateFile?: string | null;
+  cohortType: string;
+  geneSymbols: string[];
+  diseaseIds: string[];
+  nRows: number;
+  nPhenopackets: number;
+  hpoVersion: string;
+  /** Seconds since the Unix epoch */
+  lastModified?: number | null;
+  curatorOrcids: string[];
+  nWarnings: number;
+}
+
+/** Payload of the "ppkt-store-changed" event emitted by the backend when cohort directories change on disk */
+export interface RepositoryChangeEvent {
+  changedCohorts: string[];
+  removedCohorts: string[];
+  /** Problems with the file system notifications or with rescanning a cohort */
+  warnings: string[];
+}
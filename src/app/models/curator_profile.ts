@@ -0,0 +1,13 @@
+/** A biocurator who uses this installation */
+export interface CuratorProfile {
+  name: string;
+  /** ORCID identifier, e.g. 0000-0002-0736-9199 */
+  orcid: string;
+  affiliation?: string | null;
+}
+
+export interface CuratorProfiles {
+  profiles: CuratorProfile[];
+  /** Name of the curator whose ORCID is used for exports */
+  activeProfile?: string | null;
+}
@@ -0,0 +1,22 @@
+/** Uncommitted changes of one cohort directory (paths relative to the cohort directory) */
+export interface CohortGitStatus {
+  cohortName: string;
+  modified: string[];
+  untracked: string[];
+  deleted: string[];
+}
+
+/** Git status of phenopacket-store */
+export interface RepositoryGitStatus {
+  isGitRepository: boolean;
+  branch?: string | null;
+  /** Only cohorts with uncommitted changes */
+  cohorts: CohortGitStatus[];
+}
+
+/** Result of committing the changes of a cohort */
+export interface CohortCommit {
+  commitId: string;
+  message: string;
+  files: string[];
+}
@@ -0,0 +1,23 @@
+/** Where HPO releases are downloaded from */
+export type HpoReleaseSource =
+  | { type: 'github'; apiUrl: string }
+  | { type: 'purl'; baseUrl: string }
+  | { type: 'localMirror'; directory: string };
+
+/** An HPO release that is available from the source */
+export interface HpoRelease {
+  /** e.g. 2025-05-06 ("latest" if the version is only known after the download) */
+  version: string;
+  downloadUrl: string;
+  size?: number | null;
+  sha256?: string | null;
+  published?: string | null;
+  installed: boolean;
+}
+
+/** An HPO release in ~/.phenoboard/hpo */
+export interface InstalledHpoRelease {
+  version: string;
+  path: string;
+  size: number;
+}
@@ -0,0 +1,42 @@
+/** One row of an HPOA "small file" */
+export interface HpoaRow {
+  diseaseId: string;
+  diseaseName: string;
+  phenotypeId: string;
+  phenotypeName: string;
+  onsetId: string;
+  onsetName: string;
+  /** e.g. 3/5 */
+  frequency: string;
+  sex: string;
+  /** NOT or empty */
+  negation: string;
+  /** HPO ids separated by semicolons */
+  modifier: string;
+  description: string;
+  publication: string;
+  evidence: string;
+  biocuration: string;
+}
+
+/** The rows of the HPOA file of one disease */
+export interface HpoaFilePreview {
+  diseaseId: string;
+  diseaseName: string;
+  /** e.g. OMIM-154700.tab */
+  fileName: string;
+  rows: HpoaRow[];
+  /** Validation problems; the file cannot be exported if there are any */
+  errors: string[];
+}
+
+export interface HpoaPreview {
+  files: HpoaFilePreview[];
+}
+
+/** Result of writing the HPOA files of a cohort */
+export interface HpoaExportSummary {
+  outDir: string;
+  files: string[];
+  nRows: number;
+}
@@ -0,0 +1,9 @@
+/** Result of the export of the Phenopacket v2 Cohort and Family messages of a cohort */
+export interface PhenopacketCollectionSummary {
+  outDir: string;
+  cohortFile: string;
+  nMembers: number;
+  familyFiles: string[];
+  /** e.g. families without a flagged proband */
+  warnings: string[];
+}
@@ -0,0 +1,16 @@
+/** Which transcripts may be used for HGVS variants */
+export type TranscriptPolicy = 'mane_select' | 'any';
+
+/** Conventions of a repository, read from its .phenoboard.toml file */
+export interface ProjectSettings {
+  /** HPO release that must be loaded for exports, e.g. 2025-05-06 */
+  requiredHpoVersion?: string | null;
+  /** Cohort types that may be created and exported (all if empty) */
+  allowedCohortTypes: string[];
+  /** Output directory for exports, relative to the project settings file */
+  outputDir?: string | null;
+  /** With 'mane_select', HGVS variants must use the MANE Select transcript of their gene */
+  transcriptPolicy?: TranscriptPolicy | null;
+  /** Path of the project settings file */
+  path: string;
+}
@@ -0,0 +1,9 @@
+export type RecentFileKind = 'template' | 'etlJson' | 'excel' | 'exportDir';
+
+/** A recently used file or directory (see the recent-files list in the settings) */
+export interface RecentFile {
+  path: string;
+  kind: RecentFileKind;
+  /** Seconds since the Unix epoch */
+  lastUsed: number;
+}
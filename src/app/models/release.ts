@@ -0,0 +1,21 @@
+/** A phenopacket that failed the structural checks and was not included in the release */
+export interface InvalidPhenopacket {
+  cohortName: string;
+  fileName: string;
+  errors: string[];
+}
+
+/** Summary of a phenopacket-store release (zip, TSV manifest and summary JSON) */
+export interface ReleaseSummary {
+  version: string;
+  archivePath: string;
+  manifestPath: string;
+  summaryPath: string;
+  nCohorts: number;
+  nPhenopackets: number;
+  nDiseases: number;
+  nGenes: number;
+  /** The checks done before a phenopacket was added (structural only, no schema or HPO validation) */
+  checks: string;
+  invalid: InvalidPhenopacket[];
+}
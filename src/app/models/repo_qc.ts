@@ -12,3 +12,23 @@ export interface RepoQc {
   phenopacketCount: number;
   errors: QcReport[];
 }
+
+/** Comparison of the template rows of one cohort with the files in its phenopackets directory */
+export interface PhenopacketQcReport {
+  cohortName: string;
+  /** Phenopackets without a corresponding template row */
+  orphaned: string[];
+  /** Template rows without a phenopacket */
+  missing: string[];
+  /** Phenopackets older than the template */
+  stale: string[];
+  /** Phenopackets whose content differs from a fresh export */
+  differing: string[];
+  errors: string[];
+}
+
+export interface RepositoryPhenopacketQc {
+  nCohortsChecked: number;
+  /** Only cohorts with findings */
+  cohorts: PhenopacketQcReport[];
+}
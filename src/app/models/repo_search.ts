@@ -0,0 +1,30 @@
+/** Structured query over all cohorts of phenopacket-store; all criteria that are set must match */
+export interface RepoSearchQuery {
+  hpoId?: string | null;
+  includeDescendants?: boolean;
+  termStatus?: 'any' | 'observed' | 'excluded';
+  gene?: string | null;
+  variant?: string | null;
+  diseaseId?: string | null;
+  pmid?: string | null;
+  individualId?: string | null;
+  curatorOrcid?: string | null;
+  includePhenopackets?: boolean;
+}
+
+export type RecordSource = 'templateRow' | 'phenopacket';
+
+export interface RepoSearchHit {
+  cohortName: string;
+  fileName: string;
+  source?: RecordSource | null;
+  rowIndex?: number | null;
+  individualId: string;
+  pmids: string[];
+  matchedTerms: string[];
+}
+
+export interface RepoSearchResult {
+  cohorts: string[];
+  hits: RepoSearchHit[];
+}
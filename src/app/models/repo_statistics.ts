@@ -0,0 +1,36 @@
+/** Distribution of the number of observed HPO terms per individual */
+export interface TermsPerIndividual {
+  mean: number;
+  median: number;
+  max: number;
+  /** Number of observed terms → number of individuals */
+  histogram: Record<number, number>;
+}
+
+export interface VariantTypeCounts {
+  hgvs: number;
+  structural: number;
+  intergenic: number;
+}
+
+/** Cumulative size of the repository at the end of a year */
+export interface GrowthPoint {
+  year: number;
+  nCohorts: number;
+  nIndividuals: number;
+}
+
+/** Summary statistics of phenopacket-store */
+export interface RepositoryStatistics {
+  nCohorts: number;
+  nIndividuals: number;
+  nDiseases: number;
+  nGenes: number;
+  nPmids: number;
+  nHpoTerms: number;
+  termsPerIndividual: TermsPerIndividual;
+  variantTypes: VariantTypeCounts;
+  sexDistribution: Record<string, number>;
+  onsetDistribution: Record<string, number>;
+  growth: GrowthPoint[];
+}
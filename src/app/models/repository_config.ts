@@ -0,0 +1,31 @@
+/** Where templates and phenopackets are found within each cohort directory of a repository */
+export interface RepositoryLayout {
+  inputSubdir: string;
+  /** Glob pattern for the template file names, e.g. "*_individuals.json" */
+  templateGlob: string;
+  phenopacketSubdir: string;
+  /** Directories in the base directory that are not cohorts */
+  ignoredFolders: string[];
+}
+
+/** A phenopacket repository registered in the settings */
+export interface RepositoryConfig {
+  name: string;
+  /** Directory with one subdirectory per cohort */
+  baseDir: string;
+  layout: RepositoryLayout;
+}
+
+export interface RegisteredRepositories {
+  repositories: RepositoryConfig[];
+  /** null if the repository is derived from the path of the current template */
+  activeRepository?: string | null;
+}
+
+/** The layout of phenopacket-store */
+export const DEFAULT_REPOSITORY_LAYOUT: RepositoryLayout = {
+  inputSubdir: 'input',
+  templateGlob: '*_individuals.json',
+  phenopacketSubdir: 'phenopackets',
+  ignoredFolders: [],
+};
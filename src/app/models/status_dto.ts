@@ -1,3 +1,5 @@
+import { ProjectSettings } from './project_settings';
+
 export interface StatusDto {
   hpoLoaded: boolean;
   hpoVersion: string;
@@ -5,9 +7,17 @@ export interface StatusDto {
   ptTemplatePath: string;
   ptTemplateLoaded: boolean;
   biocuratorOrcid: string;
+  /** Name of the active curator profile */
+  curatorName: string;
   hpoJsonPath: string;
   hasError: boolean;
   errorMessage: string;
+  /** Problems found while loading the settings file */
+  settingsWarnings: string[];
+  /** Problems of background tasks, e.g., the HPO cache could not be written */
+  backgroundWarnings: string[];
+  /** Settings of the repository (.phenoboard.toml) that override the user settings */
+  projectSettings?: ProjectSettings | null;
 }
 
 export function defaultStatusDto(): StatusDto {
@@ -18,9 +28,13 @@ export function defaultStatusDto(): StatusDto {
     ptTemplatePath: 'not initialized',
     ptTemplateLoaded: false,
     biocuratorOrcid: 'not initialized',
+    curatorName: '',
     hpoJsonPath: 'not initialized',
     hasError: false,
     errorMessage: '',
+    settingsWarnings: [],
+    backgroundWarnings: [],
+    projectSettings: null,
   };
 }
 
@@ -1,4 +1,5 @@
-import { HpoTermDuplet } from '../../../libs/ui/src/lib/models/hpo_term_dto';
+import { CohortData } from '../../../libs/ui/src/lib/models/cohort_dto';
+import { ClinicalStatus, HpoTermDuplet } from '../../../libs/ui/src/lib/models/hpo_term_dto';
 
 /** We use this scheme to transport fenominal hits from the backend to frontend and back again */
 export interface TextAnnotationDto {
@@ -12,6 +13,91 @@ export interface TextAnnotationDto {
   onsetString: string;
 }
 
+/** A fenominal hit located in the original text (offsets are UTF-16, i.e., usable with substring) */
+export interface HitSpan {
+  termId: string;
+  label: string;
+  matchedText: string;
+  start: number;
+  end: number;
+  negated: boolean;
+  sentenceIndex: number;
+}
+
+/** A fenominal hit that could not be mapped back onto the original text */
+export interface UnlocatedHit {
+  termId: string;
+  label: string;
+  matchedText: string;
+}
+
+/** The mined text with the positions of all hits and an HTML-safe rendering with highlighted hits */
+export interface HighlightedText {
+  originalText: string;
+  hits: HitSpan[];
+  html: string;
+  /** Hits whose words could not be found in the original text (not highlighted) */
+  unlocatedHits: UnlocatedHit[];
+}
+
+/** A mined term that was not added to the new row because a more specific (observed) or more general (excluded) term implies it */
+export interface RedundantTerm {
+  hpoId: string;
+  hpoLabel: string;
+  status: ClinicalStatus;
+  impliedById: string;
+  impliedByLabel: string;
+}
+
+/** A term that was mined with different statuses; the first status was used for the new row */
+export interface StatusConflict {
+  hpoId: string;
+  hpoLabel: string;
+  keptStatus: ClinicalStatus;
+  droppedStatus: ClinicalStatus;
+}
+
+/** Result of adding a row created from mined text to a cohort */
+export interface MinedRowResult {
+  cohortData: CohortData;
+  addedColumns: string[];
+  redundantTerms: RedundantTerm[];
+  statusConflicts: StatusConflict[];
+}
+
+/** Why a hit from batch mining should be reviewed by the curator */
+export type ReviewReason = 'shortMatch' | 'partialLabelMatch' | 'negated' | 'notLocated';
+
+export interface LowConfidenceHit {
+  fileName: string;
+  individualId: string;
+  termId: string;
+  label: string;
+  matchedText: string;
+  reason: ReviewReason;
+}
+
+export interface CaseReportSummary {
+  fileName: string;
+  individualId: string;
+  pmid: string;
+  /** Observed and excluded terms of the row that was added to the cohort */
+  nObserved: number;
+  nExcluded: number;
+  nRedundant: number;
+  statusConflicts: StatusConflict[];
+  /** Problems to fix in the draft cohort, e.g., a missing PMID */
+  warnings: string[];
+  error?: string | null;
+}
+
+/** Draft cohort created by mining a directory of case reports, with a review report */
+export interface BatchMiningReport {
+  cohortData: CohortData;
+  caseReports: CaseReportSummary[];
+  lowConfidenceHits: LowConfidenceHit[];
+}
+
 /** Mirrors Rust's `std::ops::Range<usize>`, which serde serializes as `{ start, end }`. */
 interface Span {
   start: number;
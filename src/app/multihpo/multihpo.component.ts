@@ -1,7 +1,7 @@
 import { Component, inject, signal, ElementRef, ViewChild, AfterViewInit, output, input } from '@angular/core';
 import { FormsModule, ReactiveFormsModule } from '@angular/forms';
 import { CommonModule } from '@angular/common';
-import { MiningConcept, MiningStatus, SplitDialogComponent } from '@workspace/ui';
+import { ClinicalStatus, MiningConcept, MiningStatus, SplitDialogComponent } from '@workspace/ui';
 import { ConfigService } from '../services/config.service';
 import { ClipboardModule } from '@angular/cdk/clipboard';
 import { IconComponent, OntologyAutocompleteProvider, OntologyMatch, OntologyAutocompleteComponent, NotificationService } from 'ng-hpo-uikit';
@@ -34,6 +34,7 @@ const NOT_APPLICABLE = new Set([
   ],
 })
 export class MultiHpoComponent implements AfterViewInit {
+  readonly Status = ClinicalStatus;
   readonly concepts = signal<MiningConcept[]>([]);
   private configService = inject(ConfigService);
   searchingIndices = new Set<number>();
@@ -85,6 +86,19 @@ export class MultiHpoComponent implements AfterViewInit {
     });
   }
 
+  /** A fragment such as "no seizures" describes an excluded feature */
+  toggleClinicalStatus(index: number) {
+    this.concepts.update((list) => {
+      const cloned = [...list];
+      const isExcluded = cloned[index].clinicalStatus === ClinicalStatus.Excluded;
+      cloned[index] = {
+        ...cloned[index],
+        clinicalStatus: isExcluded ? ClinicalStatus.Observed : ClinicalStatus.Excluded,
+      };
+      return cloned;
+    });
+  }
+
   removeConcept(index: number) {
     this.concepts.update((list) => {
       const cloned = [...list];
@@ -634,8 +634,81 @@ export class PtTemplateComponent {
       return;
     }
     try {
-      const message = await this.configService.exportHpoa(cohortDto);
-      this.notificationService.showSuccess(message);
+      const summary = await this.configService.exportHpoa(cohortDto);
+      this.notificationService.showSuccess(
+        `Wrote ${summary.files.length} HPOA file(s) with ${summary.nRows} annotations to ${summary.outDir}`,
+      );
+    } catch (err) {
+      this.notificationService.showError(String(err));
+    }
+  }
+
+  /** Export a ready-to-run bundle for GPSEA and PheVal */
+  async exportAnalysisBundle(): Promise<void> {
+    const cohortDto = this.cohortData();
+    if (!cohortDto) {
+      this.notificationService.showError('Cohort DTO not initialized');
+      return;
+    }
+    try {
+      const summary = await this.configService.exportAnalysisBundle(cohortDto);
+      this.notificationService.showSuccess(
+        `Wrote ${summary.nPhenopackets} phenopackets and ${summary.nVcfFiles} VCF files to ${summary.bundleDir}`,
+      );
+      if (summary.warnings.length > 0) {
+        this.notificationService.showWarning(summary.warnings.join('\n'));
+      }
+    } catch (err) {
+      this.notificationService.showError(String(err));
+    }
+  }
+
+  /** Export the cohort as a Phenopacket Cohort message and one Family message per family */
+  async exportPhenopacketCollections(): Promise<void> {
+    const cohortDto = this.cohortData();
+    if (!cohortDto) {
+      this.notificationService.showError('Cohort DTO not initialized');
+      return;
+    }
+    try {
+      const summary = await this.configService.exportPhenopacketCollections(cohortDto);
+      this.notificationService.showSuccess(
+        `Wrote cohort with ${summary.nMembers} members and ${summary.familyFiles.length} families to ${summary.outDir}`,
+      );
+      if (summary.warnings.length > 0) {
+        this.notificationService.showWarning(summary.warnings.join('\n'));
+      }
+    } catch (err) {
+      this.notificationService.showError(String(err));
+    }
+  }
+
+  /** Export the cohort as a flat Excel/TSV sheet (one row per individual) for clinicians */
+  async exportCohortSheet(): Promise<void> {
+    const cohortDto = this.cohortData();
+    if (!cohortDto) {
+      this.notificationService.showError('Cohort DTO not initialized');
+      return;
+    }
+    try {
+      const path = await this.configService.exportCohortSheet(cohortDto);
+      this.notificationService.showSuccess(`Exported cohort to ${path}`);
+    } catch (err) {
+      this.notificationService.showError(String(err));
+    }
+  }
+
+  /** Replace the rows of the cohort by the rows of an edited sheet */
+  async importCohortSheet(): Promise<void> {
+    const cohortDto = this.cohortData();
+    if (!cohortDto) {
+      this.notificationService.showError('Cohort DTO not initialized');
+      return;
+    }
+    try {
+      const imported = await this.configService.importCohortSheet(cohortDto);
+      this.cohortService.setCohortData(imported);
+      this.notificationService.showSuccess(`Imported ${imported.rows.length} individuals from sheet`);
     } catch (err) {
       this.notificationService.showError(String(err));
     }
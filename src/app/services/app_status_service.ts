@@ -20,6 +20,7 @@ export class AppStatusService {
   progress = signal<number>(0);
   hasUnsavedWork = signal(false);
   private readonly appWindow = getCurrentWindow();
+  private readonly shownWarnings = new Set<string>();
 
   constructor() {
     this.init();
@@ -29,11 +30,22 @@ export class AppStatusService {
   }
 
   private async init() {
+    await this.refreshAndShowWarnings();
+  }
+
+  /** Fetch the backend status and show the settings and background warnings that were not shown before */
+  async refreshAndShowWarnings(): Promise<void> {
     try {
       const status: StatusDto = await invoke('get_status_dto');
       this.state.set(status);
+      for (const warning of [...status.settingsWarnings, ...status.backgroundWarnings]) {
+        if (!this.shownWarnings.has(warning)) {
+          this.shownWarnings.add(warning);
+          this.notificationService.showWarning(warning);
+        }
+      }
     } catch (err) {
-      console.error('Failed to fetch initial backend status', err);
+      console.error('Failed to fetch backend status', err);
     }
   }
 
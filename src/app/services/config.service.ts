@@ -2,7 +2,14 @@ import { inject, Injectable } from '@angular/core';
 import { invoke } from '@tauri-apps/api/core';
 import { StatusDto } from '../models/status_dto';
 import { PmidDto } from '../models/pmid_dto';
-import { HpoAnnotationDto, ParentChildDto, TextAnnotationDto } from '../models/text_annotation_dto';
+import {
+  BatchMiningReport,
+  HighlightedText,
+  HpoAnnotationDto,
+  MinedRowResult,
+  ParentChildDto,
+  TextAnnotationDto,
+} from '../models/text_annotation_dto';
 import {
   IndividualData,
   CohortData,
@@ -11,7 +18,12 @@ import {
   HpoGroupMap,
   CurationEvent,
 } from '../../../libs/ui/src/lib/models/cohort_dto';
-import { HpoTermData, HpoTermDuplet } from '../../../libs/ui/src/lib/models/hpo_term_dto';
+import {
+  CellMappingResult,
+  HpoTermData,
+  HpoTermDuplet,
+  MappedTerm,
+} from '../../../libs/ui/src/lib/models/hpo_term_dto';
 import {
   HgvsVariant,
   IntergenicHgvsVariant,
@@ -19,7 +31,20 @@ import {
   VariantDto,
 } from '../../../libs/ui/src/lib/models/variant_dto';
 import { ColumnTableDto, EtlDto } from '@workspace/ui';
-import { RepoQc } from '../models/repo_qc';
+import { RepoQc, RepositoryPhenopacketQc } from '../models/repo_qc';
+import { CohortOverviewRow } from '../models/cohort_overview';
+import { BatchExportReport } from '../models/batch_export';
+import { RepositoryStatistics } from '../models/repo_statistics';
+import { CohortCommit, RepositoryGitStatus } from '../models/git_status';
+import { RegisteredRepositories, RepositoryConfig } from '../models/repository_config';
+import { ReleaseSummary } from '../models/release';
+import { CuratorProfile, CuratorProfiles } from '../models/curator_profile';
+import { RecentFile, RecentFileKind } from '../models/recent_files';
+import { HpoRelease, HpoReleaseSource, InstalledHpoRelease } from '../models/hpo_release';
+import { HpoaExportSummary, HpoaPreview } from '../models/hpoa';
+import { PhenopacketCollectionSummary } from '../models/phenopacket_collections';
+import { AnalysisBundleSummary } from '../models/analysis_bundle';
+import { RepoSearchQuery, RepoSearchResult } from '../models/repo_search';
 import { OntologyMatch, MinedCell, MiningConcept } from '@workspace/ui';
 import { ComparisonReport } from '../models/comparison';
 import { PpktSaveCheckResult } from '../models/status_dto';
@@ -139,18 +164,17 @@ export class ConfigService {
     return await invoke('emit_backend_status');
   }
 
-  async highlight_hpo_mining(input_text: string): Promise<string> {
-    return await invoke('highlight_text_with_hits', { inputText: input_text });
+  async highlight_hpo_mining(input_text: string): Promise<HighlightedText> {
+    return await invoke<HighlightedText>('highlight_text_with_hits', { inputText: input_text });
   }
 
   async retrieve_pmid_title(input_pmid: string): Promise<PmidDto> {
     return await invoke('fetch_pmid_title', { input: input_pmid });
   }
 
-  /*
-  async map_text_to_annotations(input_text: string):  Promise<TextAnnotationDto[] | string> {
-    return await invoke("map_text_to_annotations", {inputText: input_text});
-  }*/
+  async map_text_to_annotations(input_text: string): Promise<TextAnnotationDto[]> {
+    return await invoke<TextAnnotationDto[]>('map_text_to_annotations', { inputText: input_text });
+  }
   async mineClinicalText(text: string): Promise<FenominalSentence[]> {
     return await invoke<FenominalSentence[]>('mine_clinical_text', { text });
   }
@@ -280,8 +304,34 @@ export class ConfigService {
   }
 
 
-  async exportHpoa(cohort_dto: CohortData): Promise<string> {
-    return invoke<string>('export_hpoa', { cohortDto: cohort_dto });
+  /** Write one HPOA file per disease of the cohort (nothing is written if validation fails) */
+  async exportHpoa(cohort_dto: CohortData): Promise<HpoaExportSummary> {
+    return invoke<HpoaExportSummary>('export_hpoa', { cohortDto: cohort_dto });
+  }
+
+  /** Rows of the HPOA files that exportHpoa would write, with validation problems */
+  async previewHpoa(cohort_dto: CohortData): Promise<HpoaPreview> {
+    return invoke<HpoaPreview>('preview_hpoa', { cohortDto: cohort_dto });
+  }
+
+  /** Export phenopackets, a GPSEA configuration and a PheVal corpus (with VCF files) for downstream analyses */
+  async exportAnalysisBundle(cohortDto: CohortData): Promise<AnalysisBundleSummary> {
+    return invoke<AnalysisBundleSummary>('export_analysis_bundle', { cohortDto });
+  }
+
+  /** Export the cohort as a Phenopacket Cohort message and Family messages (families derived from individual ids) */
+  async exportPhenopacketCollections(cohortDto: CohortData): Promise<PhenopacketCollectionSummary> {
+    return invoke<PhenopacketCollectionSummary>('export_phenopacket_collections', { cohortDto });
+  }
+
+  /** Export the cohort as Excel workbook or TSV for collaborators; returns the path of the file */
+  async exportCohortSheet(cohortDto: CohortData): Promise<string> {
+    return invoke<string>('export_cohort_sheet', { cohortDto });
+  }
+
+  /** Read an edited Excel/TSV export back into the cohort (the rows of the sheet replace the current rows) */
+  async importCohortSheet(cohortDto: CohortData): Promise<CohortData> {
+    return invoke<CohortData>('import_cohort_sheet', { cohortDto });
   }
 
   /**
@@ -312,6 +362,36 @@ export class ConfigService {
     });
   }
 
+  /** Add a row created from confirmed text-mining hits; missing HPO columns are added by the backend */
+  async addMinedRowToCohort(
+    individualData: IndividualData,
+    minedTerms: MappedTerm[],
+    variantKeyList: string[],
+    cohortData: CohortData,
+  ): Promise<MinedRowResult> {
+    return invoke<MinedRowResult>('add_mined_row_to_cohort', {
+      individualData,
+      minedTerms,
+      variantKeyList,
+      cohortData,
+    });
+  }
+
+  /** Mine every case report in the directory and create a draft cohort with one row per file */
+  async mineCaseReportDirectory(
+    directory: string,
+    diseaseData: DiseaseData,
+    cohortType: CohortType,
+    acronym: string,
+  ): Promise<BatchMiningReport> {
+    return invoke<BatchMiningReport>('mine_case_report_directory', {
+      directory,
+      diseaseData,
+      cohortType,
+      acronym,
+    });
+  }
+
   /**
    * Validate variants in the back end and return the results for display.
    * @param variantList: Variants derived from the template in the frontend
@@ -337,6 +417,28 @@ export class ConfigService {
     return invoke<ColumnTableDto>('load_external_excel', { rowBased: true });
   }
 
+  /** Recently used files of one kind (or of all kinds if kind is null), most recent first */
+  async getRecentFiles(kind: RecentFileKind | null = null): Promise<RecentFile[]> {
+    return invoke<RecentFile[]>('get_recent_files', { kind });
+  }
+
+  async clearRecentFiles(): Promise<void> {
+    return invoke<void>('clear_recent_files');
+  }
+
+  /** Load a cohort template from the recent files without showing a file dialog */
+  async reopenRecentTemplate(path: string): Promise<CohortData> {
+    return invoke<CohortData>('reopen_recent_template', { path });
+  }
+
+  async reopenRecentExternalTemplate(path: string): Promise<EtlDto> {
+    return invoke<EtlDto>('reopen_recent_external_template', { path });
+  }
+
+  async reopenRecentExcel(path: string, rowBased: boolean): Promise<ColumnTableDto> {
+    return invoke<ColumnTableDto>('reopen_recent_excel', { path, rowBased });
+  }
+
   /**
    * When we start to extract the excel file, we get only the raw table (list of EtlColumnDto objects).
    * We additionally need the user to enter information (DiseaseData, pmid, title) that we need to be able to
@@ -361,6 +463,50 @@ export class ConfigService {
     });
   }
 
+  async getHpoReleaseSource(): Promise<HpoReleaseSource> {
+    return await invoke<HpoReleaseSource>('get_hpo_release_source');
+  }
+
+  async setHpoReleaseSource(source: HpoReleaseSource): Promise<void> {
+    return await invoke<void>('set_hpo_release_source', { source });
+  }
+
+  /** List the HPO releases available from the configured source */
+  async listHpoReleases(): Promise<HpoRelease[]> {
+    return await invoke<HpoRelease[]>('list_hpo_releases');
+  }
+
+  async getInstalledHpoReleases(): Promise<InstalledHpoRelease[]> {
+    return await invoke<InstalledHpoRelease[]>('get_installed_hpo_releases');
+  }
+
+  /** Download and verify an HPO release and make it the active ontology (emits progress-update in kB) */
+  async downloadHpoRelease(release: HpoRelease): Promise<StatusDto> {
+    return await invoke<StatusDto>('download_hpo_release', { release });
+  }
+
+  async activateHpoRelease(version: string): Promise<StatusDto> {
+    return await invoke<StatusDto>('activate_hpo_release', { version });
+  }
+
+  async getCuratorProfiles(): Promise<CuratorProfiles> {
+    return await invoke<CuratorProfiles>('get_curator_profiles');
+  }
+
+  /** Add a curator profile or replace the profile with the same name */
+  async saveCuratorProfile(profile: CuratorProfile): Promise<StatusDto> {
+    return await invoke<StatusDto>('save_curator_profile', { profile });
+  }
+
+  /** Select the curator whose ORCID is used for exports */
+  async selectCuratorProfile(name: string): Promise<StatusDto> {
+    return await invoke<StatusDto>('select_curator_profile', { name });
+  }
+
+  async removeCuratorProfile(name: string): Promise<StatusDto> {
+    return await invoke<StatusDto>('remove_curator_profile', { name });
+  }
+
   /** This is called by the initialize of the VariantList component to show the variants that have been validated or that still need validation */
   async getVariantAnalysis(cohort: CohortData): Promise<VariantDto[]> {
     return await invoke<VariantDto[]>('get_variant_analysis', {
@@ -390,17 +536,23 @@ export class ConfigService {
   async createCellMappings(
     miningResults: MiningConcept[],
     cellValues: string[],
-  ): Promise<MinedCell[]> {
-    return await invoke<MinedCell[]>('create_cell_mappings', {
+  ): Promise<CellMappingResult> {
+    return await invoke<CellMappingResult>('create_cell_mappings', {
       miningResults: miningResults,
       cellValues: cellValues,
     });
   }
 
-  async getMultiHpoStrings(minedCells: MinedCell[]): Promise<string[]> {
-    return await invoke<string[]>('get_multi_hpo_strings', { minedCells: minedCells });
+  /** One multi-HPO string per row; rows not covered by any cell get an empty string */
+  async getMultiHpoStrings(minedCells: MinedCell[], nRows?: number): Promise<string[]> {
+    return await invoke<string[]>('get_multi_hpo_strings', { minedCells: minedCells, nRows: nRows });
+  }
+  /** Parse multi-HPO strings (e.g., from an edited ETL file) back into mined cells */
+  async parseMultiHpoStrings(cellValues: string[]): Promise<MinedCell[]> {
+    return await invoke<MinedCell[]>('parse_multi_hpo_strings', { cellValues });
   }
 
+
   async transformToCohortData(etlDto: EtlDto): Promise<CohortData> {
     return await invoke<CohortData>('get_cohort_data_from_etl_dto', { dto: etlDto });
   }
@@ -447,6 +599,75 @@ export class ConfigService {
     return await invoke<RepoQc>('fetch_repo_qc');
   }
 
+  async getPhenopacketStoreOverview(): Promise<CohortOverviewRow[]> {
+    return await invoke<CohortOverviewRow[]>('get_ppkt_store_overview');
+  }
+
+  async searchRepository(query: RepoSearchQuery): Promise<RepoSearchResult> {
+    return await invoke<RepoSearchResult>('search_repository', { query });
+  }
+
+  /** Compare template rows and phenopackets of all cohorts; progress is reported with "progress-update" events */
+  async checkRepositoryPhenopackets(): Promise<RepositoryPhenopacketQc> {
+    return await invoke<RepositoryPhenopacketQc>('check_repository_phenopackets');
+  }
+
+  /** Delete orphaned phenopackets of a cohort; returns the names of the deleted files */
+  async removeOrphanedPhenopackets(cohortName: string, fileNames: string[]): Promise<string[]> {
+    return await invoke<string[]>('remove_orphaned_phenopackets', { cohortName, fileNames });
+  }
+
+  /** Create the release zip, manifest and summary in a directory chosen by the user (reports "progress-update") */
+  async createReleaseArchive(version: string): Promise<ReleaseSummary> {
+    return await invoke<ReleaseSummary>('create_release_archive', { version });
+  }
+
+  async getRepositories(): Promise<RegisteredRepositories> {
+    return await invoke<RegisteredRepositories>('get_repositories');
+  }
+
+  /** Register (or replace) a repository and select it */
+  async registerRepository(config: RepositoryConfig): Promise<RegisteredRepositories> {
+    return await invoke<RegisteredRepositories>('register_repository', { config });
+  }
+
+  /** Select a registered repository; null derives the repository from the template path */
+  async selectRepository(name: string | null): Promise<RegisteredRepositories> {
+    return await invoke<RegisteredRepositories>('select_repository', { name });
+  }
+
+  async removeRepository(name: string): Promise<RegisteredRepositories> {
+    return await invoke<RegisteredRepositories>('remove_repository', { name });
+  }
+
+  async getGitStatus(): Promise<RepositoryGitStatus> {
+    return await invoke<RepositoryGitStatus>('get_git_status');
+  }
+
+  /** Commit the changed files of a cohort locally; a message is generated if none is given */
+  async commitCohort(cohortName: string, message?: string): Promise<CohortCommit> {
+    return await invoke<CohortCommit>('commit_cohort', { cohortName, message: message ?? null });
+  }
+
+  async getRepositoryStatistics(): Promise<RepositoryStatistics> {
+    return await invoke<RepositoryStatistics>('get_repository_statistics');
+  }
+
+  /** Save the repository statistics as JSON or CSV (chosen by the file extension in the save dialog) */
+  async exportRepositoryStatistics(): Promise<void> {
+    return await invoke('export_repository_statistics');
+  }
+
+  /** Regenerate the phenopackets of all cohorts; progress is reported with "progress-update" events */
+  async batchExportPhenopackets(dryRun: boolean): Promise<BatchExportReport> {
+    return await invoke<BatchExportReport>('batch_export_phenopackets', { dryRun });
+  }
+
+  /** Start watching phenopacket-store; changes are reported with the "ppkt-store-changed" event */
+  async watchPhenopacketStore(): Promise<void> {
+    return await invoke('watch_ppkt_store');
+  }
+
   async sortCohortByrows(dto: CohortData) {
     return await invoke<CohortData>('sort_cohort_by_rows', { dto: dto });
   }
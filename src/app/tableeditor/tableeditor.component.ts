@@ -577,11 +577,17 @@ export class TableEditorComponent {
 
     if (!confirmedDictionary) return [];
 
-    const cellMappings = this.configService.createCellMappings(
+    const cellMappings = await this.configService.createCellMappings(
       confirmedDictionary,
       originalEntries,
     );
-    return cellMappings;
+    if (cellMappings.conflicts.length > 0) {
+      const terms = cellMappings.conflicts.map((c) => `${c.hpoLabel} (${c.cellText})`).join('; ');
+      this.notificationService.showWarning(
+        `Terms mapped as both observed and excluded (recorded as ${cellMappings.conflicts[0].resolvedStatus}): ${terms}`,
+      );
+    }
+    return cellMappings.minedCells;
   }
 
   isCellReviewOpen = signal(false);
@@ -611,7 +617,16 @@ export class TableEditorComponent {
     const col = dto.table.columns[colIndex];
     if (!col) return;
     if (!finalResults) return;
-    const rowMultiHpoStrings = await this.configService.getMultiHpoStrings(finalResults);
+    let rowMultiHpoStrings: string[];
+    try {
+      rowMultiHpoStrings = await this.configService.getMultiHpoStrings(
+        finalResults,
+        col.values.length,
+      );
+    } catch (err) {
+      this.notificationService.showError(`Could not store the mapped terms: ${String(err)}`);
+      return;
+    }
     const newColumns: ColumnDto[] = dto.table.columns.map((column, i) => {
       if (i !== colIndex) return column;
       const newValues: EtlCellValue[] = column.values.map((cell, rowIndex) => {